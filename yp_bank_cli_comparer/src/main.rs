@@ -1,14 +1,17 @@
 
-use std::io::{BufReader};
+use std::io::{BufRead, BufReader};
 
 use std::collections::HashMap;
 
 use std::fs::{File};
 
 use std::env;
+use yp_bank_parser_lib::parsers::compression::decompress;
+use yp_bank_parser_lib::parsers::detect::detect_format;
+use yp_bank_parser_lib::parsers::format::FormatRegistry;
 use yp_bank_parser_lib::parsers::parser::Parser;
 use yp_bank_parser_lib::parsers::types::{YPBankRecord};
-use yp_bank_parser_lib::{extract_format, parse_cli_args};
+use yp_bank_parser_lib::parse_cli_args;
 
 fn usage() {
     println!("Использование:");
@@ -16,6 +19,48 @@ fn usage() {
     println!("  --format1 <format>");
     println!("  --file2 <input_file>");
     println!("  --format2 <format>");
+    println!(
+        "Поддерживаемые форматы: {}",
+        FormatRegistry::read_global().names().join(", ")
+    );
+}
+
+/// Checks that the format is registered and supports reading.
+///
+/// # Panics
+///
+/// Panics if the format is unknown or cannot be read.
+fn check_format(format: &str) {
+    let registry = FormatRegistry::read_global();
+    match registry.get(format) {
+        Some(format_impl) if format_impl.capabilities().read => {}
+        Some(_) => panic!("Format {} does not support reading", format),
+        None => panic!("Unsupported format: {}", format),
+    }
+}
+
+/// Determines the format of a file from its content, falling back to the file extension.
+///
+/// # Panics
+///
+/// Panics if the file cannot be read or its format cannot be determined.
+fn detect_input_format(reader: &mut dyn BufRead, file_path: &str) -> String {
+    let detection = detect_format(reader).expect("Failed to read input file");
+    if let Some(detection) = detection
+        && detection.is_confident()
+    {
+        println!(
+            "Detected format of '{}': {} (confidence {:.2})",
+            file_path, detection.format, detection.confidence
+        );
+        return detection.format;
+    }
+
+    let registry = FormatRegistry::read_global();
+    match registry.format_for_path(file_path) {
+        Some(format) => format.name().to_string(),
+        None => panic!("Cannot detect format of '{}', use --format1/--format2", file_path),
+    }
 }
 
 fn main() {
@@ -34,56 +79,41 @@ fn main() {
         panic!("Both --file1 and --file2 arguments are required.");
     }
 
-    let mut format1 ;
     let file1_path = args_map.get("--file1").expect("Empty --input argument!");
     let fs1 = File::open(file1_path).expect("Failed to open input file1");
-    let reader1 = BufReader::new(fs1);
+    let mut reader1 = decompress(BufReader::new(fs1)).expect("Failed to decompress input file1");
 
-    format1 = extract_format(file1_path);
+    let format1 = match args_map.get("--format1") {
+        Some(format) => format.to_string(),
+        None => detect_input_format(&mut reader1, file1_path),
+    };
 
-    if args_map.contains_key("--format1") {
-        format1 = args_map
-            .get("--format1")
-            .expect("Empty --format1 argument!")
-            .to_string();
-    }
-
-    let mut format2;
     let file2_path = args_map.get("--file2").expect("Empty --input argument!");
     let fs2 = File::open(file2_path).expect("Failed to open input file2");
-    let reader2 = BufReader::new(fs2);
-
-    format2 = extract_format(file2_path);
-
-    if args_map.contains_key("--format2") {
-        format2 = args_map
-            .get("--format2")
-            .expect("Empty --format2 argument!")
-            .to_string();
-    }
+    let mut reader2 = decompress(BufReader::new(fs2)).expect("Failed to decompress input file2");
 
-    let records1 =
-        Parser::from_read(reader1, &format1).expect("Failed to parse records from file1");
+    let format2 = match args_map.get("--format2") {
+        Some(format) => format.to_string(),
+        None => detect_input_format(&mut reader2, file2_path),
+    };
 
-    let records2 =
-        Parser::from_read(reader2, &format2).expect("Failed to parse records from file2");
+    check_format(&format1);
+    check_format(&format2);
 
-    let hashes1: HashMap<u64, &YPBankRecord> = records1
-        .iter()
-        .map(|record| (record.tx_id, record))
-        .collect();
-
-    let hashes2: HashMap<u64, &YPBankRecord> = records2
-        .iter()
-        .map(|record| (record.tx_id, record))
-        .collect();
+    // Only file1 is held in memory; file2 is streamed against it and matched records are dropped
+    let mut hashes1: HashMap<u64, YPBankRecord> = Parser::reader(reader1, &format1)
+        .expect("Failed to parse records from file1")
+        .map(|record| record.map(|record| (record.tx_id, record)))
+        .collect::<Result<_, _>>()
+        .expect("Failed to parse records from file1");
 
     let mut diffs = 0;
-    for (tx_id, record1) in &hashes1 {
-        match hashes2.get(tx_id) {
-            Some(record2) => {
+    for record2 in Parser::reader(reader2, &format2).expect("Failed to parse records from file2") {
+        let record2 = record2.expect("Failed to parse records from file2");
+        match hashes1.remove(&record2.tx_id) {
+            Some(record1) => {
                 if record1 != record2 {
-                    println!("Difference found for TX_ID {}:", tx_id);
+                    println!("Difference found for TX_ID {}:", record2.tx_id);
                     println!("  File1: {:?}", record1);
                     println!("  File2: {:?}", record2);
                     diffs += 1;
@@ -92,13 +122,23 @@ fn main() {
             None => {
                 println!(
                     "Record with TX_ID {} found in '{}' but not in '{}'",
-                    tx_id, file1_path, file2_path
+                    record2.tx_id, file2_path, file1_path
                 );
                 diffs += 1;
             }
         }
     }
 
+    let mut missing: Vec<u64> = hashes1.into_keys().collect();
+    missing.sort_unstable();
+    for tx_id in missing {
+        println!(
+            "Record with TX_ID {} found in '{}' but not in '{}'",
+            tx_id, file1_path, file2_path
+        );
+        diffs += 1;
+    }
+
     if diffs == 0 {
         println!(
             "The transaction records in '{}' and '{}' are identical.",
@@ -110,6 +150,7 @@ fn main() {
 }
 
 #[cfg(test)]
+#[allow(clippy::redundant_pattern_matching)]
 mod tests {
     use super::*;
     use std::io::Cursor;
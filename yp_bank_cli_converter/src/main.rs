@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::io;
 use std::io::{BufRead, BufReader, BufWriter, Write};
 
@@ -5,8 +6,15 @@ use std::fs::File;
 
 use std::env;
 
+use yp_bank_parser_lib::parsers::bin_format::{BinVersion, BinWriterOptions, YPBankBinParser};
+use yp_bank_parser_lib::parsers::compression::{Compression, compress, decompress};
+use yp_bank_parser_lib::parsers::detect::detect_format;
 use yp_bank_parser_lib::parsers::error::ParserError;
+use yp_bank_parser_lib::parsers::format::FormatRegistry;
 use yp_bank_parser_lib::parsers::parser::Parser;
+use yp_bank_parser_lib::parsers::profile::ImportProfile;
+use yp_bank_parser_lib::parsers::types::YPBankRecord;
+use yp_bank_parser_lib::parsers::writer::RecordWriter;
 use yp_bank_parser_lib::{extract_format, parse_cli_args};
 
 fn usage() {
@@ -15,6 +23,103 @@ fn usage() {
     println!("  --input-format <format>");
     println!("  --output <output_file>");
     println!("  --output-format <format>");
+    println!("  --max-errors <count>  (пропускать до <count> некорректных записей)");
+    println!("  --profile <profile.toml>  (профиль импорта CSV-выписки другого банка)");
+    println!("  --bin-version <1|2>  (версия выходного *.bin файла, по умолчанию 1)");
+    println!("  --bin-checksums <true|false>  (CRC32 каждой записи *.bin, требует версии 2)");
+    println!(
+        "Поддерживаемые форматы: {}",
+        FormatRegistry::read_global().names().join(", ")
+    );
+}
+
+/// Checks that the format is registered and supports reading or writing.
+///
+/// # Panics
+///
+/// Panics if the format is unknown or does not support the requested operation.
+fn check_format(format: &str, write: bool) {
+    let registry = FormatRegistry::read_global();
+    let capabilities = match registry.get(format) {
+        Some(format_impl) => format_impl.capabilities(),
+        None => panic!("Unsupported format: {}", format),
+    };
+
+    if write && !capabilities.write {
+        panic!("Format {} does not support writing", format);
+    }
+    if !write && !capabilities.read {
+        panic!("Format {} does not support reading", format);
+    }
+}
+
+/// Builds the binary writer options selected on the command line.
+///
+/// Checksums without an explicit `--bin-version` select version 2.
+///
+/// # Returns
+///
+/// Returns None when no binary output option is given, so the built-in
+/// "bin" format keeps writing version 1 files.
+///
+/// # Panics
+///
+/// Panics if `--bin-version` is not a supported version number, if
+/// `--bin-checksums` is not a boolean, or if checksums are requested for version 1.
+fn bin_writer_options(args_map: &HashMap<String, String>) -> Option<BinWriterOptions> {
+    let version = args_map.get("--bin-version").map(|version| {
+        version
+            .parse::<u16>()
+            .ok()
+            .and_then(BinVersion::from_number)
+            .expect("Invalid --bin-version argument!")
+    });
+    let checksums = args_map.get("--bin-checksums").map(|checksums| {
+        checksums
+            .parse::<bool>()
+            .expect("Invalid --bin-checksums argument!")
+    });
+    if version.is_none() && checksums.is_none() {
+        return None;
+    }
+
+    let checksums = checksums.unwrap_or(false);
+    let version = version.unwrap_or(if checksums { BinVersion::V2 } else { BinVersion::V1 });
+    if checksums && version == BinVersion::V1 {
+        panic!("--bin-checksums requires --bin-version 2");
+    }
+    Some(BinWriterOptions {
+        version,
+        checksums,
+        ..BinWriterOptions::default()
+    })
+}
+
+/// Determines the input format from its content, falling back to the file extension.
+///
+/// # Panics
+///
+/// Panics if the input cannot be read or its format cannot be determined.
+fn detect_input_format(reader: &mut dyn BufRead, file_path: Option<&str>) -> String {
+    let detection = detect_format(reader).expect("Failed to read input");
+    if let Some(detection) = detection
+        && detection.is_confident()
+    {
+        println!(
+            "Detected input format: {} (confidence {:.2})",
+            detection.format, detection.confidence
+        );
+        return detection.format;
+    }
+
+    let registry = FormatRegistry::read_global();
+    if let Some(file_path) = file_path
+        && let Some(format) = registry.format_for_path(file_path)
+    {
+        return format.name().to_string();
+    }
+
+    panic!("Cannot detect input format, use --input-format");
 }
 
 fn main() {
@@ -29,43 +134,70 @@ fn main() {
 
     let args_map = parse_cli_args(
         &args,
-        &["--input", "--input-format", "--output", "--output-format"],
+        &[
+            "--input",
+            "--input-format",
+            "--output",
+            "--output-format",
+            "--max-errors",
+            "--profile",
+            "--bin-version",
+            "--bin-checksums",
+        ],
     );
 
-    let mut input_format = "csv".to_string();
-    let reader: Box<dyn BufRead> = if args_map.contains_key("--input") {
-        let file_path = args_map.get("--input").expect("Empty --input argument!");
-
-        input_format = extract_format(file_path);
+    // The configured binary format replaces the built-in "bin" writer
+    if let Some(options) = bin_writer_options(&args_map) {
+        FormatRegistry::write_global().register(YPBankBinParser::with_options(options));
+    }
 
+    // A profile is registered as a format under its own name
+    let profile_name = args_map.get("--profile").map(|path| {
+        let profile = ImportProfile::load(path).expect("Failed to load import profile");
+        let name = profile.name.clone();
+        FormatRegistry::write_global().register(profile);
+        name
+    });
+
+    let max_errors = args_map.get("--max-errors").map(|value| {
+        value
+            .parse::<usize>()
+            .expect("Invalid --max-errors argument!")
+    });
+
+    let input_path = args_map.get("--input");
+    let reader: Box<dyn BufRead> = if let Some(file_path) = input_path {
         println!("Reading from file: {}", file_path);
         let fs = File::open(file_path).expect("Failed to open input file");
         Box::new(BufReader::new(fs))
     } else {
         Box::new(io::stdin().lock())
     };
+    // Compressed input is detected by its magic bytes
+    let mut reader = decompress(reader).expect("Failed to decompress input");
 
-    if args_map.contains_key("--input-format") {
-        input_format = args_map
-            .get("--input-format")
-            .expect("Empty --input-format argument!")
-            .to_string();
-    }
+    let input_format = match (args_map.get("--input-format"), profile_name) {
+        (Some(format), _) => format.to_string(),
+        (None, Some(name)) => name,
+        (None, None) => detect_input_format(&mut reader, input_path.map(String::as_str)),
+    };
 
     println!("Input format: {}", input_format);
+    check_format(&input_format, false);
 
     let mut output_format = "csv".to_string();
-    let writer: Box<dyn Write> = if args_map.contains_key("--output") {
+    let (writer, compression): (Box<dyn Write>, Compression) = if args_map.contains_key("--output") {
         let file_path = args_map.get("--output").expect("Empty --output argument!");
 
         output_format = extract_format(file_path);
 
         println!("Writing to file: {}", file_path);
         let fs = File::create(file_path).expect("Failed to open output file");
-        Box::new(BufWriter::new(fs))
+        (Box::new(BufWriter::new(fs)), Compression::from_path(file_path))
     } else {
-        Box::new(io::stdout().lock())
+        (Box::new(io::stdout().lock()), Compression::None)
     };
+    let mut writer = compress(writer, compression).expect("Failed to compress output");
 
     if args_map.contains_key("--output-format") {
         output_format = args_map
@@ -75,25 +207,74 @@ fn main() {
     }
 
     println!("Output format: {}", output_format);
+    check_format(&output_format, true);
 
-    let records = match Parser::from_read(reader, &input_format) {
+    let records = match Parser::reader(reader, &input_format) {
         Ok(records) => records,
         Err(err) => {
             panic!("Error parsing input: {:?}", err);
         }
     };
 
-    let write_result = Parser::write_to(writer, &records, &output_format);
-    if let Err(ParserError::ParseError(e)) = write_result {
+    let mut record_writer = match Parser::writer(&mut writer, &output_format) {
+        Ok(record_writer) => record_writer,
+        Err(err) => {
+            panic!("Write to output error: {}", err);
+        }
+    };
+
+    let result = match max_errors {
+        None => write_records(records, record_writer.as_mut()),
+        Some(max_errors) => {
+            let mut lenient = records.lenient(max_errors);
+            let result = write_records(lenient.by_ref(), record_writer.as_mut());
+            for reject in lenient.rejects() {
+                eprintln!(
+                    "Rejected record at {}: [{}] {}\n  {}",
+                    reject.location,
+                    reject.error.code(),
+                    reject.error.kind(),
+                    reject.raw_text()
+                );
+            }
+            eprintln!("Rejected records: {}", lenient.rejects().len());
+            result
+        }
+    };
+
+    if let Err(e) = result {
+        panic!("Conversion error: {}", e);
+    }
+
+    if let Err(e) = record_writer.finish() {
+        panic!("Write to output error: {}", e);
+    }
+    drop(record_writer);
+
+    // Writes the end of a compressed stream, which may still fail
+    if let Err(e) = writer.finish() {
         panic!("Write to output error: {}", e);
     }
 }
 
+/// Writes every record produced by the iterator, stopping at the first error.
+fn write_records<I>(records: I, record_writer: &mut dyn RecordWriter) -> Result<(), ParserError>
+where
+    I: Iterator<Item = Result<YPBankRecord, ParserError>>,
+{
+    for record in records {
+        record_writer.write_record(&record?)?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs;
     use std::io::BufReader;
+    use yp_bank_parser_lib::parsers::bin_format::BinRecordReader;
+    use yp_bank_parser_lib::parsers::format::Format;
     use yp_bank_parser_lib::test_helpers::{create_test_records};    
 
     fn create_temp_file(suffix: &str) -> String {
@@ -169,6 +350,61 @@ mod tests {
         cleanup_file(&output_file);
     }
 
+    #[test]
+    fn test_bin_writer_options_from_args() {
+        assert_eq!(bin_writer_options(&HashMap::new()), None);
+
+        let args_map = HashMap::from([("--bin-version".to_string(), "2".to_string())]);
+        let options = bin_writer_options(&args_map).unwrap();
+        assert_eq!(options.version, BinVersion::V2);
+
+        let format = YPBankBinParser::with_options(options);
+        let mut output = Vec::new();
+        let mut record_writer = format.writer(Box::new(&mut output)).unwrap();
+        record_writer.write_record(&create_test_records(1, 100)[0]).unwrap();
+        record_writer.finish().unwrap();
+        drop(record_writer);
+        let mut reader = BinRecordReader::new(output.as_slice());
+        assert!(reader.next().unwrap().is_ok());
+        assert_eq!(reader.header().map(|header| header.version), Some(BinVersion::V2));
+    }
+
+    #[test]
+    fn test_bin_writer_options_with_checksums() {
+        let args_map = HashMap::from([("--bin-checksums".to_string(), "true".to_string())]);
+        let options = bin_writer_options(&args_map).unwrap();
+        assert_eq!(options.version, BinVersion::V2);
+        assert!(options.checksums);
+
+        let records = create_test_records(2, 100);
+        let format = YPBankBinParser::with_options(options);
+        let mut output = Vec::new();
+        let mut record_writer = format.writer(Box::new(&mut output)).unwrap();
+        for record in &records {
+            record_writer.write_record(record).unwrap();
+        }
+        record_writer.finish().unwrap();
+        drop(record_writer);
+        assert_eq!(Parser::from_read(output.as_slice(), "bin").unwrap(), records);
+    }
+
+    #[test]
+    #[should_panic(expected = "--bin-checksums requires --bin-version 2")]
+    fn test_bin_writer_options_reject_checksums_for_v1() {
+        let args_map = HashMap::from([
+            ("--bin-version".to_string(), "1".to_string()),
+            ("--bin-checksums".to_string(), "true".to_string()),
+        ]);
+        bin_writer_options(&args_map);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid --bin-version argument!")]
+    fn test_bin_writer_options_reject_unknown_version() {
+        let args_map = HashMap::from([("--bin-version".to_string(), "3".to_string())]);
+        bin_writer_options(&args_map);
+    }
+
     #[test]
     fn test_bin_file_read_write() {
         let input_file = create_temp_file("input.bin");
@@ -244,6 +480,30 @@ mod tests {
         cleanup_file(&bin_file);
     }
 
+    #[test]
+    fn test_compressed_file_read_write() {
+        let gz_file = create_temp_file("export.csv.gz");
+        let zst_file = create_temp_file("records.bin.zst");
+
+        let records = create_test_records(5, 300);
+
+        for file_path in [&gz_file, &zst_file] {
+            let file = try_test!(File::create(file_path), cleanup_file(&gz_file), cleanup_file(&zst_file));
+            let mut writer = try_test!(compress(BufWriter::new(file), Compression::from_path(file_path)), cleanup_file(&gz_file), cleanup_file(&zst_file));
+            try_test!(Parser::write_to(&mut writer, &records, &extract_format(file_path)), cleanup_file(&gz_file), cleanup_file(&zst_file));
+            try_test!(writer.finish(), cleanup_file(&gz_file), cleanup_file(&zst_file));
+
+            let file = try_test!(File::open(file_path), cleanup_file(&gz_file), cleanup_file(&zst_file));
+            let mut reader = try_test!(decompress(BufReader::new(file)), cleanup_file(&gz_file), cleanup_file(&zst_file));
+            assert_eq!(detect_input_format(&mut reader, Some(file_path)), extract_format(file_path));
+            let parsed_records = try_test!(Parser::from_read(reader, &extract_format(file_path)), cleanup_file(&gz_file), cleanup_file(&zst_file));
+            assert_eq!(parsed_records, records);
+        }
+
+        cleanup_file(&gz_file);
+        cleanup_file(&zst_file);
+    }
+
     #[test]
     fn test_large_file_processing() {
         let input_file = create_temp_file("large.csv");
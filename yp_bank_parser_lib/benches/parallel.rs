@@ -0,0 +1,53 @@
+//! Compares single-threaded, parallel and zero-copy parallel parsing of the
+//! `create_test_records` fixtures.
+//!
+//! cargo bench -p yp_bank_parser_lib --bench parallel
+
+use std::hint::black_box;
+use std::io::Cursor;
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+
+use yp_bank_parser_lib::parsers::bin_format::YPBankBinParser;
+use yp_bank_parser_lib::parsers::csv_format::YPBankCsvParser;
+use yp_bank_parser_lib::test_helpers::create_test_records;
+
+/// Number of generated records, about 5 MiB of CSV and 6 MiB of BIN.
+const RECORD_COUNT: usize = 100_000;
+
+fn parse_benchmarks(c: &mut Criterion) {
+    let records = create_test_records(RECORD_COUNT, 1);
+    let mut csv = Vec::new();
+    YPBankCsvParser::write_to(&mut csv, &records).unwrap();
+    let mut bin = Vec::new();
+    YPBankBinParser::write_to(&mut bin, &records).unwrap();
+
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(20);
+
+    group.throughput(Throughput::Bytes(csv.len() as u64));
+    group.bench_function(BenchmarkId::new("csv", "from_read"), |b| {
+        b.iter(|| YPBankCsvParser::from_read(Cursor::new(black_box(&csv))).unwrap())
+    });
+    group.bench_function(BenchmarkId::new("csv", "from_read_parallel"), |b| {
+        b.iter(|| YPBankCsvParser::from_read_parallel(Cursor::new(black_box(&csv))).unwrap())
+    });
+    group.bench_function(BenchmarkId::new("csv", "parse_bytes_parallel"), |b| {
+        b.iter(|| YPBankCsvParser::parse_bytes_parallel(black_box(&csv)).unwrap())
+    });
+
+    group.throughput(Throughput::Bytes(bin.len() as u64));
+    group.bench_function(BenchmarkId::new("bin", "from_read"), |b| {
+        b.iter(|| YPBankBinParser::from_read(Cursor::new(black_box(&bin))).unwrap())
+    });
+    group.bench_function(BenchmarkId::new("bin", "from_read_parallel"), |b| {
+        b.iter(|| YPBankBinParser::from_read_parallel(Cursor::new(black_box(&bin))).unwrap())
+    });
+    group.bench_function(BenchmarkId::new("bin", "parse_bytes_parallel"), |b| {
+        b.iter(|| YPBankBinParser::parse_bytes_parallel(black_box(&bin)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse_benchmarks);
+criterion_main!(benches);
@@ -0,0 +1,55 @@
+//! Compares single-threaded and parallel parsing of generated CSV and BIN data.
+//!
+//! cargo run --release -p yp_bank_parser_lib --example parallel_parse -- 1000000
+
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+use yp_bank_parser_lib::parsers::bin_format::YPBankBinParser;
+use yp_bank_parser_lib::parsers::csv_format::YPBankCsvParser;
+use yp_bank_parser_lib::parsers::error::ParserError;
+use yp_bank_parser_lib::parsers::parallel::ParallelOptions;
+use yp_bank_parser_lib::parsers::types::YPBankRecord;
+use yp_bank_parser_lib::test_helpers::create_test_records;
+
+/// Runs `parse` and returns its records and the time it took.
+fn timed(parse: impl FnOnce() -> Result<Vec<YPBankRecord>, ParserError>) -> (Vec<YPBankRecord>, Duration) {
+    let start = Instant::now();
+    let records = parse().expect("generated data must parse");
+    (records, start.elapsed())
+}
+
+fn main() {
+    let count = std::env::args()
+        .nth(1)
+        .map(|count| count.parse().expect("record count must be a number"))
+        .unwrap_or(500_000);
+    let records = create_test_records(count, 1);
+    println!("{} records, {} threads", count, ParallelOptions::default().threads);
+
+    let mut csv = Vec::new();
+    YPBankCsvParser::write_to(&mut csv, &records).unwrap();
+    let mut bin = Vec::new();
+    YPBankBinParser::write_to(&mut bin, &records).unwrap();
+
+    for (format, data) in [("csv", &csv), ("bin", &bin)] {
+        let (serial, serial_time) = timed(|| match format {
+            "csv" => YPBankCsvParser::from_read(Cursor::new(data)),
+            _ => YPBankBinParser::from_read(Cursor::new(data)),
+        });
+        let (parallel, parallel_time) = timed(|| match format {
+            "csv" => YPBankCsvParser::from_read_parallel(Cursor::new(data)),
+            _ => YPBankBinParser::from_read_parallel(Cursor::new(data)),
+        });
+        assert_eq!(serial, parallel);
+
+        println!(
+            "{}: {} MiB, from_read {:?}, from_read_parallel {:?}, speedup {:.2}x",
+            format,
+            data.len() / (1024 * 1024),
+            serial_time,
+            parallel_time,
+            serial_time.as_secs_f64() / parallel_time.as_secs_f64()
+        );
+    }
+}
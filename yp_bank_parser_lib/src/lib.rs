@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use crate::parsers::format::FormatRegistry;
+
 pub mod parsers;
 
 /// Magic bytes identifying a YPBankBin record header: "YPBN"
@@ -8,6 +10,15 @@ pub const MAGIC: [u8; 4] = [0x59, 0x50, 0x42, 0x4E];
 /// Header size in bytes (MAGIC + RECORD_SIZE)
 pub const HEADER_SIZE: usize = 8;
 
+/// Magic bytes identifying a YPBankBin v2 file header: "YPBF"
+pub const FILE_MAGIC: [u8; 4] = [0x59, 0x50, 0x42, 0x46];
+
+/// Size in bytes of the fixed part of a v2 file header, up to and including the producer length
+pub const FILE_HEADER_SIZE: usize = 30;
+
+/// Record header size in bytes of v2 files (MAGIC + RECORD_SIZE + RECORD_FLAGS)
+pub const HEADER_SIZE_V2: usize = 9;
+
 /// Minimum body size in bytes (fixed fields without description)
 pub const MIN_BODY_SIZE: usize = 46;
 
@@ -59,6 +70,9 @@ pub mod test_helpers {
 
 /// Extracts the file format from a file path based on its extension.
 ///
+/// The extension is looked up in the process-wide `FormatRegistry`, so formats
+/// registered by downstream crates are recognised as well.
+///
 /// # Arguments
 ///
 /// * `file_path` - A string slice containing the file path
@@ -69,6 +83,7 @@ pub mod test_helpers {
 /// - "csv" for .csv files
 /// - "txt" for .txt files  
 /// - "bin" for .bin files
+/// - the name of any registered format matching the extension
 /// - "csv" as default for unknown extensions
 ///
 /// # Examples
@@ -79,20 +94,11 @@ pub mod test_helpers {
 /// assert_eq!(extract_format("data.csv"), "csv");
 /// assert_eq!(extract_format("data.txt"), "txt");
 /// assert_eq!(extract_format("data.bin"), "bin");
+/// assert_eq!(extract_format("data.bin.zst"), "bin"); // compression extension ignored
 /// assert_eq!(extract_format("data"), "csv"); // default
 /// ```
 pub fn extract_format(file_path: &str) -> String {
-    let split_path: Vec<&str> = file_path.split(".").collect();
-    if split_path.len() > 1 
-       && let Some(ext) = split_path.last() {
-        match *ext {
-            "csv" => return "csv".to_string(),
-            "txt" => return "txt".to_string(),
-            "bin" => return "bin".to_string(),
-            _ => return "csv".to_string(),
-        }
-    }    
-    "csv".to_string()
+    FormatRegistry::read_global().extract_format(file_path)
 }
 
 /// Parses command line arguments into a HashMap.
@@ -286,6 +292,89 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_record_reader_streams_all_formats() {
+        let records = create_test_records(12, 3000);
+
+        for format in ["csv", "txt", "bin"] {
+            let mut output = Vec::new();
+            try_test!(Parser::write_to(&mut output, &records, format));
+
+            let mut reader = try_test!(Parser::reader(Cursor::new(output), format));
+            for original in &records {
+                let parsed = try_test!(reader.next().expect("record expected"));
+                assert_eq!(original, &parsed);
+            }
+            assert!(reader.next().is_none());
+        }
+    }
+
+    #[test]
+    fn test_record_reader_stops_after_invalid_header() {
+        let csv_data = "INVALID,HEADER\n123,Deposit,456,789,1000,1640995200,Success,Test\n";
+        let mut reader = try_test!(Parser::reader(Cursor::new(csv_data), "csv"));
+
+        assert!(matches!(reader.next(), Some(Err(_))));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_record_reader_unsupported_format() {
+        let result = Parser::reader(Cursor::new("some data"), "xml");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_record_writer_matches_write_to() {
+        let records = create_test_records(7, 4000);
+
+        for format in ["csv", "txt", "bin"] {
+            let mut expected = Vec::new();
+            try_test!(Parser::write_to(&mut expected, &records, format));
+
+            let mut output = Vec::new();
+            {
+                let mut writer = try_test!(Parser::writer(&mut output, format));
+                for record in &records {
+                    try_test!(writer.write_record(record));
+                }
+                try_test!(writer.finish());
+            }
+
+            assert_eq!(expected, output);
+        }
+    }
+
+    #[test]
+    fn test_txt_record_writer_numbers_sections() {
+        let records = create_test_records(3, 4100);
+
+        let mut output = Vec::new();
+        {
+            let mut writer = try_test!(Parser::writer(&mut output, "txt"));
+            for record in &records {
+                try_test!(writer.write_record(record));
+            }
+            try_test!(writer.finish());
+        }
+
+        let output_str = try_test!(String::from_utf8(output));
+        assert!(output_str.starts_with("# Record 0 ("));
+        assert!(output_str.contains("\n\n# Record 1 ("));
+        assert!(output_str.contains("\n\n# Record 2 ("));
+        assert!(!output_str.ends_with("\n\n"));
+    }
+
+    #[test]
+    fn test_csv_record_writer_without_records() {
+        let mut output = Vec::new();
+        {
+            let mut writer = try_test!(Parser::writer(&mut output, "csv"));
+            assert!(writer.finish().is_err());
+        }
+        assert!(output.is_empty());
+    }
+
     #[test]
     fn test_csv_read_invalid_header() {
         let csv_data = "INVALID,HEADER\n123,Deposit";
@@ -345,4 +434,73 @@ mod tests {
         assert_eq!(dict.get("--file2"), None);
         assert_eq!(dict.get("--file10"), None);
     }
+
+    #[test]
+    fn test_txt_description_escaping() {
+        let mut records = create_test_records(1, 5);
+        records[0].description = " Memo\n# not a separator\\ ".to_string();
+
+        let mut output = Vec::new();
+        Parser::write_to(&mut output, &records, "txt").unwrap();
+        let text = String::from_utf8(output.clone()).unwrap();
+        assert!(text.contains("description: \" Memo\\n# not a separator\\\\ \"\n"));
+        assert_eq!(Parser::from_read(Cursor::new(output), "txt").unwrap(), records);
+    }
+
+    #[test]
+    fn test_txt_reads_descriptions_written_before_escaping() {
+        // Written by the text writer before descriptions were quoted and escaped
+        let text = "# Record 0 (Deposit)\n\
+                    tx_id: 1\ntx_type: Deposit\nfrom_user_id: 0\nto_user_id: 2\n\
+                    amount: 100\ntimestamp: 5\nstatus: Success\n\
+                    description: C:\\new\\table\n\
+                    \n\
+                    # Record 1 (Deposit)\n\
+                    tx_id: 2\ntx_type: Deposit\nfrom_user_id: 0\nto_user_id: 2\n\
+                    amount: 100\ntimestamp: 5\nstatus: Success\n\
+                    description: \"Premium\"\n";
+        let records = Parser::from_read(Cursor::new(text), "txt").unwrap();
+        assert_eq!(records[0].description, "C:\\new\\table");
+        assert_eq!(records[1].description, "\"Premium\"");
+
+        let mut output = Vec::new();
+        Parser::write_to(&mut output, &records, "txt").unwrap();
+        assert!(String::from_utf8(output.clone()).unwrap().contains("description: C:\\new\\table\n"));
+        assert_eq!(Parser::from_read(Cursor::new(output), "txt").unwrap(), records);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_description_roundtrip_all_formats(
+            description in proptest::prelude::any::<String>(),
+            seed in 0u64..1000,
+        ) {
+            let mut records = create_test_records(3, seed);
+            records[1].description = description;
+
+            let registry = crate::parsers::format::FormatRegistry::default();
+            for format in registry.names() {
+                let mut output = Vec::new();
+                Parser::write_to(&mut output, &records, &format).unwrap();
+                let parsed = Parser::from_read(Cursor::new(output), &format).unwrap();
+                proptest::prop_assert_eq!(&parsed, &records, "format {}", format);
+            }
+        }
+
+        #[test]
+        fn test_description_roundtrip_bin_txt_bin(
+            description in "[ \t\r\n\\#:,\"a-zя\u{a0}\u{2028}]{0,40}",
+        ) {
+            let mut records = create_test_records(2, 1);
+            records[0].description = description;
+
+            let mut bin = Vec::new();
+            Parser::write_to(&mut bin, &records, "bin").unwrap();
+            let mut txt = Vec::new();
+            Parser::write_to(&mut txt, &Parser::from_read(Cursor::new(bin), "bin").unwrap(), "txt").unwrap();
+            let mut bin = Vec::new();
+            Parser::write_to(&mut bin, &Parser::from_read(Cursor::new(txt), "txt").unwrap(), "bin").unwrap();
+            proptest::prop_assert_eq!(Parser::from_read(Cursor::new(bin), "bin").unwrap(), records);
+        }
+    }
 }
@@ -0,0 +1,643 @@
+use std::future::poll_fn;
+use std::io::{BufRead, Read};
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::parsers::bin_format::{BinRecordReader, BinRecordWriter, BinVersion, MAX_FILE_HEADER_SIZE, parse_frame_header};
+use crate::parsers::csv_dialect::CsvDialect;
+use crate::parsers::csv_format::{CsvRecordReader, CsvRecordWriter, MAX_QUOTED_BYTES, MAX_QUOTED_LINES};
+use crate::parsers::error::ParserError;
+use crate::parsers::reader::{RecordSource, SourceLocation};
+use crate::parsers::txt_format::{TxtRecordReader, TxtRecordWriter};
+use crate::parsers::types::YPBankRecord;
+use crate::parsers::writer::RecordWriter;
+use crate::{FILE_HEADER_SIZE, FILE_MAGIC};
+
+/// Number of bytes requested from the async input per read.
+const READ_CHUNK_SIZE: usize = 8 * 1024;
+
+/// Amount of encoded output collected before it is written to the async output.
+const WRITE_BUFFER_SIZE: usize = 8 * 1024;
+
+/// Input bytes received from an async source, read by a synchronous record reader.
+///
+/// Reading past the buffered bytes reports end of input, so a record reader
+/// is only called once the buffer holds everything its next record needs,
+/// or once the source itself has ended.
+struct InputBuffer {
+    data: Vec<u8>,
+    pos: usize,
+}
+
+impl InputBuffer {
+    fn pending(&self) -> &[u8] {
+        &self.data[self.pos..]
+    }
+
+    /// Drops the bytes already consumed by the record reader.
+    fn compact(&mut self) {
+        self.data.drain(..self.pos);
+        self.pos = 0;
+    }
+}
+
+impl Read for InputBuffer {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.pending().read(buf)?;
+        self.pos += read;
+        Ok(read)
+    }
+}
+
+impl BufRead for InputBuffer {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        Ok(self.pending())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos += amt;
+    }
+}
+
+/// Counts the complete CSV records at the start of `data`, stopping at `wanted`.
+///
+/// A record ends at a line feed outside quotes, as in `read_record_text`. A
+/// quote left open past the limits of `read_record_text` makes the reader
+/// report the record as unterminated, so it counts as complete too.
+fn complete_csv_records(data: &[u8], quote: char, wanted: usize) -> usize {
+    let mut quote_bytes = [0u8; 4];
+    let quote = quote.encode_utf8(&mut quote_bytes).as_bytes();
+    let mut quoted = false;
+    let mut found = 0;
+    let (mut record_start, mut record_lines) = (0, 0);
+    let mut i = 0;
+    while i < data.len() && found < wanted {
+        if data[i..].starts_with(quote) {
+            quoted = !quoted;
+            i += quote.len();
+            continue;
+        }
+        if data[i] == b'\n' {
+            record_lines += 1;
+            if !quoted {
+                found += 1;
+                (record_start, record_lines) = (i + 1, 0);
+            } else if record_lines >= MAX_QUOTED_LINES || i + 1 - record_start >= MAX_QUOTED_BYTES {
+                return wanted;
+            }
+        }
+        i += 1;
+    }
+    found
+}
+
+/// Returns whether `data` holds a complete text section, closed by a '#' line.
+///
+/// A line that is not valid UTF-8 also counts, since the text reader stops at it.
+fn has_complete_txt_section(data: &[u8]) -> bool {
+    let Some(end) = data.iter().rposition(|&b| b == b'\n') else {
+        return false;
+    };
+
+    let mut has_keys = false;
+    for line in data[..end].split(|&b| b == b'\n') {
+        let Ok(line) = std::str::from_utf8(line) else {
+            return true;
+        };
+        if line.starts_with('#') {
+            if has_keys {
+                return true;
+            }
+        } else if !line.trim().is_empty() {
+            has_keys = true;
+        }
+    }
+    false
+}
+
+/// Returns how many bytes at the start of `data` the next binary record needs.
+///
+/// Includes the file header while the layout `version` is not known yet. A
+/// header or frame header that the binary reader rejects needs no more bytes.
+fn bin_bytes_needed(data: &[u8], version: Option<BinVersion>) -> usize {
+    let (version, frame_start) = match version {
+        Some(version) => (version, 0),
+        None if data.len() < FILE_MAGIC.len() => return FILE_MAGIC.len(),
+        None if data[..FILE_MAGIC.len()] != FILE_MAGIC => (BinVersion::V1, 0),
+        None => {
+            let Some(fixed) = data.get(..FILE_HEADER_SIZE) else {
+                return FILE_HEADER_SIZE;
+            };
+            let header_size = u32::from_be_bytes([fixed[8], fixed[9], fixed[10], fixed[11]]) as usize;
+            if fixed[4..6] != BinVersion::V2.number().to_be_bytes()
+                || !(FILE_HEADER_SIZE..=MAX_FILE_HEADER_SIZE).contains(&header_size)
+            {
+                return FILE_HEADER_SIZE;
+            }
+            (BinVersion::V2, header_size)
+        }
+    };
+
+    let frame_header_end = frame_start + version.frame_header_size();
+    let Some(frame_header) = data.get(frame_start..frame_header_end) else {
+        return frame_header_end;
+    };
+    match parse_frame_header(frame_header, version) {
+        Ok((_, frame_size)) => frame_start + frame_size,
+        Err(_) => frame_header_end,
+    }
+}
+
+/// Synchronous record reader of one of the formats supported asynchronously.
+enum Decoder {
+    Csv {
+        reader: CsvRecordReader<InputBuffer>,
+        quote: char,
+        has_header: bool,
+    },
+    Txt(TxtRecordReader<InputBuffer>),
+    Bin(BinRecordReader<InputBuffer>),
+}
+
+impl Decoder {
+    fn input(&mut self) -> &mut InputBuffer {
+        match self {
+            Decoder::Csv { reader, .. } => reader.get_mut(),
+            Decoder::Txt(reader) => reader.get_mut(),
+            Decoder::Bin(reader) => reader.get_mut(),
+        }
+    }
+
+    fn source(&mut self) -> &mut dyn RecordSource {
+        match self {
+            Decoder::Csv { reader, .. } => reader,
+            Decoder::Txt(reader) => reader,
+            Decoder::Bin(reader) => reader,
+        }
+    }
+
+    fn source_ref(&self) -> &dyn RecordSource {
+        match self {
+            Decoder::Csv { reader, .. } => reader,
+            Decoder::Txt(reader) => reader,
+            Decoder::Bin(reader) => reader,
+        }
+    }
+
+    /// Returns whether the buffered input holds everything the next record needs.
+    ///
+    /// # Arguments
+    ///
+    /// * `started` - Whether a record was requested before, so that a CSV header has been read
+    fn has_complete_record(&mut self, started: bool) -> bool {
+        match self {
+            Decoder::Csv {
+                reader,
+                quote,
+                has_header,
+            } => {
+                let wanted = if *has_header && !started { 2 } else { 1 };
+                let replayed: String = reader.replayed().collect();
+                if replayed.is_empty() {
+                    complete_csv_records(reader.get_mut().pending(), *quote, wanted) == wanted
+                } else {
+                    let data = [replayed.as_bytes(), reader.get_mut().pending()].concat();
+                    complete_csv_records(&data, *quote, wanted) == wanted
+                }
+            }
+            Decoder::Txt(reader) => has_complete_txt_section(reader.get_mut().pending()),
+            Decoder::Bin(reader) => {
+                let version = reader.version();
+                let pending = reader.get_mut().pending();
+                pending.len() >= bin_bytes_needed(pending, version)
+            }
+        }
+    }
+}
+
+/// Stream of YPBank records read from a tokio `AsyncRead` source.
+///
+/// Input is read into a buffer until it holds a complete record (a CSV line
+/// outside quotes, a text section or a binary frame), which is then decoded
+/// by the synchronous reader of the format. Records, locations, warnings and
+/// errors are therefore exactly those of `Parser::reader`.
+///
+/// Records are available through the `Stream` implementation or `next_record`.
+pub struct AsyncRecordReader<R> {
+    input: R,
+    decoder: Decoder,
+    started: bool,
+    eof: bool,
+    done: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRecordReader<R> {
+    /// Creates an async record reader for the specified format.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - A source implementing the AsyncRead trait
+    /// * `format` - "csv", "txt" or "bin"
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::UnsupportedFormat for any other format.
+    pub fn new(input: R, format: &str) -> Result<Self, ParserError> {
+        let buffer = InputBuffer {
+            data: Vec::new(),
+            pos: 0,
+        };
+        let decoder = match format.to_ascii_lowercase().as_str() {
+            "csv" => return Ok(Self::csv_with_dialect(input, CsvDialect::default())),
+            "txt" => Decoder::Txt(TxtRecordReader::new(buffer)),
+            "bin" => Decoder::Bin(BinRecordReader::new(buffer)),
+            _ => return Err(ParserError::UnsupportedFormat(format.to_string())),
+        };
+        Ok(Self::with_decoder(input, decoder))
+    }
+
+    /// Creates an async CSV record reader for the given dialect.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - A source implementing the AsyncRead trait
+    /// * `dialect` - The CSV variant of the input
+    pub fn csv_with_dialect(input: R, dialect: CsvDialect) -> Self {
+        let buffer = InputBuffer {
+            data: Vec::new(),
+            pos: 0,
+        };
+        let decoder = Decoder::Csv {
+            quote: dialect.quote,
+            has_header: dialect.has_header,
+            reader: CsvRecordReader::with_dialect(buffer, dialect),
+        };
+        Self::with_decoder(input, decoder)
+    }
+
+    fn with_decoder(input: R, decoder: Decoder) -> Self {
+        AsyncRecordReader {
+            input,
+            decoder,
+            started: false,
+            eof: false,
+            done: false,
+        }
+    }
+
+    /// Location of the record most recently returned.
+    pub fn location(&self) -> SourceLocation {
+        self.decoder.source_ref().location()
+    }
+
+    /// Located, non-fatal problems found in the input read so far.
+    pub fn warnings(&self) -> &[ParserError] {
+        self.decoder.source_ref().warnings()
+    }
+
+    /// Returns the next record, or None at end of input.
+    pub async fn next_record(&mut self) -> Option<Result<YPBankRecord, ParserError>> {
+        poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+
+    /// Reads all remaining records, stopping at the first error.
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing a Vec<YPBankRecord>, or the first ParserError, like `Parser::from_read`.
+    pub async fn collect_records(mut self) -> Result<Vec<YPBankRecord>, ParserError> {
+        let mut records = Vec::new();
+        while let Some(record) = self.next_record().await {
+            records.push(record?);
+        }
+        Ok(records)
+    }
+
+    /// Appends the next chunk of the async input to the buffer.
+    fn poll_fill(&mut self, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        let buffer = self.decoder.input();
+        buffer.compact();
+        let start = buffer.data.len();
+        buffer.data.resize(start + READ_CHUNK_SIZE, 0);
+
+        let mut read_buf = ReadBuf::new(&mut buffer.data[start..]);
+        let result = Pin::new(&mut self.input).poll_read(cx, &mut read_buf);
+        let filled = read_buf.filled().len();
+        buffer.data.truncate(start + filled);
+
+        ready!(result)?;
+        if filled == 0 {
+            self.eof = true;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for AsyncRecordReader<R> {
+    type Item = Result<YPBankRecord, ParserError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        while !this.eof && !this.decoder.has_complete_record(this.started) {
+            if let Err(e) = ready!(this.poll_fill(cx)) {
+                this.done = true;
+                return Poll::Ready(Some(Err(ParserError::Io(e))));
+            }
+        }
+
+        this.started = true;
+        let source = this.decoder.source();
+        let item = source.next();
+        if item.as_ref().is_none_or(|record| record.is_err() && !source.can_resume()) {
+            this.done = true;
+        }
+        Poll::Ready(item)
+    }
+}
+
+/// Synchronous record writer of one of the formats supported asynchronously.
+enum Encoder {
+    Csv(CsvRecordWriter<Vec<u8>>),
+    Txt(TxtRecordWriter<Vec<u8>>),
+    Bin(BinRecordWriter<Vec<u8>>),
+}
+
+impl Encoder {
+    fn output(&mut self) -> &mut Vec<u8> {
+        match self {
+            Encoder::Csv(writer) => writer.get_mut(),
+            Encoder::Txt(writer) => writer.get_mut(),
+            Encoder::Bin(writer) => writer.get_mut(),
+        }
+    }
+
+    fn writer(&mut self) -> &mut dyn RecordWriter {
+        match self {
+            Encoder::Csv(writer) => writer,
+            Encoder::Txt(writer) => writer,
+            Encoder::Bin(writer) => writer,
+        }
+    }
+}
+
+/// Sink writing YPBank records to a tokio `AsyncWrite` output.
+///
+/// Records are encoded by the synchronous writer of the format into a buffer
+/// that is written out once it grows past 8 KiB, so the output is byte for
+/// byte that of `Parser::writer`. `finish` (or closing the `Sink`) must be
+/// called after the last record.
+pub struct AsyncRecordWriter<W> {
+    output: W,
+    encoder: Encoder,
+    written: usize,
+    finished: bool,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncRecordWriter<W> {
+    /// Creates an async record writer for the specified format.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - An output implementing the AsyncWrite trait
+    /// * `format` - "csv", "txt" or "bin"
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::UnsupportedFormat for any other format.
+    pub fn new(output: W, format: &str) -> Result<Self, ParserError> {
+        let encoder = match format.to_ascii_lowercase().as_str() {
+            "csv" => Encoder::Csv(CsvRecordWriter::new(Vec::new())),
+            "txt" => Encoder::Txt(TxtRecordWriter::new(Vec::new())),
+            "bin" => Encoder::Bin(BinRecordWriter::new(Vec::new())),
+            _ => return Err(ParserError::UnsupportedFormat(format.to_string())),
+        };
+        Ok(AsyncRecordWriter {
+            output,
+            encoder,
+            written: 0,
+            finished: false,
+        })
+    }
+
+    /// Writes a single record.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - The YPBankRecord to write
+    pub async fn write_record(&mut self, record: &YPBankRecord) -> Result<(), ParserError> {
+        poll_fn(|cx| self.poll_reserve(cx)).await?;
+        self.encoder.writer().write_record(record)
+    }
+
+    /// Completes the output and flushes the async writer, without shutting it down.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `RecordWriter::finish` for the format, such as ParserError::NoRecords.
+    pub async fn finish(&mut self) -> Result<(), ParserError> {
+        self.finish_encoder()?;
+        poll_fn(|cx| self.poll_write_all(cx)).await?;
+        poll_fn(|cx| Pin::new(&mut self.output).poll_flush(cx)).await?;
+        Ok(())
+    }
+
+    /// Returns the async output.
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+
+    fn finish_encoder(&mut self) -> Result<(), ParserError> {
+        if !self.finished {
+            self.finished = true;
+            self.encoder.writer().finish()?;
+        }
+        Ok(())
+    }
+
+    /// Writes out the buffer if it is full.
+    fn poll_reserve(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), ParserError>> {
+        if self.encoder.output().len() >= WRITE_BUFFER_SIZE {
+            ready!(self.poll_write_all(cx))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Writes out the whole buffer.
+    fn poll_write_all(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), ParserError>> {
+        let buffer = self.encoder.output();
+        while self.written < buffer.len() {
+            let written = ready!(Pin::new(&mut self.output).poll_write(cx, &buffer[self.written..]))?;
+            if written == 0 {
+                return Poll::Ready(Err(ParserError::Io(std::io::ErrorKind::WriteZero.into())));
+            }
+            self.written += written;
+        }
+        buffer.clear();
+        self.written = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> Sink<YPBankRecord> for AsyncRecordWriter<W> {
+    type Error = ParserError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), ParserError>> {
+        self.get_mut().poll_reserve(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, record: YPBankRecord) -> Result<(), ParserError> {
+        self.get_mut().encoder.writer().write_record(&record)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), ParserError>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_all(cx))?;
+        Poll::Ready(ready!(Pin::new(&mut this.output).poll_flush(cx)).map_err(ParserError::Io))
+    }
+
+    /// Completes the output like `finish` and shuts the async writer down.
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), ParserError>> {
+        let this = self.get_mut();
+        this.finish_encoder()?;
+        ready!(this.poll_write_all(cx))?;
+        Poll::Ready(ready!(Pin::new(&mut this.output).poll_shutdown(cx)).map_err(ParserError::Io))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::parser::Parser;
+    use crate::test_helpers::create_test_records;
+    use std::io::Cursor;
+
+    /// Async source returning at most `chunk` bytes per read, to split records across reads.
+    struct Trickle {
+        data: Vec<u8>,
+        pos: usize,
+        chunk: usize,
+    }
+
+    impl AsyncRead for Trickle {
+        fn poll_read(mut self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+            let end = (self.pos + self.chunk).min(self.data.len()).min(self.pos + buf.remaining());
+            buf.put_slice(&self.data[self.pos..end]);
+            self.pos = end;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn trickle(data: &[u8], chunk: usize) -> Trickle {
+        Trickle {
+            data: data.to_vec(),
+            pos: 0,
+            chunk,
+        }
+    }
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[tokio::test]
+    async fn test_async_roundtrip_all_formats() {
+        let mut records = create_test_records(30, 2400);
+        records[3].description = "multi\nline, \"quoted\"\r\n# not a section".to_string();
+
+        for format in ["csv", "txt", "bin"] {
+            let mut writer = AsyncRecordWriter::new(Vec::new(), format).unwrap();
+            assert_send(&writer);
+            for record in &records {
+                writer.write_record(record).await.unwrap();
+            }
+            writer.finish().await.unwrap();
+            let output = writer.into_inner();
+
+            let mut expected = Vec::new();
+            Parser::write_to(&mut expected, &records, format).unwrap();
+            assert_eq!(output, expected, "{}", format);
+
+            for chunk in [1, 7, 4096] {
+                let reader = AsyncRecordReader::new(trickle(&output, chunk), format).unwrap();
+                assert_send(&reader);
+                assert_eq!(reader.collect_records().await.unwrap(), records, "{} by {}", format, chunk);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_errors_match_sync_reader() {
+        let records = create_test_records(5, 2400);
+        for format in ["csv", "txt", "bin"] {
+            let mut input = Vec::new();
+            Parser::write_to(&mut input, &records, format).unwrap();
+            // Damage the middle of the input
+            let middle = input.len() / 2;
+            input[middle..middle + 32].fill(b'@');
+
+            let sync: Vec<_> = Parser::reader(Cursor::new(input.clone()), format).unwrap().collect();
+            let mut reader = AsyncRecordReader::new(trickle(&input, 3), format).unwrap();
+            let mut asynchronous = Vec::new();
+            while let Some(record) = reader.next_record().await {
+                asynchronous.push(record);
+            }
+
+            assert!(sync.iter().any(Result::is_err), "{}", format);
+            assert_eq!(sync.len(), asynchronous.len(), "{}", format);
+            for (sync, asynchronous) in sync.iter().zip(&asynchronous) {
+                match (sync, asynchronous) {
+                    (Ok(sync), Ok(asynchronous)) => assert_eq!(sync, asynchronous),
+                    (Err(sync), Err(asynchronous)) => {
+                        assert_eq!(sync.code(), asynchronous.code(), "{}", format);
+                        assert_eq!(sync.location(), asynchronous.location(), "{}", format);
+                    }
+                    _ => panic!("{}: {:?} != {:?}", format, sync, asynchronous),
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_unterminated_quote_matches_sync_reader() {
+        let records = create_test_records(MAX_QUOTED_LINES + 5, 2400);
+        let mut input = Vec::new();
+        Parser::write_to(&mut input, &records, "csv").unwrap();
+        // Open a quote in the first record that nothing closes
+        let first_record = input.iter().position(|&b| b == b'\n').unwrap() + 1;
+        input.insert(first_record, b'"');
+
+        let sync: Vec<_> = Parser::reader(Cursor::new(input.clone()), "csv").unwrap().collect();
+        let mut reader = AsyncRecordReader::new(trickle(&input, 4096), "csv").unwrap();
+        let mut asynchronous = Vec::new();
+        while let Some(record) = reader.next_record().await {
+            asynchronous.push(record);
+        }
+
+        assert_eq!(sync.len(), records.len());
+        assert_eq!(sync.iter().filter(|record| record.is_err()).count(), 1);
+        assert_eq!(sync.len(), asynchronous.len());
+        for (sync, asynchronous) in sync.iter().zip(&asynchronous) {
+            match (sync, asynchronous) {
+                (Ok(sync), Ok(asynchronous)) => assert_eq!(sync, asynchronous),
+                (Err(sync), Err(asynchronous)) => assert_eq!(sync.location(), asynchronous.location()),
+                _ => panic!("{:?} != {:?}", sync, asynchronous),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_unsupported_format_and_empty_writer() {
+        assert_eq!(
+            AsyncRecordReader::new(&b""[..], "json").err().unwrap().code(),
+            "unsupported_format"
+        );
+        let mut writer = AsyncRecordWriter::new(Vec::new(), "bin").unwrap();
+        assert_eq!(writer.finish().await.unwrap_err().code(), "no_records");
+    }
+}
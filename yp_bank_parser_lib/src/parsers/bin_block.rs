@@ -0,0 +1,875 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{BufRead, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+
+use crate::parsers::bin_format::BinRecordWriter;
+use crate::parsers::bin_slice::BinSliceReader;
+use crate::parsers::compression::{Compression, compress, decoder};
+use crate::parsers::error::ParserError;
+use crate::parsers::format::{Format, FormatCapabilities};
+use crate::parsers::reader::{RecordReader, RecordSource, SourceLocation};
+use crate::parsers::types::YPBankRecord;
+use crate::parsers::writer::RecordWriter;
+
+/// Magic bytes identifying a YPBank block container: "YPBC"
+pub const BLOCK_MAGIC: [u8; 4] = [0x59, 0x50, 0x42, 0x43];
+
+/// Layout version of the block container.
+const BLOCK_VERSION: u16 = 1;
+
+/// Default maximum number of records per block.
+pub const DEFAULT_BLOCK_RECORDS: u32 = 65536;
+
+/// Size of the container header: magic, version, codec, reserved byte and records per block.
+const CONTAINER_HEADER_SIZE: u64 = 12;
+
+/// Size of the header preceding each block: record count, compressed and uncompressed size, CRC32.
+const BLOCK_HEADER_SIZE: u64 = 16;
+
+/// Size of the end-of-blocks marker, a block header with a record count of 0.
+const END_MARKER_SIZE: u64 = 4;
+
+/// Size of one entry of the block index.
+const BLOCK_ENTRY_SIZE: u64 = 48;
+
+/// Size of the footer: index offset and magic.
+const FOOTER_SIZE: u64 = 12;
+
+/// Position and summary of one block of a container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockInfo {
+    /// Byte offset of the block header.
+    pub offset: u64,
+    /// Index of the first record of the block in the container.
+    pub first_record: u64,
+    /// Number of records in the block.
+    pub record_count: u32,
+    /// Size of the compressed block payload.
+    pub compressed_size: u32,
+    /// Size of the block once decompressed.
+    pub uncompressed_size: u32,
+    /// CRC32 of the decompressed block.
+    pub checksum: u32,
+    /// Smallest `tx_id` in the block.
+    pub min_tx_id: u64,
+    /// Largest `tx_id` in the block.
+    pub max_tx_id: u64,
+}
+
+impl BlockInfo {
+    /// Returns the indices of the records of the block.
+    pub fn records(&self) -> Range<u64> {
+        self.first_record..self.first_record + self.record_count as u64
+    }
+
+    /// Returns whether the block may contain a record with `tx_id`.
+    pub fn may_contain(&self, tx_id: u64) -> bool {
+        (self.min_tx_id..=self.max_tx_id).contains(&tx_id)
+    }
+
+    fn location(&self) -> SourceLocation {
+        SourceLocation {
+            byte_offset: Some(self.offset),
+            record_index: Some(self.first_record),
+            ..SourceLocation::default()
+        }
+    }
+
+    fn write_entry<W: Write>(&self, writer: &mut W) -> Result<(), ParserError> {
+        writer.write_u64::<BigEndian>(self.offset)?;
+        writer.write_u64::<BigEndian>(self.first_record)?;
+        writer.write_u32::<BigEndian>(self.record_count)?;
+        writer.write_u32::<BigEndian>(self.compressed_size)?;
+        writer.write_u32::<BigEndian>(self.uncompressed_size)?;
+        writer.write_u32::<BigEndian>(self.checksum)?;
+        writer.write_u64::<BigEndian>(self.min_tx_id)?;
+        writer.write_u64::<BigEndian>(self.max_tx_id)?;
+        Ok(())
+    }
+
+    fn read_entry<R: Read>(reader: &mut R) -> Result<Self, ParserError> {
+        Ok(BlockInfo {
+            offset: reader.read_u64::<BigEndian>()?,
+            first_record: reader.read_u64::<BigEndian>()?,
+            record_count: reader.read_u32::<BigEndian>()?,
+            compressed_size: reader.read_u32::<BigEndian>()?,
+            uncompressed_size: reader.read_u32::<BigEndian>()?,
+            checksum: reader.read_u32::<BigEndian>()?,
+            min_tx_id: reader.read_u64::<BigEndian>()?,
+            max_tx_id: reader.read_u64::<BigEndian>()?,
+        })
+    }
+}
+
+fn codec_id(compression: Compression) -> u8 {
+    match compression {
+        Compression::None => 0,
+        Compression::Gzip => 1,
+        Compression::Zstd => 2,
+    }
+}
+
+/// Reads the container header.
+///
+/// # Returns
+///
+/// Returns the compression of the blocks and the maximum number of records per block.
+fn read_container_header<R: Read>(reader: &mut R) -> Result<(Compression, u32), ParserError> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != BLOCK_MAGIC {
+        return Err(ParserError::InvalidFileHeader(format!("bad container magic {:02X?}", magic)));
+    }
+    let version = reader.read_u16::<BigEndian>()?;
+    if version != BLOCK_VERSION {
+        return Err(ParserError::UnsupportedBinVersion(version));
+    }
+    let compression = match reader.read_u8()? {
+        0 => Compression::None,
+        1 => Compression::Gzip,
+        2 => Compression::Zstd,
+        other => {
+            return Err(ParserError::InvalidFileHeader(format!("unknown block compression {}", other)));
+        }
+    };
+    let _reserved = reader.read_u8()?;
+    let block_records = reader.read_u32::<BigEndian>()?;
+    if block_records == 0 {
+        return Err(ParserError::InvalidFileHeader("block size of 0 records".to_string()));
+    }
+    Ok((compression, block_records))
+}
+
+/// Reads the compressed payload of a block.
+fn read_payload<R: Read>(reader: R, block: &BlockInfo) -> Result<Vec<u8>, ParserError> {
+    let mut payload = Vec::new();
+    reader.take(block.compressed_size as u64).read_to_end(&mut payload)?;
+    if payload.len() != block.compressed_size as usize {
+        return Err(ParserError::UnexpectedEof {
+            expected: block.compressed_size as usize,
+            actual: payload.len(),
+        }
+        .at(block.location()));
+    }
+    Ok(payload)
+}
+
+/// Moves the location of an error inside a decompressed block to the block in the container.
+fn relocate(error: ParserError, block: &BlockInfo) -> ParserError {
+    let record_index = error
+        .location()
+        .and_then(|location| location.record_index)
+        .map(|index| block.first_record + index);
+    let error = match error {
+        ParserError::Located { source, .. } => *source,
+        other => other,
+    };
+    error.at(SourceLocation {
+        byte_offset: Some(block.offset),
+        record_index,
+        ..SourceLocation::default()
+    })
+}
+
+/// Decompresses a block payload, checks it and decodes its record frames.
+///
+/// # Errors
+///
+/// Returns ParserError::ChecksumMismatch if the decompressed block does not
+/// match its size and CRC32, RecordCountMismatch if it holds a different
+/// number of records, and the errors of `BinSliceReader` for bad frames.
+fn decode_block(payload: &[u8], block: &BlockInfo, compression: Compression) -> Result<Vec<YPBankRecord>, ParserError> {
+    let mut raw = Vec::new();
+    // One byte more than declared is enough to notice an oversized block
+    decoder(payload, compression)?
+        .take(block.uncompressed_size as u64 + 1)
+        .read_to_end(&mut raw)
+        .map_err(|e| ParserError::from(e).at(block.location()))?;
+    if raw.len() != block.uncompressed_size as usize || crc32fast::hash(&raw) != block.checksum {
+        return Err(ParserError::ChecksumMismatch {
+            record_index: block.first_record,
+            offset: block.offset,
+        }
+        .at(block.location()));
+    }
+
+    let mut records = Vec::new();
+    for record in BinSliceReader::new(&raw).map_err(|e| relocate(e, block))? {
+        records.push(record.map_err(|e| relocate(e, block))?.to_owned());
+    }
+    if records.len() != block.record_count as usize {
+        return Err(ParserError::RecordCountMismatch {
+            expected: block.record_count as u64,
+            actual: records.len() as u64,
+        }
+        .at(block.location()));
+    }
+    Ok(records)
+}
+
+/// Options of a `BlockBinWriter`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockWriterOptions {
+    /// Compression applied to each block.
+    pub compression: Compression,
+    /// Maximum number of records per block.
+    pub block_records: u32,
+}
+
+impl Default for BlockWriterOptions {
+    /// Uses zstd, or gzip, whichever is enabled first, and `DEFAULT_BLOCK_RECORDS`.
+    fn default() -> Self {
+        let compression = if cfg!(feature = "zstd") {
+            Compression::Zstd
+        } else if cfg!(feature = "gzip") {
+            Compression::Gzip
+        } else {
+            Compression::None
+        };
+        BlockWriterOptions {
+            compression,
+            block_records: DEFAULT_BLOCK_RECORDS,
+        }
+    }
+}
+
+/// Incremental writer of a block-compressed binary container.
+///
+/// Records are encoded as version 1 binary frames and collected into blocks
+/// of up to `block_records` records, each compressed independently. `finish`
+/// appends a block index, so that `SeekableBlockReader` can reach any block
+/// without decompressing the others.
+///
+/// Layout (big-endian): `BLOCK_MAGIC`, version (u16), codec (u8), reserved
+/// (u8), records per block (u32); each block as record count (u32), compressed
+/// size (u32), uncompressed size (u32), CRC32 of the uncompressed block (u32)
+/// and the compressed frames; a record count of 0 ending the blocks; the block
+/// count (u32) and one `BlockInfo` entry per block; the index offset (u64) and
+/// `BLOCK_MAGIC` again.
+pub struct BlockBinWriter<W> {
+    writer: W,
+    options: BlockWriterOptions,
+    frames: BinRecordWriter<Vec<u8>>,
+    block: BlockInfo,
+    blocks: Vec<BlockInfo>,
+    position: u64,
+    records_written: u64,
+}
+
+impl<W: Write> BlockBinWriter<W> {
+    /// Creates a block writer with the default options.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - A writer implementing the Write trait
+    pub fn new(writer: W) -> Self {
+        Self::with_options(writer, BlockWriterOptions::default())
+    }
+
+    /// Creates a block writer with the given options.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - A writer implementing the Write trait
+    /// * `options` - Compression and block size
+    pub fn with_options(writer: W, options: BlockWriterOptions) -> Self {
+        BlockBinWriter {
+            writer,
+            options,
+            frames: BinRecordWriter::new(Vec::new()),
+            block: Self::empty_block(0),
+            blocks: Vec::new(),
+            position: 0,
+            records_written: 0,
+        }
+    }
+
+    fn empty_block(first_record: u64) -> BlockInfo {
+        BlockInfo {
+            offset: 0,
+            first_record,
+            record_count: 0,
+            compressed_size: 0,
+            uncompressed_size: 0,
+            checksum: 0,
+            min_tx_id: u64::MAX,
+            max_tx_id: 0,
+        }
+    }
+
+    fn write_container_header(&mut self) -> Result<(), ParserError> {
+        if self.options.block_records == 0 {
+            return Err(ParserError::UnsupportedFormat("block size of 0 records".to_string()));
+        }
+        self.writer.write_all(&BLOCK_MAGIC)?;
+        self.writer.write_u16::<BigEndian>(BLOCK_VERSION)?;
+        self.writer.write_u8(codec_id(self.options.compression))?;
+        self.writer.write_u8(0)?;
+        self.writer.write_u32::<BigEndian>(self.options.block_records)?;
+        self.position = CONTAINER_HEADER_SIZE;
+        Ok(())
+    }
+
+    /// Compresses and writes the records collected so far as one block.
+    fn write_block(&mut self) -> Result<(), ParserError> {
+        if self.block.record_count == 0 {
+            return Ok(());
+        }
+
+        let raw = self.frames.get_mut();
+        let too_large = || ParserError::UnsupportedFormat("block larger than 4 GiB, use fewer records per block".to_string());
+        let mut encoder = compress(Vec::new(), self.options.compression)?;
+        encoder.write_all(raw)?;
+        let payload = encoder.finish()?;
+
+        let block = BlockInfo {
+            offset: self.position,
+            compressed_size: u32::try_from(payload.len()).map_err(|_| too_large())?,
+            uncompressed_size: u32::try_from(raw.len()).map_err(|_| too_large())?,
+            checksum: crc32fast::hash(raw),
+            ..self.block
+        };
+        raw.clear();
+
+        self.writer.write_u32::<BigEndian>(block.record_count)?;
+        self.writer.write_u32::<BigEndian>(block.compressed_size)?;
+        self.writer.write_u32::<BigEndian>(block.uncompressed_size)?;
+        self.writer.write_u32::<BigEndian>(block.checksum)?;
+        self.writer.write_all(&payload)?;
+
+        self.position += BLOCK_HEADER_SIZE + payload.len() as u64;
+        self.blocks.push(block);
+        self.block = Self::empty_block(self.records_written);
+        Ok(())
+    }
+}
+
+impl<W: Write> RecordWriter for BlockBinWriter<W> {
+    /// Adds a record to the current block, writing the block once it is full.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::UnsupportedFormat if the block size is 0 or the
+    /// feature of the chosen compression is disabled.
+    fn write_record(&mut self, record: &YPBankRecord) -> Result<(), ParserError> {
+        if self.records_written == 0 {
+            self.write_container_header()?;
+        }
+
+        self.frames.write_record(record)?;
+        self.block.record_count += 1;
+        self.block.min_tx_id = self.block.min_tx_id.min(record.tx_id);
+        self.block.max_tx_id = self.block.max_tx_id.max(record.tx_id);
+        self.records_written += 1;
+
+        if self.block.record_count == self.options.block_records {
+            self.write_block()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the last block, the block index and the footer, and flushes the writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::NoRecords if no records were written.
+    fn finish(&mut self) -> Result<(), ParserError> {
+        if self.records_written == 0 {
+            return Err(ParserError::NoRecords);
+        }
+        self.write_block()?;
+
+        self.writer.write_u32::<BigEndian>(0)?;
+        let index_offset = self.position + END_MARKER_SIZE;
+        self.writer.write_u32::<BigEndian>(self.blocks.len() as u32)?;
+        for block in &self.blocks {
+            block.write_entry(&mut self.writer)?;
+        }
+        self.writer.write_u64::<BigEndian>(index_offset)?;
+        self.writer.write_all(&BLOCK_MAGIC)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Streaming reader of a block-compressed binary container.
+///
+/// Blocks are read and decompressed one at a time in file order; the block
+/// index at the end is not needed. Records are located by the offset of their
+/// block and their index in the container.
+pub struct BlockBinReader<R> {
+    reader: R,
+    compression: Option<Compression>,
+    position: u64,
+    next_record: u64,
+    records: std::vec::IntoIter<YPBankRecord>,
+    block_offset: u64,
+    done: bool,
+    location: SourceLocation,
+}
+
+impl<R: Read> BlockBinReader<R> {
+    /// Creates a streaming container reader.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader implementing the Read trait
+    pub fn new(reader: R) -> Self {
+        BlockBinReader {
+            reader,
+            compression: None,
+            position: 0,
+            next_record: 0,
+            records: Vec::new().into_iter(),
+            block_offset: 0,
+            done: false,
+            location: SourceLocation::default(),
+        }
+    }
+
+    /// Reads and decodes the next block.
+    ///
+    /// # Returns
+    ///
+    /// Returns false once the end-of-blocks marker is reached.
+    fn read_block(&mut self) -> Result<bool, ParserError> {
+        let compression = match self.compression {
+            Some(compression) => compression,
+            None => {
+                let (compression, _) = read_container_header(&mut self.reader)?;
+                self.position = CONTAINER_HEADER_SIZE;
+                *self.compression.insert(compression)
+            }
+        };
+
+        let record_count = self.reader.read_u32::<BigEndian>()?;
+        if record_count == 0 {
+            return Ok(false);
+        }
+        // The tx_id range of a block is only recorded in the block index
+        let block = BlockInfo {
+            offset: self.position,
+            first_record: self.next_record,
+            record_count,
+            compressed_size: self.reader.read_u32::<BigEndian>()?,
+            uncompressed_size: self.reader.read_u32::<BigEndian>()?,
+            checksum: self.reader.read_u32::<BigEndian>()?,
+            min_tx_id: 0,
+            max_tx_id: u64::MAX,
+        };
+        let payload = read_payload(&mut self.reader, &block)?;
+        let records = decode_block(&payload, &block, compression)?;
+
+        self.block_offset = block.offset;
+        self.position += BLOCK_HEADER_SIZE + block.compressed_size as u64;
+        self.records = records.into_iter();
+        Ok(true)
+    }
+}
+
+impl<R: Read> Iterator for BlockBinReader<R> {
+    type Item = Result<YPBankRecord, ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if let Some(record) = self.records.next() {
+                self.location = SourceLocation {
+                    byte_offset: Some(self.block_offset),
+                    record_index: Some(self.next_record),
+                    ..SourceLocation::default()
+                };
+                self.next_record += 1;
+                return Some(Ok(record));
+            }
+
+            self.location = SourceLocation {
+                byte_offset: Some(self.position),
+                record_index: Some(self.next_record),
+                ..SourceLocation::default()
+            };
+            match self.read_block() {
+                Ok(true) => {}
+                Ok(false) => self.done = true,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e.at(self.location)));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<R: Read> RecordSource for BlockBinReader<R> {
+    fn location(&self) -> SourceLocation {
+        self.location
+    }
+}
+
+/// Random-access reader over a block-compressed binary container.
+///
+/// The block index is read from the end of the file on creation. Records are
+/// then reached by decompressing only the block that holds them; the most
+/// recently decoded block is kept, so neighbouring lookups are cheap.
+pub struct SeekableBlockReader<R> {
+    reader: R,
+    compression: Compression,
+    block_records: u32,
+    blocks: Vec<BlockInfo>,
+    cached: Option<(usize, Vec<YPBankRecord>)>,
+}
+
+impl<R: Read + Seek> SeekableBlockReader<R> {
+    /// Creates a seekable container reader, reading the header and block index.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader implementing the Read and Seek traits
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing the SeekableBlockReader, or ParserError on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::InvalidFileHeader for a bad container header, or
+    /// ParserError::InvalidIndex if the footer or block index is damaged.
+    pub fn new(mut reader: R) -> Result<Self, ParserError> {
+        reader.seek(SeekFrom::Start(0))?;
+        let (compression, block_records) = read_container_header(&mut reader)?;
+
+        let len = reader.seek(SeekFrom::End(0))?;
+        if len < CONTAINER_HEADER_SIZE + END_MARKER_SIZE + 4 + FOOTER_SIZE {
+            return Err(ParserError::InvalidIndex("container is too short for a block index".to_string()));
+        }
+        reader.seek(SeekFrom::End(-(FOOTER_SIZE as i64)))?;
+        let index_offset = reader.read_u64::<BigEndian>()?;
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != BLOCK_MAGIC {
+            return Err(ParserError::InvalidIndex(format!("bad footer magic {:02X?}", magic)));
+        }
+        let index_end = len - FOOTER_SIZE;
+        if !(CONTAINER_HEADER_SIZE + END_MARKER_SIZE..=index_end - 4).contains(&index_offset) {
+            return Err(ParserError::InvalidIndex(format!("index offset {} is out of range", index_offset)));
+        }
+
+        reader.seek(SeekFrom::Start(index_offset))?;
+        let block_count = reader.read_u32::<BigEndian>()? as u64;
+        if block_count * BLOCK_ENTRY_SIZE != index_end - index_offset - 4 {
+            return Err(ParserError::InvalidIndex(format!(
+                "{} blocks do not fit an index of {} bytes",
+                block_count,
+                index_end - index_offset
+            )));
+        }
+
+        let mut blocks = Vec::new();
+        let mut next_record = 0;
+        for _ in 0..block_count {
+            let block = BlockInfo::read_entry(&mut reader)?;
+            if block.first_record != next_record
+                || block.offset + BLOCK_HEADER_SIZE + block.compressed_size as u64 > index_offset
+            {
+                return Err(ParserError::InvalidIndex(format!("inconsistent entry for block at {}", block.offset)));
+            }
+            next_record = block.records().end;
+            blocks.push(block);
+        }
+
+        Ok(SeekableBlockReader {
+            reader,
+            compression,
+            block_records,
+            blocks,
+            cached: None,
+        })
+    }
+
+    /// Returns the compression of the blocks.
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Returns the maximum number of records per block.
+    pub fn block_records(&self) -> u32 {
+        self.block_records
+    }
+
+    /// Returns the block index.
+    pub fn blocks(&self) -> &[BlockInfo] {
+        &self.blocks
+    }
+
+    /// Returns the number of records in the container.
+    pub fn count(&self) -> u64 {
+        self.blocks.last().map_or(0, |block| block.records().end)
+    }
+
+    /// Reads the compressed payload of a block.
+    fn read_block_payload(&mut self, block: &BlockInfo) -> Result<Vec<u8>, ParserError> {
+        self.reader.seek(SeekFrom::Start(block.offset + BLOCK_HEADER_SIZE))?;
+        read_payload(&mut self.reader, block)
+    }
+
+    /// Decodes a block, or returns it from the cache.
+    fn load_block(&mut self, block_index: usize) -> Result<&[YPBankRecord], ParserError> {
+        if self.cached.as_ref().is_none_or(|(cached, _)| *cached != block_index) {
+            let block = self.blocks[block_index];
+            let payload = self.read_block_payload(&block)?;
+            let records = decode_block(&payload, &block, self.compression)?;
+            self.cached = Some((block_index, records));
+        }
+        Ok(self.cached.as_ref().map(|(_, records)| records.as_slice()).unwrap_or_default())
+    }
+
+    /// Reads a single record by index.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Zero-based index of the record
+    ///
+    /// # Returns
+    ///
+    /// Returns the record, or None if the container has fewer records.
+    pub fn get(&mut self, index: u64) -> Result<Option<YPBankRecord>, ParserError> {
+        Ok(self.range(index..index.saturating_add(1))?.pop())
+    }
+
+    /// Reads the records with indices in `range`, decompressing only the blocks that hold them.
+    ///
+    /// The range is clipped to the records present in the container.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - Zero-based indices of the records to read
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing the records, or the first ParserError met.
+    pub fn range(&mut self, range: Range<u64>) -> Result<Vec<YPBankRecord>, ParserError> {
+        let first = self.blocks.partition_point(|block| block.records().end <= range.start);
+        let mut records = Vec::new();
+        for block_index in first..self.blocks.len() {
+            let block_range = self.blocks[block_index].records();
+            if block_range.start >= range.end {
+                break;
+            }
+            let start = range.start.max(block_range.start) - block_range.start;
+            let end = range.end.min(block_range.end) - block_range.start;
+            records.extend_from_slice(&self.load_block(block_index)?[start as usize..end as usize]);
+        }
+        Ok(records)
+    }
+
+    /// Finds a record by `tx_id`, decompressing only blocks whose `tx_id` range covers it.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx_id` - Transaction id to look up
+    ///
+    /// # Returns
+    ///
+    /// Returns the first record with the `tx_id`, or None if there is none.
+    pub fn find_tx_id(&mut self, tx_id: u64) -> Result<Option<YPBankRecord>, ParserError> {
+        for block_index in 0..self.blocks.len() {
+            if !self.blocks[block_index].may_contain(tx_id) {
+                continue;
+            }
+            if let Some(record) = self.load_block(block_index)?.iter().find(|record| record.tx_id == tx_id) {
+                return Ok(Some(record.clone()));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reads all records, decompressing blocks on several threads.
+    ///
+    /// The compressed blocks are read sequentially and then decoded in
+    /// parallel, one contiguous run of blocks per available CPU; records are
+    /// returned in container order.
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing all records, or the ParserError of the first bad block.
+    pub fn read_all_parallel(&mut self) -> Result<Vec<YPBankRecord>, ParserError> {
+        let blocks = self.blocks.clone();
+        let payloads = blocks
+            .iter()
+            .map(|block| self.read_block_payload(block))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+        let chunk = blocks.len().div_ceil(threads).max(1);
+        let compression = self.compression;
+        let decoded: Vec<Result<Vec<YPBankRecord>, ParserError>> = std::thread::scope(|scope| {
+            let workers: Vec<_> = blocks
+                .chunks(chunk)
+                .zip(payloads.chunks(chunk))
+                .map(|(blocks, payloads)| {
+                    scope.spawn(move || {
+                        let mut records = Vec::new();
+                        for (block, payload) in blocks.iter().zip(payloads) {
+                            records.extend(decode_block(payload, block, compression)?);
+                        }
+                        Ok(records)
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+                .collect()
+        });
+
+        let mut records = Vec::new();
+        for part in decoded {
+            records.extend(part?);
+        }
+        Ok(records)
+    }
+}
+
+/// Block-compressed binary container format, registered as "binz".
+pub struct YPBankBlockParser;
+
+impl Format for YPBankBlockParser {
+    fn name(&self) -> &str {
+        "binz"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["binz"]
+    }
+
+    fn reader<'a>(&self, input: Box<dyn BufRead + 'a>) -> Result<RecordReader<'a>, ParserError> {
+        Ok(RecordReader::from_source(BlockBinReader::new(input)))
+    }
+
+    fn writer<'a>(
+        &self,
+        output: Box<dyn Write + 'a>,
+    ) -> Result<Box<dyn RecordWriter + 'a>, ParserError> {
+        Ok(Box::new(BlockBinWriter::new(output)))
+    }
+
+    fn capabilities(&self) -> FormatCapabilities {
+        FormatCapabilities {
+            read: true,
+            write: true,
+            binary: true,
+        }
+    }
+
+    /// Recognises the input by `BLOCK_MAGIC`, with full confidence once the version is known.
+    fn sniff(&self, head: &[u8]) -> f32 {
+        if head.len() < BLOCK_MAGIC.len() || head[0..4] != BLOCK_MAGIC {
+            return 0.0;
+        }
+        match head.get(4..6) {
+            None => 0.9,
+            Some(version) if version == BLOCK_VERSION.to_be_bytes() => 1.0,
+            Some(_) => 0.3,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::parser::Parser;
+    use crate::test_helpers::create_test_records;
+    use std::io::Cursor;
+
+    fn write_container(records: &[YPBankRecord], compression: Compression, block_records: u32) -> Vec<u8> {
+        let options = BlockWriterOptions {
+            compression,
+            block_records,
+        };
+        let mut output = Vec::new();
+        let mut writer = BlockBinWriter::with_options(&mut output, options);
+        for record in records {
+            writer.write_record(record).unwrap();
+        }
+        writer.finish().unwrap();
+        drop(writer);
+        output
+    }
+
+    fn available_compressions() -> Vec<Compression> {
+        let mut compressions = vec![Compression::None];
+        if cfg!(feature = "gzip") {
+            compressions.push(Compression::Gzip);
+        }
+        if cfg!(feature = "zstd") {
+            compressions.push(Compression::Zstd);
+        }
+        compressions
+    }
+
+    #[test]
+    fn test_block_container_stream_and_seek() {
+        let records = create_test_records(100, 2300);
+        for compression in available_compressions() {
+            let data = write_container(&records, compression, 16);
+
+            let streamed = Parser::from_read(Cursor::new(&data), "binz").unwrap();
+            assert_eq!(streamed, records, "{:?}", compression);
+
+            let mut reader = SeekableBlockReader::new(Cursor::new(data)).unwrap();
+            assert_eq!(reader.compression(), compression);
+            assert_eq!(reader.blocks().len(), 7);
+            assert_eq!(reader.count(), 100);
+            assert_eq!(reader.get(50).unwrap().as_ref(), Some(&records[50]));
+            assert_eq!(reader.range(14..40).unwrap(), records[14..40]);
+            assert_eq!(reader.range(95..200).unwrap(), records[95..]);
+            assert_eq!(reader.get(100).unwrap(), None);
+            assert_eq!(reader.find_tx_id(records[77].tx_id).unwrap().as_ref(), Some(&records[77]));
+            assert_eq!(reader.find_tx_id(u64::MAX).unwrap(), None);
+            assert_eq!(reader.read_all_parallel().unwrap(), records);
+        }
+    }
+
+    #[test]
+    fn test_block_container_detects_corrupted_block() {
+        let records = create_test_records(40, 2300);
+        let mut data = write_container(&records, Compression::None, 10);
+        let mut reader = SeekableBlockReader::new(Cursor::new(data.clone())).unwrap();
+        let block = reader.blocks()[2];
+        assert_eq!(reader.get(5).unwrap().as_ref(), Some(&records[5]));
+
+        data[(block.offset + BLOCK_HEADER_SIZE + 20) as usize] ^= 0xFF;
+        let mut reader = SeekableBlockReader::new(Cursor::new(data.clone())).unwrap();
+        assert_eq!(reader.get(5).unwrap().as_ref(), Some(&records[5]));
+        let error = reader.get(25).unwrap_err();
+        assert_eq!(error.code(), "checksum_mismatch");
+        assert_eq!(error.location().and_then(|location| location.byte_offset), Some(block.offset));
+
+        let streamed: Vec<_> = BlockBinReader::new(Cursor::new(data)).collect();
+        assert_eq!(streamed.len(), 21);
+        assert_eq!(streamed[20].as_ref().unwrap_err().code(), "checksum_mismatch");
+    }
+
+    #[test]
+    fn test_block_container_rejects_damaged_index() {
+        let records = create_test_records(5, 2300);
+        let mut data = write_container(&records, Compression::None, 2);
+        let footer = data.len() - FOOTER_SIZE as usize;
+        data[footer + 7] = data[footer + 7].wrapping_add(1);
+
+        let error = SeekableBlockReader::new(Cursor::new(data)).err().unwrap();
+        assert_eq!(error.code(), "invalid_index");
+    }
+
+    #[test]
+    fn test_block_container_format_registered() {
+        let records = create_test_records(3, 2300);
+        let mut output = Vec::new();
+        Parser::write_to(&mut output, &records, "binz").unwrap();
+        assert_eq!(crate::extract_format("archive.binz"), "binz");
+
+        let detection = crate::parsers::format::FormatRegistry::default().detect(&output).unwrap();
+        assert_eq!(detection.format, "binz");
+        assert_eq!(Parser::from_read(Cursor::new(output), "binz").unwrap(), records);
+    }
+}
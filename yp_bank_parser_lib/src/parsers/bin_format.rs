@@ -1,18 +1,698 @@
 
 use byteorder::{BigEndian, WriteBytesExt};
-use std::io::{Read, Write};
+use std::collections::VecDeque;
+use std::io::{BufRead, Read, Write};
 
 use crate::parsers::error::ParserError;
-use crate::parsers::types::{Status, TransactionType, YPBankRecord};
+use crate::parsers::format::{Format, FormatCapabilities};
+use crate::parsers::parallel::{ParallelOptions, parse_bin_parallel};
+use crate::parsers::reader::{RecordReader, RecordSource, SourceLocation};
+use crate::parsers::types::{Status, TransactionType, YPBankRecord, YPBankRecordRef};
+use crate::parsers::writer::RecordWriter;
 
-use crate::{HEADER_SIZE, MAGIC, MIN_BODY_SIZE, MAX_RECORD_SIZE};
+use crate::{FILE_HEADER_SIZE, FILE_MAGIC, HEADER_SIZE, HEADER_SIZE_V2, MAGIC, MIN_BODY_SIZE, MAX_RECORD_SIZE};
 
 const MAGIC_HEADER: u32 = 0x5950424E; // 'YPBN' in ASCII
 
+/// Largest accepted v2 file header, including any extension bytes.
+pub(crate) const MAX_FILE_HEADER_SIZE: usize = 64 * 1024;
+
+/// File header flags understood by this version of the crate.
+const KNOWN_FILE_FLAGS: u16 = 0;
+
+/// Record flag: the body is followed by a CRC32 trailer (u32, big-endian)
+/// computed over the whole frame before it.
+pub const RECORD_FLAG_CHECKSUM: u8 = 0x01;
+
+/// Record flags understood by this version of the crate.
+const KNOWN_RECORD_FLAGS: u8 = RECORD_FLAG_CHECKSUM;
+
+/// Size in bytes of the CRC32 trailer of a record.
+const CHECKSUM_SIZE: usize = 4;
+
+/// Value of the record count field when the count was not known to the writer.
+const UNKNOWN_RECORD_COUNT: u64 = u64::MAX;
+
+/// Producer written to v2 file headers by default.
+pub const DEFAULT_PRODUCER: &str = concat!("yp_bank_parser_lib ", env!("CARGO_PKG_VERSION"));
+
 /// Parser for YPBank binary format files.
-pub struct YPBankBinParser;
+///
+/// Reads version 1 files, a bare sequence of record frames, and version 2
+/// files, which start with a `YPBF` file header (producer, creation time,
+/// record count and flags) and carry a flags byte in every frame. The version
+/// to write is chosen with `BinWriterOptions`; in version 2,
+/// `BinWriterOptions::checksums` appends a CRC32 to every record and a
+/// mismatch is reported as `ParserError::ChecksumMismatch` when reading.
+/// `BinRecordReader::with_recovery` resumes after corruption at the next
+/// plausible record.
+///
+/// As a registered `Format` it writes with its `BinWriterOptions`; the
+/// default instance emits version 1 files readable by older releases.
+#[derive(Debug, Clone, Default)]
+pub struct YPBankBinParser {
+    options: BinWriterOptions,
+}
+
+/// Layout version of a YPBank binary file.
+///
+/// Version 1 files are a bare sequence of `MAGIC` + size + body frames.
+/// Version 2 files start with a `BinFileHeader`, and every frame carries a
+/// record flags byte after its size, such as `RECORD_FLAG_CHECKSUM`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BinVersion {
+    #[default]
+    V1,
+    V2,
+}
+
+impl BinVersion {
+    /// Returns the layout version with the given number.
+    ///
+    /// # Arguments
+    ///
+    /// * `number` - Version number, as stored in the file header
+    ///
+    /// # Returns
+    ///
+    /// Returns None for version numbers this release does not know.
+    pub fn from_number(number: u16) -> Option<BinVersion> {
+        match number {
+            1 => Some(BinVersion::V1),
+            2 => Some(BinVersion::V2),
+            _ => None,
+        }
+    }
+
+    /// Returns the version number stored in the file header.
+    pub fn number(self) -> u16 {
+        match self {
+            BinVersion::V1 => 1,
+            BinVersion::V2 => 2,
+        }
+    }
+
+    /// Returns the size of a record frame header in this version.
+    pub fn frame_header_size(self) -> usize {
+        match self {
+            BinVersion::V1 => HEADER_SIZE,
+            BinVersion::V2 => HEADER_SIZE_V2,
+        }
+    }
+}
+
+/// File-level header of a version 2 binary file.
+///
+/// Layout (big-endian): `FILE_MAGIC`, version (u16), flags (u16), header size
+/// (u32, the whole header including extension bytes), creation time (u64,
+/// milliseconds since the Unix epoch), record count (u64, `u64::MAX` when
+/// unknown), producer length (u16) and the UTF-8 producer string. Readers skip
+/// header bytes they do not understand, so fields can be appended later.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinFileHeader {
+    pub version: BinVersion,
+    pub flags: u16,
+    pub producer: String,
+    pub created_at: u64,
+    pub record_count: Option<u64>,
+}
+
+impl BinFileHeader {
+    /// Reads a file header whose `FILE_MAGIC` has already been consumed.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader positioned just after the file magic
+    ///
+    /// # Returns
+    ///
+    /// Returns the header and its total size in bytes, including the magic.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::UnsupportedBinVersion or UnsupportedBinFlags for files
+    /// written by a newer producer, and InvalidFileHeader for inconsistent sizes.
+    pub(crate) fn read_after_magic<R: Read>(reader: &mut R) -> Result<(BinFileHeader, u64), ParserError> {
+        let mut fixed = [0u8; FILE_HEADER_SIZE - 4];
+        reader.read_exact(&mut fixed)?;
+
+        let version = u16::from_be_bytes(fixed[0..2].try_into()?);
+        let flags = u16::from_be_bytes(fixed[2..4].try_into()?);
+        let header_size = u32::from_be_bytes(fixed[4..8].try_into()?) as usize;
+        let created_at = u64::from_be_bytes(fixed[8..16].try_into()?);
+        let record_count = u64::from_be_bytes(fixed[16..24].try_into()?);
+        let producer_len = u16::from_be_bytes(fixed[24..26].try_into()?) as usize;
+
+        let version = match version {
+            2 => BinVersion::V2,
+            other => return Err(ParserError::UnsupportedBinVersion(other)),
+        };
+        if flags & !KNOWN_FILE_FLAGS != 0 {
+            return Err(ParserError::UnsupportedBinFlags(flags & !KNOWN_FILE_FLAGS));
+        }
+        if header_size < FILE_HEADER_SIZE + producer_len || header_size > MAX_FILE_HEADER_SIZE {
+            return Err(ParserError::InvalidFileHeader(format!(
+                "header size {} does not fit a producer of {} bytes",
+                header_size, producer_len
+            )));
+        }
+
+        let mut rest = vec![0u8; header_size - FILE_HEADER_SIZE];
+        reader.read_exact(&mut rest)?;
+        rest.truncate(producer_len);
+        let producer = String::from_utf8(rest)?;
+
+        let header = BinFileHeader {
+            version,
+            flags,
+            producer,
+            created_at,
+            record_count: (record_count != UNKNOWN_RECORD_COUNT).then_some(record_count),
+        };
+        Ok((header, header_size as u64))
+    }
+
+    /// Writes the header, including `FILE_MAGIC`.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - A writer implementing the Write trait
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::InvalidFileHeader if the producer is longer than 65535 bytes.
+    pub(crate) fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), ParserError> {
+        let producer = self.producer.as_bytes();
+        let producer_len = u16::try_from(producer.len()).map_err(|_| {
+            ParserError::InvalidFileHeader(format!("producer of {} bytes is too long", producer.len()))
+        })?;
+
+        writer.write_all(&FILE_MAGIC)?;
+        writer.write_u16::<BigEndian>(self.version.number())?;
+        writer.write_u16::<BigEndian>(self.flags)?;
+        writer.write_u32::<BigEndian>((FILE_HEADER_SIZE + producer.len()) as u32)?;
+        writer.write_u64::<BigEndian>(self.created_at)?;
+        writer.write_u64::<BigEndian>(self.record_count.unwrap_or(UNKNOWN_RECORD_COUNT))?;
+        writer.write_u16::<BigEndian>(producer_len)?;
+        writer.write_all(producer)?;
+        Ok(())
+    }
+}
+
+/// Reader that can push consumed bytes back, tracking its position in the input.
+struct PushbackReader<R> {
+    inner: R,
+    pushback: VecDeque<u8>,
+    position: u64,
+}
+
+impl<R: Read> PushbackReader<R> {
+    fn new(inner: R) -> Self {
+        PushbackReader {
+            inner,
+            pushback: VecDeque::new(),
+            position: 0,
+        }
+    }
+
+    /// Returns `bytes` to the front of the input, so that they are read again next.
+    fn unread(&mut self, bytes: &[u8]) {
+        for &byte in bytes.iter().rev() {
+            self.pushback.push_front(byte);
+        }
+        self.position -= bytes.len() as u64;
+    }
+}
+
+impl<R: Read> Read for PushbackReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = if self.pushback.is_empty() {
+            self.inner.read(buf)?
+        } else {
+            self.pushback.read(buf)?
+        };
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+/// A range of input bytes skipped by a recovering `BinRecordReader`.
+#[derive(Debug)]
+pub struct SkippedRange {
+    /// Byte offset of the first skipped byte.
+    pub start: u64,
+    /// Byte offset just past the last skipped byte: the next recovered record or the end of input.
+    pub end: u64,
+    /// The framing error that started the skip.
+    pub cause: ParserError,
+}
+
+/// Streaming reader yielding YPBank records from a binary source one frame at a time.
+///
+/// The layout version is detected from the first bytes: a `FILE_MAGIC` header
+/// selects version 2, a record `MAGIC` a header-less version 1 file.
+/// Each frame is read in full before it is decoded, so a record with a
+/// malformed body or a checksum mismatch can be skipped. Framing errors (bad
+/// magic, size or record flags, or a truncated frame) end the stream, since
+/// the position of the next record can no longer be trusted, unless the reader
+/// was created with `with_recovery`.
+pub struct BinRecordReader<R> {
+    reader: PushbackReader<R>,
+    version: Option<BinVersion>,
+    header: Option<BinFileHeader>,
+    recover: bool,
+    skipped: Vec<SkippedRange>,
+    done: bool,
+    frame: Vec<u8>,
+    record_index: u64,
+    location: SourceLocation,
+}
+
+impl<R: Read> BinRecordReader<R> {
+    /// Creates a new binary record reader over the given reader.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader implementing the Read trait
+    pub fn new(reader: R) -> Self {
+        BinRecordReader {
+            reader: PushbackReader::new(reader),
+            version: None,
+            header: None,
+            recover: false,
+            skipped: Vec::new(),
+            done: false,
+            frame: Vec::new(),
+            record_index: 0,
+            location: SourceLocation::default(),
+        }
+    }
+
+    /// Creates a binary record reader positioned at a frame boundary of a file
+    /// whose layout version is already known.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader positioned at the start of a frame
+    /// * `version` - Layout version of the file
+    /// * `offset` - Byte offset of the frame in the file
+    /// * `record_index` - Index of the record in the frame
+    pub(crate) fn resume(reader: R, version: BinVersion, offset: u64, record_index: u64) -> Self {
+        let mut resumed = Self::new(reader);
+        resumed.version = Some(version);
+        resumed.reader.position = offset;
+        resumed.record_index = record_index;
+        resumed
+    }
+
+    /// Creates a binary record reader that resynchronises after framing errors.
+    ///
+    /// Instead of ending the stream, a framing error makes the reader scan
+    /// forward for the next `MAGIC` sequence that starts a plausible record:
+    /// one with valid size and flags, a matching checksum if it has one, and a
+    /// body that decodes. The bytes in between are reported by `skipped()`, and
+    /// the record count of a version 2 header is no longer checked once
+    /// anything was skipped. Errors in the file header are not recovered from.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader implementing the Read trait
+    pub fn with_recovery(reader: R) -> Self {
+        BinRecordReader {
+            recover: true,
+            ..Self::new(reader)
+        }
+    }
+
+    /// Returns the layout version of the input, once the first record has been requested.
+    pub fn version(&self) -> Option<BinVersion> {
+        self.version
+    }
+
+    /// Returns the file header of a version 2 input, once the first record has been requested.
+    pub fn header(&self) -> Option<&BinFileHeader> {
+        self.header.as_ref()
+    }
+
+    /// Returns the underlying reader.
+    #[cfg(feature = "async")]
+    pub(crate) fn get_mut(&mut self) -> &mut R {
+        &mut self.reader.inner
+    }
+
+    /// Returns the byte ranges skipped so far by a recovering reader.
+    pub fn skipped(&self) -> &[SkippedRange] {
+        &self.skipped
+    }
+
+    /// Appends input bytes to the frame buffer until it holds `len` bytes.
+    ///
+    /// # Returns
+    ///
+    /// Returns false if the input ended first; the buffer then holds the bytes that were read.
+    fn fill_frame(&mut self, len: usize) -> Result<bool, ParserError> {
+        let mut filled = self.frame.len();
+        self.frame.resize(len, 0);
+        while filled < len {
+            match self.reader.read(&mut self.frame[filled..]) {
+                Ok(0) => {
+                    self.frame.truncate(filled);
+                    return Ok(false);
+                }
+                Ok(read) => filled += read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.frame.truncate(filled);
+                    return Err(ParserError::Io(e));
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Detects the layout version from the first four bytes of the input.
+    ///
+    /// Consumes the file header of a version 2 input; for version 1 the bytes
+    /// read are the `MAGIC` of the first frame and are left in the frame buffer.
+    ///
+    /// # Returns
+    ///
+    /// Returns false for empty input.
+    fn read_preamble(&mut self) -> Result<bool, ParserError> {
+        if !self.fill_frame(FILE_MAGIC.len())? {
+            return Ok(false);
+        }
+
+        if self.frame == FILE_MAGIC {
+            self.frame.clear();
+            let (header, _) = BinFileHeader::read_after_magic(&mut self.reader)?;
+            self.version = Some(header.version);
+            self.header = Some(header);
+        } else {
+            self.version = Some(BinVersion::V1);
+        }
+        Ok(true)
+    }
+
+    /// Ends the input, checking the record count declared by the file header.
+    fn end_of_input(&self) -> Result<Option<u32>, ParserError> {
+        match self.header.as_ref().and_then(|header| header.record_count) {
+            Some(expected) if expected != self.record_index && self.skipped.is_empty() => {
+                Err(ParserError::RecordCountMismatch {
+                    expected,
+                    actual: self.record_index,
+                })
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Reads the next record frame (header, body and trailer) into the frame buffer.
+    ///
+    /// On error the frame buffer holds the bytes of the frame consumed so far.
+    ///
+    /// # Returns
+    ///
+    /// Returns the body size of the frame, or None at end of input.
+    fn read_frame(&mut self) -> Result<Option<u32>, ParserError> {
+        self.location = SourceLocation {
+            byte_offset: Some(self.reader.position),
+            record_index: Some(self.record_index),
+            ..SourceLocation::default()
+        };
+        self.frame.clear();
+
+        if self.version.is_none() {
+            if !self.read_preamble()? {
+                return Ok(None);
+            }
+            self.location.byte_offset = Some(self.reader.position - self.frame.len() as u64);
+        }
+        let frame_header_size = self.version.unwrap_or_default().frame_header_size();
+
+        if !self.fill_frame(frame_header_size)? {
+            // A partial frame header is ignored, unless it has to be reported as skipped
+            if self.recover && !self.frame.is_empty() {
+                return Err(ParserError::UnexpectedEof {
+                    expected: frame_header_size,
+                    actual: self.frame.len(),
+                });
+            }
+            return self.end_of_input();
+        }
+
+        let (record_size, frame_size) = parse_frame_header(&self.frame, self.version.unwrap_or_default())?;
+        if !self.fill_frame(frame_size)? {
+            return Err(ParserError::UnexpectedEof {
+                expected: frame_size,
+                actual: self.frame.len(),
+            });
+        }
+
+        self.record_index += 1;
+        Ok(Some(record_size))
+    }
+
+    /// Decodes the frame in the buffer after verifying its checksum.
+    fn decode_frame(&self, record_size: u32) -> Result<YPBankRecord, ParserError> {
+        decode_frame(&self.frame, self.version.unwrap_or_default(), record_size, self.location)
+            .map(|record| record.to_owned())
+    }
+
+    /// Consumes input up to the next `MAGIC` sequence, leaving it unread.
+    ///
+    /// # Returns
+    ///
+    /// Returns false if the input ended without another `MAGIC`.
+    fn scan_to_magic(&mut self) -> Result<bool, ParserError> {
+        let mut window = [0u8; 4];
+        let mut scanned = 0;
+        let mut byte = [0u8; 1];
+        loop {
+            match self.reader.read_exact(&mut byte) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(false),
+                Err(e) => return Err(ParserError::Io(e)),
+            }
+            window.rotate_left(1);
+            window[3] = byte[0];
+            scanned += 1;
+            if scanned >= window.len() && window == MAGIC {
+                self.reader.unread(&MAGIC);
+                return Ok(true);
+            }
+        }
+    }
+
+    /// Skips forward from the frame that failed with `cause` to the next plausible record.
+    ///
+    /// # Returns
+    ///
+    /// Returns the recovered record, or None if the input ended first.
+    fn resync(&mut self, cause: ParserError) -> Result<Option<YPBankRecord>, ParserError> {
+        let start = self.location.byte_offset.unwrap_or_default();
+        self.skipped.push(SkippedRange { start, end: start, cause });
+        loop {
+            // Rescan everything after the first byte of the rejected frame
+            if self.frame.len() > 1 {
+                let rest = self.frame.split_off(1);
+                self.reader.unread(&rest);
+            }
+
+            let found = self.scan_to_magic()?;
+            let candidate = self.reader.position;
+            if let Some(skipped) = self.skipped.last_mut() {
+                skipped.end = candidate;
+            }
+            if !found {
+                return Ok(None);
+            }
+
+            match self.read_frame() {
+                Ok(Some(record_size)) => match self.decode_frame(record_size) {
+                    Ok(record) => return Ok(Some(record)),
+                    Err(_) => self.record_index -= 1,
+                },
+                Ok(None) => {
+                    if let Some(skipped) = self.skipped.last_mut() {
+                        skipped.end = self.reader.position;
+                    }
+                    return Ok(None);
+                }
+                Err(e) if is_framing_error(&e) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Validates a record frame header.
+///
+/// # Arguments
+///
+/// * `frame_header` - The first `version.frame_header_size()` bytes of a frame
+/// * `version` - Layout version of the file
+///
+/// # Returns
+///
+/// Returns the body size and the total size of the frame, including its header and trailer.
+///
+/// # Errors
+///
+/// Returns ParserError::InvalidMagic, UnsupportedBinFlags, RecordTooSmall or RecordTooLarge.
+pub(crate) fn parse_frame_header(frame_header: &[u8], version: BinVersion) -> Result<(u32, usize), ParserError> {
+    let magic: [u8; 4] = frame_header[0..4].try_into()?;
+    if magic != MAGIC {
+        return Err(ParserError::InvalidMagic(magic));
+    }
+
+    let record_size = u32::from_be_bytes(frame_header[4..8].try_into()?);
+
+    let record_flags = match version {
+        BinVersion::V1 => 0,
+        BinVersion::V2 => frame_header[HEADER_SIZE],
+    };
+    if record_flags & !KNOWN_RECORD_FLAGS != 0 {
+        return Err(ParserError::UnsupportedBinFlags((record_flags & !KNOWN_RECORD_FLAGS) as u16));
+    }
+    let trailer_size = if record_flags & RECORD_FLAG_CHECKSUM != 0 {
+        CHECKSUM_SIZE
+    } else {
+        0
+    };
+
+    // Validate size bounds
+    if record_size < MIN_BODY_SIZE as u32 {
+        return Err(ParserError::RecordTooSmall(record_size, MIN_BODY_SIZE));
+    }
+
+    if record_size > MAX_RECORD_SIZE as u32 {
+        return Err(ParserError::RecordTooLarge(record_size, MAX_RECORD_SIZE));
+    }
+
+    Ok((record_size, version.frame_header_size() + record_size as usize + trailer_size))
+}
+
+/// Decodes a complete frame after verifying its checksum, if it has one.
+///
+/// # Arguments
+///
+/// * `frame` - A frame validated by `parse_frame_header`, including its trailer
+/// * `version` - Layout version of the file
+/// * `record_size` - Body size from the frame header
+/// * `location` - Location of the frame, reported by a checksum mismatch
+///
+/// # Returns
+///
+/// Returns a Result containing a view of the record borrowing from `frame`.
+///
+/// # Errors
+///
+/// Returns ParserError::ChecksumMismatch if the frame was altered after it was
+/// written, or the errors of a malformed body.
+pub(crate) fn decode_frame(
+    frame: &[u8],
+    version: BinVersion,
+    record_size: u32,
+    location: SourceLocation,
+) -> Result<YPBankRecordRef<'_>, ParserError> {
+    let record_flags = match version {
+        BinVersion::V1 => 0,
+        BinVersion::V2 => frame[HEADER_SIZE],
+    };
+    if record_flags & RECORD_FLAG_CHECKSUM != 0 {
+        let (data, trailer) = frame.split_at(frame.len() - CHECKSUM_SIZE);
+        if crc32fast::hash(data) != u32::from_be_bytes(trailer.try_into()?) {
+            return Err(ParserError::ChecksumMismatch {
+                record_index: location.record_index.unwrap_or_default(),
+                offset: location.byte_offset.unwrap_or_default(),
+            });
+        }
+    }
+
+    let frame_header_size = version.frame_header_size();
+    YPBankBinParser::parse_record_ref(&frame[frame_header_size..frame_header_size + record_size as usize])
+}
+
+/// Returns whether an error leaves the position of the next frame unknown.
+fn is_framing_error(error: &ParserError) -> bool {
+    matches!(
+        error,
+        ParserError::InvalidMagic(_)
+            | ParserError::RecordTooSmall(_, _)
+            | ParserError::RecordTooLarge(_, _)
+            | ParserError::UnsupportedBinFlags(_)
+            | ParserError::UnexpectedEof { .. }
+    )
+}
+
+impl<R: Read> Iterator for BinRecordReader<R> {
+    type Item = Result<YPBankRecord, ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.read_frame() {
+            Ok(Some(record_size)) => Some(self.decode_frame(record_size).map_err(|e| e.at(self.location))),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) if self.recover && self.version.is_some() && is_framing_error(&e) => {
+                let cause = e.at(self.location);
+                match self.resync(cause) {
+                    Ok(Some(record)) => Some(Ok(record)),
+                    Ok(None) => {
+                        self.done = true;
+                        None
+                    }
+                    Err(e) => {
+                        self.done = true;
+                        Some(Err(e.at(self.location)))
+                    }
+                }
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e.at(self.location)))
+            }
+        }
+    }
+}
+
+impl<R: Read> RecordSource for BinRecordReader<R> {
+    fn location(&self) -> SourceLocation {
+        self.location
+    }
+
+    fn raw(&self) -> &[u8] {
+        &self.frame
+    }
+
+    fn can_resume(&self) -> bool {
+        !self.done
+    }
+}
 
-impl YPBankBinParser {    
+impl YPBankBinParser {
+    /// Creates a binary format whose registry writer uses the given options.
+    ///
+    /// Register it in place of the built-in "bin" format to make
+    /// `Parser::writer` and `Parser::write_to` emit version 2 files.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Layout version and file header contents of written files
+    pub fn with_options(options: BinWriterOptions) -> Self {
+        YPBankBinParser { options }
+    }
+
+    /// Returns the options used by the registry writer.
+    pub fn options(&self) -> &BinWriterOptions {
+        &self.options
+    }
 
     /// Parses YPBank records from a byte slice.
     ///
@@ -30,6 +710,8 @@ impl YPBankBinParser {
     /// Reads YPBank records from a binary format reader.
     ///
     /// Parses the binary format with magic headers and validates record structure.
+    /// Collects the output of a `BinRecordReader`; use the reader directly
+    /// to process large inputs in constant memory.
     ///
     /// # Arguments
     ///
@@ -43,101 +725,117 @@ impl YPBankBinParser {
     ///
     /// Returns various ParserError variants for invalid magic bytes, size validation failures,
     /// or I/O errors.
-    pub fn from_read<R: Read>(mut reader: R) -> Result<Vec<YPBankRecord>, ParserError> {
-        let mut records = Vec::new();
-        let mut header_buf = [0u8; HEADER_SIZE];
-
-        loop {
-            match reader.read_exact(&mut header_buf) {
-                Ok(()) => {}
-                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
-                Err(e) => return Err(ParserError::Io(e)),
-            }
+    pub fn from_read<R: Read>(reader: R) -> Result<Vec<YPBankRecord>, ParserError> {
+        BinRecordReader::new(reader).collect()
+    }
 
-            let magic: [u8; 4] = header_buf[0..4].try_into()?;
-            if magic != MAGIC {
-                return Err(ParserError::InvalidMagic(magic));
-            }
+    /// Reads YPBank records from a binary format reader, decoding on several threads.
+    ///
+    /// The whole input is copied into memory and parsed by `parse_bytes_parallel`;
+    /// data already in memory or mapped should be passed to `parse_bytes_parallel`
+    /// directly. Records and parsing errors are those of `from_read`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader implementing the Read trait
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing a Vec<YPBankRecord> on success, or ParserError on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns various ParserError variants for invalid magic bytes, size validation failures,
+    /// or I/O errors.
+    pub fn from_read_parallel<R: Read>(mut reader: R) -> Result<Vec<YPBankRecord>, ParserError> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Self::parse_bytes_parallel(&data)
+    }
 
-            let record_size = u32::from_be_bytes(header_buf[4..8].try_into()?);
-            
-            // Validate size bounds
-            if record_size < MIN_BODY_SIZE as u32 {
-                return Err(ParserError::RecordTooSmall(record_size, MIN_BODY_SIZE));
-            }
-            
-            if record_size > MAX_RECORD_SIZE as u32 {
-                return Err(ParserError::RecordTooLarge(record_size, MAX_RECORD_SIZE));
-            }
+    /// Parses YPBank records from a byte slice on several threads, without copying it.
+    ///
+    /// Runs `parse_bin_parallel` with the default `ParallelOptions`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Byte slice containing binary YPBank data, such as `MmapBinFile::as_bytes`
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing a Vec<YPBankRecord> on success, or ParserError on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `parse_bin_parallel`.
+    pub fn parse_bytes_parallel(data: &[u8]) -> Result<Vec<YPBankRecord>, ParserError> {
+        parse_bin_parallel(data, &ParallelOptions::default())
+    }
 
-            // Parse record directly from reader without pre-buffering entire body
-            let record = Self::parse_record_from_reader(&mut reader, record_size)?;
-            records.push(record);
+    /// Decodes a record body in place, borrowing the description from `body`.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - The body of one frame, without frame header or trailer
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing the YPBankRecordRef, or ParserError for a malformed body.
+    pub(crate) fn parse_record_ref(body: &[u8]) -> Result<YPBankRecordRef<'_>, ParserError> {
+        let record_size = body.len();
+        if record_size < MIN_BODY_SIZE {
+            return Err(ParserError::RecordTooSmall(record_size as u32, MIN_BODY_SIZE));
         }
 
-        Ok(records)
-    }
-
-    fn parse_record_from_reader<R: Read>(reader: &mut R, record_size: u32) -> Result<YPBankRecord, ParserError> {
-        // Use take() to limit reading to exactly record_size bytes
-        let mut limited_reader = reader.take(record_size as u64);
-        
-        let mut buffer = [0u8; 8];
-        
         // TX_ID: 8 bytes, u64 big-endian
-        limited_reader.read_exact(&mut buffer)?;
-        let tx_id = u64::from_be_bytes(buffer);
-        
+        let tx_id = u64::from_be_bytes(body[0..8].try_into()?);
+
         // TX_TYPE: 1 byte
-        let mut tx_type_buf = [0u8; 1];
-        limited_reader.read_exact(&mut tx_type_buf)?;
-        let tx_type = TransactionType::from_byte(tx_type_buf[0])?;
-        
+        let tx_type = TransactionType::from_byte(body[8])?;
+
         // FROM_USER_ID: 8 bytes
-        limited_reader.read_exact(&mut buffer)?;
-        let from_user_id = u64::from_be_bytes(buffer);
-        
+        let from_user_id = u64::from_be_bytes(body[9..17].try_into()?);
+
         // TO_USER_ID: 8 bytes
-        limited_reader.read_exact(&mut buffer)?;
-        let to_user_id = u64::from_be_bytes(buffer);
-        
+        let to_user_id = u64::from_be_bytes(body[17..25].try_into()?);
+
         // AMOUNT: 8 bytes
-        limited_reader.read_exact(&mut buffer)?;
-        let amount = i64::from_be_bytes(buffer);
-        
+        let amount = i64::from_be_bytes(body[25..33].try_into()?);
+
         // TIMESTAMP: 8 bytes
-        limited_reader.read_exact(&mut buffer)?;
-        let timestamp = u64::from_be_bytes(buffer);
-        
+        let timestamp = u64::from_be_bytes(body[33..41].try_into()?);
+
         // STATUS: 1 byte
-        let mut status_buf = [0u8; 1];
-        limited_reader.read_exact(&mut status_buf)?;
-        let status = Status::from_byte(status_buf[0])?;
-        
+        let status = Status::from_byte(body[41])?;
+
         // DESC_LEN: 4 bytes
-        let mut desc_len_buf = [0u8; 4];
-        limited_reader.read_exact(&mut desc_len_buf)?;
-        let desc_len = u32::from_be_bytes(desc_len_buf);
-        
+        let desc_len = u32::from_be_bytes(body[42..46].try_into()?);
+
         // Read description
-        if desc_len > (record_size - 42) { // 42 = sum of all fixed field sizes
-            return Err(ParserError::DescriptionOverflow { 
-                desc_len, 
-                remaining: record_size as usize - 42 
+        if desc_len as usize > record_size - 42 { // 42 = sum of all fixed field sizes
+            return Err(ParserError::DescriptionOverflow {
+                desc_len,
+                remaining: record_size - 42,
             });
         }
-        
-        let mut description_bytes = vec![0u8; desc_len as usize];
-        limited_reader.read_exact(&mut description_bytes)?;
-        let description = String::from_utf8(description_bytes)?;
-        
-        // Ensure we've consumed exactly record_size bytes
-        if limited_reader.limit() != 0 {
-            // This should not happen if our calculations are correct
-            return Err(ParserError::ParseError(format!("Did not consume all record bytes: {} remaining", limited_reader.limit())));
+
+        let description_bytes = body.get(MIN_BODY_SIZE..MIN_BODY_SIZE + desc_len as usize).ok_or(
+            ParserError::UnexpectedEof {
+                expected: desc_len as usize,
+                actual: record_size - MIN_BODY_SIZE,
+            },
+        )?;
+        // Validated in place; only a failure copies the bytes, for the error value
+        let description = std::str::from_utf8(description_bytes)
+            .map_err(|_| ParserError::from(String::from_utf8(description_bytes.to_vec()).unwrap_err()))?;
+
+        // Ensure the description ends the body
+        let trailing = record_size - MIN_BODY_SIZE - desc_len as usize;
+        if trailing != 0 {
+            return Err(ParserError::TrailingBytes(trailing as u64));
         }
 
-        Ok(YPBankRecord {
+        Ok(YPBankRecordRef {
             tx_id,
             tx_type,
             from_user_id,
@@ -147,16 +845,39 @@ impl YPBankBinParser {
             status,
             description,
         })
-    }    
+    }
+
+    /// Writes YPBank records to a writer in binary format version 1.
+    ///
+    /// Each record is written with a magic header, size field, and binary-encoded data,
+    /// using a `BinRecordWriter`. Use `write_with_options` to choose the version.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - A writer implementing the Write trait
+    /// * `records` - Slice of YPBankRecord to write
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result with () on success, or ParserError on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::NoRecords if no records are provided, or I/O errors during writing.
+    pub fn write_to<W: Write>(writer: W, records: &[YPBankRecord]) -> Result<(), ParserError> {
+        Self::write_with_options(writer, records, BinWriterOptions::default())
+    }
 
-    /// Writes YPBank records to a writer in binary format.
+    /// Writes YPBank records to a writer in the binary layout selected by `options`.
     ///
-    /// Each record is written with a magic header, size field, and binary-encoded data.
+    /// For version 2 the record count in the file header is taken from `records`
+    /// unless `options` already sets it.
     ///
     /// # Arguments
     ///
     /// * `writer` - A writer implementing the Write trait
     /// * `records` - Slice of YPBankRecord to write
+    /// * `options` - Layout version and file header contents
     ///
     /// # Returns
     ///
@@ -164,36 +885,523 @@ impl YPBankBinParser {
     ///
     /// # Errors
     ///
-    /// Returns ParserError::ParseError if no records are provided, or I/O errors during writing.
-    pub fn write_to<W: Write>(mut writer: W, records: &[YPBankRecord]) -> Result<(), ParserError> {
-        if records.is_empty() {
-            return Err(ParserError::ParseError("No records to write".to_string()));
+    /// Returns ParserError::NoRecords if no records are provided, or I/O errors during writing.
+    pub fn write_with_options<W: Write>(
+        writer: W,
+        records: &[YPBankRecord],
+        mut options: BinWriterOptions,
+    ) -> Result<(), ParserError> {
+        options.record_count.get_or_insert(records.len() as u64);
+        let mut record_writer = BinRecordWriter::with_options(writer, options);
+        for record in records {
+            record_writer.write_record(record)?;
         }
+        record_writer.finish()
+    }
+}
 
-        for record in records.iter() {
-            //}.take (1) {
-            // Write magic header for each record
-            writer.write_all(&MAGIC_HEADER.to_be_bytes())?;
+impl Format for YPBankBinParser {
+    fn name(&self) -> &str {
+        "bin"
+    }
 
-            // Calculate and write record size
-            let desc_bytes = record.description.as_bytes();
-            let record_size = 8 + 1 + 8 + 8 + 8 + 8 + 1 + 4 + desc_bytes.len() as u32;
-            writer.write_u32::<BigEndian>(record_size)?;
+    fn extensions(&self) -> &[&str] {
+        &["bin"]
+    }
 
-            // Write record fields
-            writer.write_u64::<BigEndian>(record.tx_id)?;
-            writer.write_u8(record.tx_type as u8)?;
-            writer.write_u64::<BigEndian>(record.from_user_id)?;
-            writer.write_u64::<BigEndian>(record.to_user_id)?;
-            writer.write_i64::<BigEndian>(record.amount)?;
-            writer.write_u64::<BigEndian>(record.timestamp)?;
-            writer.write_u8(record.status as u8)?;
+    fn reader<'a>(&self, input: Box<dyn BufRead + 'a>) -> Result<RecordReader<'a>, ParserError> {
+        Ok(RecordReader::from_source(BinRecordReader::new(input)))
+    }
+
+    fn writer<'a>(
+        &self,
+        output: Box<dyn Write + 'a>,
+    ) -> Result<Box<dyn RecordWriter + 'a>, ParserError> {
+        Ok(Box::new(BinRecordWriter::with_options(output, self.options.clone())))
+    }
+
+    fn capabilities(&self) -> FormatCapabilities {
+        FormatCapabilities {
+            read: true,
+            write: true,
+            binary: true,
+        }
+    }
 
-            // Write description
-            writer.write_u32::<BigEndian>(desc_bytes.len() as u32)?;
-            writer.write_all(desc_bytes)?;
+    /// Recognises the input by the `FILE_MAGIC` of a version 2 header or the
+    /// `MAGIC` bytes of the first record header, with full confidence once the
+    /// version or record size is available and valid.
+    fn sniff(&self, head: &[u8]) -> f32 {
+        if head.len() >= FILE_MAGIC.len() && head[0..4] == FILE_MAGIC {
+            return match head.get(4..6) {
+                None => 0.9,
+                Some(version) if version == BinVersion::V2.number().to_be_bytes() => 1.0,
+                Some(_) => 0.3,
+            };
         }
+        if head.len() < MAGIC.len() || head[0..4] != MAGIC {
+            return 0.0;
+        }
+        if head.len() < HEADER_SIZE {
+            return 0.9;
+        }
+
+        let record_size = u32::from_be_bytes([head[4], head[5], head[6], head[7]]) as usize;
+        if (MIN_BODY_SIZE..=MAX_RECORD_SIZE).contains(&record_size) {
+            1.0
+        } else {
+            0.3
+        }
+    }
+}
+
+/// Options of a `BinRecordWriter`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinWriterOptions {
+    /// Layout version to emit; version 1 keeps files readable by older releases.
+    pub version: BinVersion,
+    /// Producer recorded in the version 2 file header.
+    pub producer: String,
+    /// Creation time in milliseconds since the Unix epoch; the current time when None.
+    pub created_at: Option<u64>,
+    /// Number of records that will be written, if known in advance.
+    pub record_count: Option<u64>,
+    /// Append a CRC32 trailer to every record; requires version 2.
+    pub checksums: bool,
+}
+
+impl Default for BinWriterOptions {
+    fn default() -> Self {
+        BinWriterOptions {
+            version: BinVersion::V1,
+            producer: DEFAULT_PRODUCER.to_string(),
+            created_at: None,
+            record_count: None,
+            checksums: false,
+        }
+    }
+}
 
+/// Incremental writer emitting YPBank records in binary format.
+///
+/// Each record is framed with the `MAGIC` header and its body size. In
+/// version 2 the file header is written before the first record, and each
+/// frame carries a record flags byte and, if enabled, a CRC32 trailer.
+pub struct BinRecordWriter<W> {
+    writer: W,
+    options: BinWriterOptions,
+    frame: Vec<u8>,
+    records_written: usize,
+}
+
+impl<W: Write> BinRecordWriter<W> {
+    /// Creates a new binary record writer emitting version 1 files.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - A writer implementing the Write trait
+    pub fn new(writer: W) -> Self {
+        Self::with_options(writer, BinWriterOptions::default())
+    }
+
+    /// Creates a new binary record writer with the given options.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - A writer implementing the Write trait
+    /// * `options` - Layout version and file header contents
+    pub fn with_options(writer: W, options: BinWriterOptions) -> Self {
+        BinRecordWriter {
+            writer,
+            options,
+            frame: Vec::new(),
+            records_written: 0,
+        }
+    }
+
+    /// Returns the underlying writer.
+    pub(crate) fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Writes the version 2 file header.
+    fn write_file_header(&mut self) -> Result<(), ParserError> {
+        let created_at = self.options.created_at.unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_millis() as u64)
+                .unwrap_or(0)
+        });
+        let header = BinFileHeader {
+            version: self.options.version,
+            flags: 0,
+            producer: self.options.producer.clone(),
+            created_at,
+            record_count: self.options.record_count,
+        };
+        header.write_to(&mut self.writer)
+    }
+}
+
+impl<W: Write> RecordWriter for BinRecordWriter<W> {
+    /// Encodes and writes one record frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::UnsupportedFormat if checksums are requested for a
+    /// version 1 file, which has no record flags to signal them.
+    fn write_record(&mut self, record: &YPBankRecord) -> Result<(), ParserError> {
+        let version = self.options.version;
+        if self.options.checksums && version == BinVersion::V1 {
+            return Err(ParserError::UnsupportedFormat(
+                "record checksums require binary format version 2".to_string(),
+            ));
+        }
+        if version == BinVersion::V2 && self.records_written == 0 {
+            self.write_file_header()?;
+        }
+
+        // The frame is assembled in memory so that its checksum can be appended
+        let writer = &mut self.frame;
+        writer.clear();
+
+        // Write magic header for each record
+        writer.write_all(&MAGIC_HEADER.to_be_bytes())?;
+
+        // Calculate and write record size
+        let desc_bytes = record.description.as_bytes();
+        let record_size = 8 + 1 + 8 + 8 + 8 + 8 + 1 + 4 + desc_bytes.len() as u32;
+        writer.write_u32::<BigEndian>(record_size)?;
+
+        if version == BinVersion::V2 {
+            let record_flags = if self.options.checksums { RECORD_FLAG_CHECKSUM } else { 0 };
+            writer.write_u8(record_flags)?;
+        }
+
+        // Write record fields
+        writer.write_u64::<BigEndian>(record.tx_id)?;
+        writer.write_u8(record.tx_type as u8)?;
+        writer.write_u64::<BigEndian>(record.from_user_id)?;
+        writer.write_u64::<BigEndian>(record.to_user_id)?;
+        writer.write_i64::<BigEndian>(record.amount)?;
+        writer.write_u64::<BigEndian>(record.timestamp)?;
+        writer.write_u8(record.status as u8)?;
+
+        // Write description
+        writer.write_u32::<BigEndian>(desc_bytes.len() as u32)?;
+        writer.write_all(desc_bytes)?;
+
+        if self.options.checksums {
+            let checksum = crc32fast::hash(writer);
+            writer.write_u32::<BigEndian>(checksum)?;
+        }
+
+        self.writer.write_all(&self.frame)?;
+        self.records_written += 1;
         Ok(())
     }
+
+    /// Flushes the writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::NoRecords if no records were written, or
+    /// RecordCountMismatch if the count promised in the options was not met.
+    fn finish(&mut self) -> Result<(), ParserError> {
+        if self.records_written == 0 {
+            return Err(ParserError::NoRecords);
+        }
+        if self.options.version == BinVersion::V2
+            && let Some(expected) = self.options.record_count
+            && expected != self.records_written as u64
+        {
+            return Err(ParserError::RecordCountMismatch {
+                expected,
+                actual: self.records_written as u64,
+            });
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::create_test_records;
+    use std::io::Cursor;
+
+    fn v2_options() -> BinWriterOptions {
+        BinWriterOptions {
+            version: BinVersion::V2,
+            producer: "test producer".to_string(),
+            created_at: Some(1_700_000_000_000),
+            record_count: None,
+            checksums: false,
+        }
+    }
+
+    #[test]
+    fn test_bin_v2_roundtrip_with_header() {
+        let records = create_test_records(5, 1600);
+        let mut output = Vec::new();
+        YPBankBinParser::write_with_options(&mut output, &records, v2_options()).unwrap();
+        assert_eq!(output[0..4], FILE_MAGIC);
+
+        let mut reader = BinRecordReader::new(Cursor::new(&output));
+        let parsed: Vec<YPBankRecord> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(parsed, records);
+        assert_eq!(reader.version(), Some(BinVersion::V2));
+        assert_eq!(
+            reader.header(),
+            Some(&BinFileHeader {
+                version: BinVersion::V2,
+                flags: 0,
+                producer: "test producer".to_string(),
+                created_at: 1_700_000_000_000,
+                record_count: Some(5),
+            })
+        );
+        assert_eq!(YPBankBinParser::parse_bytes(&output).unwrap(), records);
+    }
+
+    #[test]
+    fn test_bin_v1_is_default_and_headerless() {
+        let records = create_test_records(2, 1600);
+        let mut output = Vec::new();
+        YPBankBinParser::write_to(&mut output, &records).unwrap();
+        assert_eq!(output[0..4], MAGIC);
+
+        let mut reader = BinRecordReader::new(Cursor::new(&output));
+        assert_eq!(reader.by_ref().count(), 2);
+        assert_eq!(reader.version(), Some(BinVersion::V1));
+        assert_eq!(reader.header(), None);
+    }
+
+    #[test]
+    fn test_bin_v2_record_offsets_follow_header() {
+        let records = create_test_records(2, 1600);
+        let mut output = Vec::new();
+        YPBankBinParser::write_with_options(&mut output, &records, v2_options()).unwrap();
+
+        let mut reader = BinRecordReader::new(Cursor::new(&output));
+        reader.next().unwrap().unwrap();
+        let header_size = (FILE_HEADER_SIZE + "test producer".len()) as u64;
+        assert_eq!(reader.location().byte_offset, Some(header_size));
+    }
+
+    #[test]
+    fn test_bin_v2_rejects_unknown_version_and_flags() {
+        let records = create_test_records(1, 1600);
+        let mut output = Vec::new();
+        YPBankBinParser::write_with_options(&mut output, &records, v2_options()).unwrap();
+
+        let mut newer = output.clone();
+        newer[4..6].copy_from_slice(&3u16.to_be_bytes());
+        let error = YPBankBinParser::parse_bytes(&newer).unwrap_err();
+        assert_eq!(error.code(), "unsupported_bin_version");
+
+        let mut flagged = output.clone();
+        flagged[6..8].copy_from_slice(&0x8000u16.to_be_bytes());
+        let error = YPBankBinParser::parse_bytes(&flagged).unwrap_err();
+        assert_eq!(error.code(), "unsupported_bin_flags");
+
+        let record_flags = FILE_HEADER_SIZE + "test producer".len() + HEADER_SIZE;
+        let mut flagged = output;
+        flagged[record_flags] = 0x80;
+        let error = YPBankBinParser::parse_bytes(&flagged).unwrap_err();
+        assert_eq!(error.code(), "unsupported_bin_flags");
+    }
+
+    #[test]
+    fn test_bin_v2_detects_missing_records() {
+        let records = create_test_records(3, 1600);
+        let mut output = Vec::new();
+        YPBankBinParser::write_with_options(&mut output, &records, v2_options()).unwrap();
+
+        let mut truncated = Vec::new();
+        YPBankBinParser::write_with_options(
+            &mut truncated,
+            &records[..2],
+            BinWriterOptions {
+                record_count: Some(3),
+                ..v2_options()
+            },
+        )
+        .unwrap_err();
+
+        let last_frame = output.len() - (HEADER_SIZE_V2 + MIN_BODY_SIZE + records[2].description.len());
+        let error = YPBankBinParser::parse_bytes(&output[..last_frame]).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ParserError::RecordCountMismatch { expected: 3, actual: 2 }
+        ));
+    }
+
+    #[test]
+    fn test_bin_checksums_detect_corruption() {
+        let records = create_test_records(3, 1600);
+        let options = BinWriterOptions {
+            checksums: true,
+            ..v2_options()
+        };
+        let mut output = Vec::new();
+        YPBankBinParser::write_with_options(&mut output, &records, options).unwrap();
+        assert_eq!(YPBankBinParser::parse_bytes(&output).unwrap(), records);
+
+        // Flip a bit in the amount of the second record
+        let header_size = FILE_HEADER_SIZE + "test producer".len();
+        let first_frame = HEADER_SIZE_V2 + MIN_BODY_SIZE + records[0].description.len() + 4;
+        let second_offset = header_size + first_frame;
+        let mut corrupted = output.clone();
+        corrupted[second_offset + HEADER_SIZE_V2 + 8 + 1 + 8 + 8 + 7] ^= 0x01;
+
+        let results: Vec<_> = BinRecordReader::new(Cursor::new(&corrupted)).collect();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap(), &records[0]);
+        let error = results[1].as_ref().unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ParserError::ChecksumMismatch { record_index: 1, offset } if *offset == second_offset as u64
+        ));
+        assert_eq!(results[2].as_ref().unwrap(), &records[2]);
+    }
+
+    #[test]
+    fn test_bin_checksums_require_v2() {
+        let options = BinWriterOptions {
+            checksums: true,
+            ..BinWriterOptions::default()
+        };
+        let mut output = Vec::new();
+        let error = YPBankBinParser::write_with_options(&mut output, &create_test_records(1, 1600), options)
+            .unwrap_err();
+        assert_eq!(error.code(), "unsupported_format");
+    }
+
+    #[test]
+    fn test_bin_format_writer_uses_options() {
+        let records = create_test_records(3, 1600);
+        let format = YPBankBinParser::with_options(v2_options());
+        let mut output = Vec::new();
+        let mut writer = format.writer(Box::new(&mut output)).unwrap();
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        writer.finish().unwrap();
+        drop(writer);
+
+        assert_eq!(output[0..4], FILE_MAGIC);
+        let mut reader = BinRecordReader::new(Cursor::new(output));
+        assert_eq!(reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap(), records);
+        assert_eq!(reader.header().unwrap().producer, "test producer");
+    }
+
+    #[test]
+    fn test_bin_format_writer_with_checksums() {
+        let records = create_test_records(2, 1600);
+        let format = YPBankBinParser::with_options(BinWriterOptions {
+            checksums: true,
+            ..v2_options()
+        });
+        let mut output = Vec::new();
+        let mut writer = format.writer(Box::new(&mut output)).unwrap();
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        writer.finish().unwrap();
+        drop(writer);
+
+        // The flags byte of the first frame follows its magic and size
+        let header_size = FILE_HEADER_SIZE + "test producer".len();
+        assert_eq!(output[header_size + HEADER_SIZE], RECORD_FLAG_CHECKSUM);
+        assert_eq!(YPBankBinParser::parse_bytes(&output).unwrap(), records);
+    }
+
+    #[test]
+    fn test_bin_version_from_number() {
+        assert_eq!(BinVersion::from_number(1), Some(BinVersion::V1));
+        assert_eq!(BinVersion::from_number(2), Some(BinVersion::V2));
+        assert_eq!(BinVersion::from_number(3), None);
+    }
+
+    /// Returns the byte offset of every frame in a version 1 file.
+    fn frame_offsets(records: &[YPBankRecord]) -> Vec<usize> {
+        let mut offsets = vec![0];
+        for record in records {
+            let last = *offsets.last().unwrap();
+            offsets.push(last + HEADER_SIZE + MIN_BODY_SIZE + record.description.len());
+        }
+        offsets
+    }
+
+    #[test]
+    fn test_bin_recovery_skips_corrupted_frames() {
+        let records = create_test_records(5, 1600);
+        let offsets = frame_offsets(&records);
+        let mut output = Vec::new();
+        YPBankBinParser::write_to(&mut output, &records).unwrap();
+
+        // Damage the magic of the second frame and the size of the fourth
+        output[offsets[1]] = b'X';
+        output[offsets[3] + 4..offsets[3] + 8].copy_from_slice(&u32::MAX.to_be_bytes());
+
+        let error = YPBankBinParser::parse_bytes(&output).unwrap_err();
+        assert_eq!(error.code(), "invalid_magic");
+
+        let mut reader = BinRecordReader::with_recovery(Cursor::new(&output));
+        let recovered: Vec<YPBankRecord> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(recovered, vec![records[0].clone(), records[2].clone(), records[4].clone()]);
+
+        let skipped: Vec<(u64, u64, &str)> = reader
+            .skipped()
+            .iter()
+            .map(|range| (range.start, range.end, range.cause.code()))
+            .collect();
+        assert_eq!(
+            skipped,
+            vec![
+                (offsets[1] as u64, offsets[2] as u64, "invalid_magic"),
+                (offsets[3] as u64, offsets[4] as u64, "record_too_large"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bin_recovery_skips_inserted_garbage() {
+        let records = create_test_records(3, 1600);
+        let mut written = Vec::new();
+        YPBankBinParser::write_with_options(&mut written, &records, v2_options()).unwrap();
+
+        // Garbage containing a stray MAGIC between the first two frames, and at the end
+        let header_size = FILE_HEADER_SIZE + "test producer".len();
+        let first_end = header_size + HEADER_SIZE_V2 + MIN_BODY_SIZE + records[0].description.len();
+        let garbage = [&b"junk"[..], &MAGIC, &[0, 0, 0, 1]].concat();
+        let mut output = written[..first_end].to_vec();
+        output.extend_from_slice(&garbage);
+        output.extend_from_slice(&written[first_end..]);
+        output.extend_from_slice(b"tail");
+
+        let mut reader = BinRecordReader::with_recovery(Cursor::new(&output));
+        let recovered: Vec<YPBankRecord> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(recovered, records);
+
+        let skipped: Vec<(usize, usize)> = reader
+            .skipped()
+            .iter()
+            .map(|range| (range.start as usize, range.end as usize))
+            .collect();
+        assert_eq!(
+            skipped,
+            vec![(first_end, first_end + garbage.len()), (output.len() - 4, output.len())]
+        );
+    }
+
+    #[test]
+    fn test_bin_sniff_v2_header() {
+        let mut output = Vec::new();
+        YPBankBinParser::write_with_options(&mut output, &create_test_records(1, 1600), v2_options()).unwrap();
+        assert_eq!(YPBankBinParser::default().sniff(&output), 1.0);
+        assert_eq!(YPBankBinParser::default().sniff(&output[..4]), 0.9);
+    }
 }
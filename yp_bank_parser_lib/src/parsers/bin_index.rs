@@ -0,0 +1,720 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::fs::File;
+use std::collections::VecDeque;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::parsers::bin_format::{BinRecordReader, BinVersion};
+use crate::parsers::error::ParserError;
+use crate::parsers::reader::RecordSource;
+use crate::parsers::types::YPBankRecord;
+
+/// Magic bytes identifying a YPBank binary index file: "YPBX"
+pub const INDEX_MAGIC: [u8; 4] = [0x59, 0x50, 0x42, 0x58];
+
+/// Layout version of the index file.
+const INDEX_VERSION: u16 = 2;
+
+/// Index flag: the index has a section ordered by timestamp.
+const INDEX_FLAG_TIMESTAMPS: u16 = 0x01;
+
+/// Extension appended to the data file path to name its sidecar index.
+pub const INDEX_EXTENSION: &str = "idx";
+
+/// Size in bytes of each of the regions at the start and the end of a data
+/// file covered by `DataFingerprint::sample_checksum`.
+pub const SAMPLE_SIZE: u64 = 64 * 1024;
+
+/// Size and checksums of a data file, used to detect that an index is stale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataFingerprint {
+    /// Size of the data file in bytes.
+    pub size: u64,
+    /// CRC32 of the whole data file.
+    pub checksum: u32,
+    /// CRC32 of the first and the last `SAMPLE_SIZE` bytes of the data file.
+    pub sample_checksum: u32,
+}
+
+impl DataFingerprint {
+    /// Computes the fingerprint of everything `reader` yields.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader implementing the Read trait
+    pub fn of<R: Read>(reader: R) -> Result<Self, ParserError> {
+        let mut hashing = HashingReader::new(reader);
+        std::io::copy(&mut hashing, &mut std::io::sink())?;
+        Ok(hashing.fingerprint())
+    }
+
+    /// Computes the sample checksum of a data file of known size, reading at
+    /// most `2 * SAMPLE_SIZE` bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The data file; it is left at an unspecified position
+    /// * `size` - Size of the data file in bytes
+    fn sample_of<R: Read + Seek>(reader: &mut R, size: u64) -> Result<u32, ParserError> {
+        let head_size = size.min(SAMPLE_SIZE);
+        let tail_start = head_size.max(size.saturating_sub(SAMPLE_SIZE));
+
+        let mut head = vec![0u8; head_size as usize];
+        reader.seek(SeekFrom::Start(0))?;
+        reader.read_exact(&mut head)?;
+        let mut tail = vec![0u8; (size - tail_start) as usize];
+        reader.seek(SeekFrom::Start(tail_start))?;
+        reader.read_exact(&mut tail)?;
+        Ok(sample_checksum(&head, &tail))
+    }
+}
+
+/// Returns the CRC32 of the first bytes of a data file followed by its last bytes.
+fn sample_checksum(head: &[u8], tail: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(head);
+    hasher.update(tail);
+    hasher.finalize()
+}
+
+/// Reader computing the fingerprint of the bytes read through it.
+struct HashingReader<R> {
+    inner: R,
+    hasher: crc32fast::Hasher,
+    size: u64,
+    /// The first `SAMPLE_SIZE` bytes.
+    head: Vec<u8>,
+    /// The last `SAMPLE_SIZE` bytes after `head`.
+    tail: VecDeque<u8>,
+}
+
+impl<R: Read> HashingReader<R> {
+    fn new(inner: R) -> Self {
+        HashingReader {
+            inner,
+            hasher: crc32fast::Hasher::new(),
+            size: 0,
+            head: Vec::new(),
+            tail: VecDeque::new(),
+        }
+    }
+
+    fn fingerprint(&self) -> DataFingerprint {
+        let (tail_front, tail_back) = self.tail.as_slices();
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&self.head);
+        hasher.update(tail_front);
+        hasher.update(tail_back);
+        DataFingerprint {
+            size: self.size,
+            checksum: self.hasher.clone().finalize(),
+            sample_checksum: hasher.finalize(),
+        }
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        self.size += read as u64;
+
+        let head_free = (SAMPLE_SIZE as usize - self.head.len()).min(read);
+        self.head.extend_from_slice(&buf[..head_free]);
+        let rest = &buf[head_free..read];
+        let keep = rest.len().min(SAMPLE_SIZE as usize);
+        self.tail.extend(&rest[rest.len() - keep..]);
+        let excess = self.tail.len().saturating_sub(SAMPLE_SIZE as usize);
+        self.tail.drain(..excess);
+        Ok(read)
+    }
+}
+
+/// Position of one record in the data file, under a lookup key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct IndexEntry {
+    /// The `tx_id` or `timestamp` of the record.
+    pub key: u64,
+    /// Byte offset of the record frame.
+    pub offset: u64,
+    /// Index of the record in the data file.
+    pub record_index: u64,
+}
+
+/// Sidecar index of a YPBank binary file.
+///
+/// Maps `tx_id`, and optionally `timestamp`, to the byte offset of each
+/// record. The index remembers the `DataFingerprint` of the data file it was
+/// built from and is rejected once the file changes.
+///
+/// `verify` only compares the size and the first and last `SAMPLE_SIZE`
+/// bytes, so it misses a same-size change in the middle of the file;
+/// `verify_full` hashes the whole file and is what `IndexedBinReader::new`
+/// and `IndexedBinReader::open` use. A change made after the reader was
+/// created is caught by `IndexedBinReader::get` when the record at the
+/// indexed offset has another `tx_id`.
+///
+/// File layout (big-endian): `INDEX_MAGIC`, index version (u16), flags (u16),
+/// data file version (u16), data size (u64), data CRC32 (u32), sample CRC32
+/// (u32), entry count
+/// (u64), the entries sorted by `tx_id`, then, with `INDEX_FLAG_TIMESTAMPS`,
+/// the same number of entries sorted by timestamp. Each entry is key (u64),
+/// offset (u64) and record index (u64).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinIndex {
+    pub fingerprint: DataFingerprint,
+    pub version: BinVersion,
+    pub by_tx_id: Vec<IndexEntry>,
+    pub by_timestamp: Option<Vec<IndexEntry>>,
+}
+
+impl BinIndex {
+    /// Builds an index by reading a binary data file once.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader positioned at the start of the data file
+    /// * `with_timestamps` - Also index records by timestamp, for `IndexedBinReader::time_range`
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing the BinIndex, or the first ParserError of the data file.
+    pub fn build<R: Read>(reader: R, with_timestamps: bool) -> Result<Self, ParserError> {
+        let mut hashing = HashingReader::new(reader);
+        let mut by_tx_id = Vec::new();
+        let mut by_timestamp = Vec::new();
+
+        let mut records = BinRecordReader::new(&mut hashing);
+        while let Some(record) = records.next() {
+            let record = record?;
+            let location = records.location();
+            let entry = IndexEntry {
+                key: record.tx_id,
+                offset: location.byte_offset.unwrap_or_default(),
+                record_index: location.record_index.unwrap_or_default(),
+            };
+            by_tx_id.push(entry);
+            if with_timestamps {
+                by_timestamp.push(IndexEntry {
+                    key: record.timestamp,
+                    ..entry
+                });
+            }
+        }
+        let version = records.version().unwrap_or_default();
+        drop(records);
+
+        // Bytes the record reader did not need still count towards the fingerprint
+        std::io::copy(&mut hashing, &mut std::io::sink())?;
+
+        by_tx_id.sort_unstable();
+        by_timestamp.sort_unstable();
+        Ok(BinIndex {
+            fingerprint: hashing.fingerprint(),
+            version,
+            by_tx_id,
+            by_timestamp: with_timestamps.then_some(by_timestamp),
+        })
+    }
+
+    /// Returns the sidecar index path of a data file: the data path with `.idx` appended.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_path` - Path of the binary data file
+    pub fn sidecar_path<P: AsRef<Path>>(data_path: P) -> PathBuf {
+        let mut path = data_path.as_ref().as_os_str().to_owned();
+        path.push(".");
+        path.push(INDEX_EXTENSION);
+        PathBuf::from(path)
+    }
+
+    /// Checks cheaply that the index was built from the data `reader` yields.
+    ///
+    /// Compares the size and the checksum of the first and last `SAMPLE_SIZE`
+    /// bytes, so at most `2 * SAMPLE_SIZE` bytes are read whatever the size of
+    /// the data. This detects appended or truncated records, a rewritten file
+    /// header, and changes near the start or the end of the file, but not a
+    /// same-size change in the middle of a large file; `verify_full` detects
+    /// any change.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The data file; it is left at an unspecified position
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::StaleIndex if the size or the sample checksum differs.
+    pub fn verify<R: Read + Seek>(&self, reader: &mut R) -> Result<(), ParserError> {
+        let size = self.verify_size(reader)?;
+        let sample_checksum = DataFingerprint::sample_of(reader, size)?;
+        if sample_checksum != self.fingerprint.sample_checksum {
+            return Err(ParserError::StaleIndex(format!(
+                "data sample checksum is {:08x}, index was built for {:08x}",
+                sample_checksum, self.fingerprint.sample_checksum
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks that the index was built from exactly the data `reader` yields.
+    ///
+    /// Compares the size first, so a data file of a different size is
+    /// rejected without being read; otherwise hashes the whole file, which
+    /// detects any change to its content.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The data file; it is left at an unspecified position
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::StaleIndex if the size or checksum differs.
+    pub fn verify_full<R: Read + Seek>(&self, reader: &mut R) -> Result<(), ParserError> {
+        self.verify_size(reader)?;
+        reader.seek(SeekFrom::Start(0))?;
+        let fingerprint = DataFingerprint::of(reader)?;
+        if fingerprint != self.fingerprint {
+            return Err(ParserError::StaleIndex(format!(
+                "data checksum is {:08x}, index was built for {:08x}",
+                fingerprint.checksum, self.fingerprint.checksum
+            )));
+        }
+        Ok(())
+    }
+
+    /// Compares the size of the data file with the one the index was built for.
+    ///
+    /// # Returns
+    ///
+    /// Returns the size of the data file.
+    fn verify_size<R: Seek>(&self, reader: &mut R) -> Result<u64, ParserError> {
+        let size = reader.seek(SeekFrom::End(0))?;
+        if size != self.fingerprint.size {
+            return Err(ParserError::StaleIndex(format!(
+                "data size is {} bytes, index was built for {} bytes",
+                size, self.fingerprint.size
+            )));
+        }
+        Ok(size)
+    }
+
+    /// Writes the index in its binary layout.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - A writer implementing the Write trait
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<(), ParserError> {
+        let flags = if self.by_timestamp.is_some() {
+            INDEX_FLAG_TIMESTAMPS
+        } else {
+            0
+        };
+        writer.write_all(&INDEX_MAGIC)?;
+        writer.write_u16::<BigEndian>(INDEX_VERSION)?;
+        writer.write_u16::<BigEndian>(flags)?;
+        writer.write_u16::<BigEndian>(self.version.number())?;
+        writer.write_u64::<BigEndian>(self.fingerprint.size)?;
+        writer.write_u32::<BigEndian>(self.fingerprint.checksum)?;
+        writer.write_u32::<BigEndian>(self.fingerprint.sample_checksum)?;
+        writer.write_u64::<BigEndian>(self.by_tx_id.len() as u64)?;
+
+        let sections = std::iter::once(&self.by_tx_id).chain(self.by_timestamp.as_ref());
+        for entry in sections.flatten() {
+            writer.write_u64::<BigEndian>(entry.key)?;
+            writer.write_u64::<BigEndian>(entry.offset)?;
+            writer.write_u64::<BigEndian>(entry.record_index)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads an index written by `write_to`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader implementing the Read trait
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::InvalidIndex if the input is not an index this version understands.
+    pub fn read_from<R: Read>(mut reader: R) -> Result<Self, ParserError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != INDEX_MAGIC {
+            return Err(ParserError::InvalidIndex(format!("bad magic {:02X?}", magic)));
+        }
+        let index_version = reader.read_u16::<BigEndian>()?;
+        if index_version != INDEX_VERSION {
+            return Err(ParserError::InvalidIndex(format!("unsupported version {}", index_version)));
+        }
+        let flags = reader.read_u16::<BigEndian>()?;
+        if flags & !INDEX_FLAG_TIMESTAMPS != 0 {
+            return Err(ParserError::InvalidIndex(format!("unsupported flags {:#06x}", flags)));
+        }
+        let version = match reader.read_u16::<BigEndian>()? {
+            1 => BinVersion::V1,
+            2 => BinVersion::V2,
+            other => return Err(ParserError::UnsupportedBinVersion(other)),
+        };
+        let fingerprint = DataFingerprint {
+            size: reader.read_u64::<BigEndian>()?,
+            checksum: reader.read_u32::<BigEndian>()?,
+            sample_checksum: reader.read_u32::<BigEndian>()?,
+        };
+        let count = reader.read_u64::<BigEndian>()?;
+
+        let mut read_section = || -> Result<Vec<IndexEntry>, ParserError> {
+            let mut entries = Vec::new();
+            for _ in 0..count {
+                entries.push(IndexEntry {
+                    key: reader.read_u64::<BigEndian>()?,
+                    offset: reader.read_u64::<BigEndian>()?,
+                    record_index: reader.read_u64::<BigEndian>()?,
+                });
+            }
+            Ok(entries)
+        };
+        let by_tx_id = read_section()?;
+        let by_timestamp = if flags & INDEX_FLAG_TIMESTAMPS != 0 {
+            Some(read_section()?)
+        } else {
+            None
+        };
+
+        Ok(BinIndex {
+            fingerprint,
+            version,
+            by_tx_id,
+            by_timestamp,
+        })
+    }
+
+    /// Saves the index to a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the index file, usually `sidecar_path` of the data file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ParserError> {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+
+    /// Loads an index from a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the index file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ParserError> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+}
+
+/// Binary file reader answering point and range queries through a `BinIndex`.
+pub struct IndexedBinReader<R> {
+    reader: R,
+    index: BinIndex,
+}
+
+impl<R: Read + Seek> IndexedBinReader<R> {
+    /// Creates an indexed reader after checking that the index matches the data.
+    ///
+    /// The whole data file is hashed with `BinIndex::verify_full`, so any
+    /// change since the index was built is rejected.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The binary data file
+    /// * `index` - An index built from the data file
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::StaleIndex if the data changed since the index was built.
+    pub fn new(mut reader: R, index: BinIndex) -> Result<Self, ParserError> {
+        index.verify_full(&mut reader)?;
+        Ok(IndexedBinReader { reader, index })
+    }
+
+    /// Returns the index in use.
+    pub fn index(&self) -> &BinIndex {
+        &self.index
+    }
+
+    /// Decodes the record at an index entry and checks that it has the entry key.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The index entry
+    /// * `key_of` - Returns the field of the record the entry is keyed by
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::StaleIndex if the decoded record has another key.
+    fn read_entry(
+        &mut self,
+        entry: &IndexEntry,
+        key_of: fn(&YPBankRecord) -> u64,
+    ) -> Result<YPBankRecord, ParserError> {
+        self.reader.seek(SeekFrom::Start(entry.offset))?;
+        let record = BinRecordReader::resume(&mut self.reader, self.index.version, entry.offset, entry.record_index)
+            .next()
+            .unwrap_or(Err(ParserError::UnexpectedEof {
+                expected: crate::HEADER_SIZE,
+                actual: 0,
+            }))?;
+        if key_of(&record) != entry.key {
+            return Err(ParserError::StaleIndex(format!(
+                "record at offset {} has key {}, index expects {}",
+                entry.offset,
+                key_of(&record),
+                entry.key
+            )));
+        }
+        Ok(record)
+    }
+
+    /// Looks up a record by `tx_id`.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx_id` - The transaction id
+    ///
+    /// # Returns
+    ///
+    /// Returns the first record in file order with this `tx_id`, or None.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::StaleIndex if the record at the indexed offset has
+    /// another `tx_id`, which means the data changed after the reader was created.
+    pub fn get(&mut self, tx_id: u64) -> Result<Option<YPBankRecord>, ParserError> {
+        let position = self.index.by_tx_id.partition_point(|entry| entry.key < tx_id);
+        match self.index.by_tx_id.get(position).copied() {
+            Some(entry) if entry.key == tx_id => self.read_entry(&entry, |record| record.tx_id).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Reads the records whose timestamp lies in `range`, ordered by timestamp.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - Timestamps in milliseconds since the Unix epoch
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::InvalidIndex if the index was built without timestamps,
+    /// and ParserError::StaleIndex if a record at an indexed offset has another timestamp.
+    pub fn time_range(&mut self, range: Range<u64>) -> Result<Vec<YPBankRecord>, ParserError> {
+        let Some(by_timestamp) = self.index.by_timestamp.as_ref() else {
+            return Err(ParserError::InvalidIndex("index was built without timestamps".to_string()));
+        };
+        let start = by_timestamp.partition_point(|entry| entry.key < range.start);
+        let end = by_timestamp.partition_point(|entry| entry.key < range.end);
+        let entries = by_timestamp[start..end.max(start)].to_vec();
+
+        entries
+            .iter()
+            .map(|entry| self.read_entry(entry, |record| record.timestamp))
+            .collect()
+    }
+}
+
+impl IndexedBinReader<BufReader<File>> {
+    /// Opens a binary data file with its sidecar index, rebuilding the index if
+    /// it is missing, unreadable or stale according to `BinIndex::verify_full`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_path` - Path of the binary data file
+    /// * `with_timestamps` - Index timestamps when the index has to be rebuilt
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing the IndexedBinReader, or ParserError on failure.
+    pub fn open<P: AsRef<Path>>(data_path: P, with_timestamps: bool) -> Result<Self, ParserError> {
+        let index_path = BinIndex::sidecar_path(&data_path);
+        let mut reader = BufReader::new(File::open(&data_path)?);
+
+        if let Ok(index) = BinIndex::load(&index_path)
+            && index.verify_full(&mut reader).is_ok()
+        {
+            return Ok(IndexedBinReader { reader, index });
+        }
+
+        reader.seek(SeekFrom::Start(0))?;
+        let index = BinIndex::build(&mut reader, with_timestamps)?;
+        index.save(&index_path)?;
+        Ok(IndexedBinReader { reader, index })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::bin_format::{BinWriterOptions, YPBankBinParser};
+    use crate::test_helpers::create_test_records;
+    use std::io::Cursor;
+
+    fn write_bin(records: &[YPBankRecord]) -> Vec<u8> {
+        let options = BinWriterOptions {
+            version: BinVersion::V2,
+            ..BinWriterOptions::default()
+        };
+        let mut output = Vec::new();
+        YPBankBinParser::write_with_options(&mut output, records, options).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_index_point_lookup_and_time_range() {
+        let records = create_test_records(200, 2000);
+        let data = write_bin(&records);
+        let index = BinIndex::build(Cursor::new(&data), true).unwrap();
+        assert_eq!(index.fingerprint.size, data.len() as u64);
+
+        let mut reader = IndexedBinReader::new(Cursor::new(&data), index).unwrap();
+        assert_eq!(reader.get(records[150].tx_id).unwrap().as_ref(), Some(&records[150]));
+        assert_eq!(reader.get(u64::MAX).unwrap(), None);
+
+        let mut timestamps: Vec<u64> = records.iter().map(|record| record.timestamp).collect();
+        timestamps.sort_unstable();
+        let range = timestamps[20]..timestamps[60];
+        let expected = records.iter().filter(|record| range.contains(&record.timestamp)).count();
+        let found = reader.time_range(range.clone()).unwrap();
+        assert_eq!(found.len(), expected);
+        assert!(found.iter().all(|record| range.contains(&record.timestamp)));
+        assert!(found.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp));
+    }
+
+    #[test]
+    fn test_index_serialization_roundtrip() {
+        let data = write_bin(&create_test_records(10, 2000));
+        for with_timestamps in [false, true] {
+            let index = BinIndex::build(Cursor::new(&data), with_timestamps).unwrap();
+            let mut serialized = Vec::new();
+            index.write_to(&mut serialized).unwrap();
+            assert_eq!(BinIndex::read_from(Cursor::new(serialized)).unwrap(), index);
+        }
+
+        let index = BinIndex::build(Cursor::new(&data), false).unwrap();
+        let mut reader = IndexedBinReader::new(Cursor::new(&data), index).unwrap();
+        assert_eq!(reader.time_range(0..u64::MAX).unwrap_err().code(), "invalid_index");
+    }
+
+    #[test]
+    fn test_index_rejects_changed_data() {
+        let records = create_test_records(10, 2000);
+        let data = write_bin(&records);
+        let index = BinIndex::build(Cursor::new(&data), false).unwrap();
+
+        let mut modified = data.clone();
+        let last = modified.len() - 1;
+        modified[last] ^= 0x01;
+        let error = IndexedBinReader::new(Cursor::new(&modified), index.clone()).err().unwrap();
+        assert_eq!(error.code(), "stale_index");
+
+        let longer = write_bin(&create_test_records(11, 2000));
+        let error = IndexedBinReader::new(Cursor::new(&longer), index).err().unwrap();
+        assert_eq!(error.code(), "stale_index");
+    }
+
+    /// Reader counting the bytes read through it.
+    struct CountingReader<R> {
+        inner: R,
+        read: u64,
+    }
+
+    impl<R: Read> Read for CountingReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let read = self.inner.read(buf)?;
+            self.read += read as u64;
+            Ok(read)
+        }
+    }
+
+    impl<R: Seek> Seek for CountingReader<R> {
+        fn seek(&mut self, position: SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(position)
+        }
+    }
+
+    #[test]
+    fn test_index_quick_and_full_verification() {
+        let data = write_bin(&create_test_records(5000, 2000));
+        assert!(data.len() as u64 > 4 * SAMPLE_SIZE);
+        let index = BinIndex::build(Cursor::new(&data), false).unwrap();
+        assert_eq!(index.fingerprint, DataFingerprint::of(Cursor::new(&data)).unwrap());
+
+        let mut reader = CountingReader {
+            inner: Cursor::new(&data),
+            read: 0,
+        };
+        index.verify(&mut reader).unwrap();
+        assert!(reader.read <= 2 * SAMPLE_SIZE);
+        index.verify_full(&mut reader).unwrap();
+
+        // A same-size change in the middle is only seen by the full check
+        let mut modified = data.clone();
+        modified[data.len() / 2] ^= 0x01;
+        index.verify(&mut Cursor::new(&modified)).unwrap();
+        assert_eq!(index.verify_full(&mut Cursor::new(&modified)).unwrap_err().code(), "stale_index");
+
+        for at in [10, data.len() - 10] {
+            let mut modified = data.clone();
+            modified[at] ^= 0x01;
+            assert_eq!(index.verify(&mut Cursor::new(&modified)).unwrap_err().code(), "stale_index");
+        }
+    }
+
+    #[test]
+    fn test_indexed_reader_rejects_change_in_the_middle() {
+        let records = create_test_records(5000, 2000);
+        let data = write_bin(&records);
+        let index = BinIndex::build(Cursor::new(&data), false).unwrap();
+
+        let mut modified = data.clone();
+        modified[data.len() / 2] ^= 0x01;
+        let error = IndexedBinReader::new(Cursor::new(modified), index).err().unwrap();
+        assert_eq!(error.code(), "stale_index");
+    }
+
+    #[test]
+    fn test_indexed_reader_get_detects_moved_record() {
+        let records = create_test_records(5, 2000);
+        let data = write_bin(&records);
+        let index = BinIndex::build(Cursor::new(&data), false).unwrap();
+        let entry = index.by_tx_id.iter().find(|entry| entry.key == records[3].tx_id).copied().unwrap();
+
+        // The data changes after verification, under the reader
+        let mut modified = data.clone();
+        modified[entry.offset as usize + crate::HEADER_SIZE + 7] ^= 0x01;
+        let mut reader = IndexedBinReader {
+            reader: Cursor::new(modified),
+            index,
+        };
+        assert_eq!(reader.get(records[3].tx_id).unwrap_err().code(), "stale_index");
+        assert_eq!(reader.get(records[2].tx_id).unwrap().as_ref(), Some(&records[2]));
+    }
+
+    #[test]
+    fn test_index_open_rebuilds_stale_sidecar() {
+        let dir = std::env::temp_dir().join(format!("yp_bank_index_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let data_path = dir.join("records.bin");
+        let records = create_test_records(5, 2000);
+        std::fs::write(&data_path, write_bin(&records)).unwrap();
+
+        let mut reader = IndexedBinReader::open(&data_path, false).unwrap();
+        assert_eq!(reader.get(records[3].tx_id).unwrap().as_ref(), Some(&records[3]));
+        assert!(BinIndex::sidecar_path(&data_path).exists());
+
+        let changed = create_test_records(6, 3000);
+        std::fs::write(&data_path, write_bin(&changed)).unwrap();
+        let mut reader = IndexedBinReader::open(&data_path, true).unwrap();
+        assert_eq!(reader.get(changed[5].tx_id).unwrap().as_ref(), Some(&changed[5]));
+        assert!(reader.index().by_timestamp.is_some());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
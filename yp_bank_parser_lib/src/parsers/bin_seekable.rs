@@ -0,0 +1,278 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
+
+use crate::parsers::bin_format::{BinFileHeader, BinRecordReader, BinVersion, parse_frame_header};
+use crate::parsers::error::ParserError;
+use crate::parsers::reader::SourceLocation;
+use crate::parsers::types::YPBankRecord;
+use crate::{FILE_MAGIC, MAGIC};
+
+/// Number of records between remembered frame offsets.
+const CHECKPOINT_INTERVAL: u64 = 1024;
+
+/// Random-access reader over a YPBank binary file.
+///
+/// Records are located by reading only the header of each frame and seeking
+/// over its body, so reaching the N-th record costs N small reads instead of
+/// N decoded records. The offset of every 1024th frame is remembered, so
+/// later lookups only walk from the nearest known frame.
+pub struct SeekableBinReader<R> {
+    reader: R,
+    version: BinVersion,
+    header: Option<BinFileHeader>,
+    len: u64,
+    checkpoints: Vec<u64>,
+    known: (u64, u64),
+    count: Option<u64>,
+}
+
+impl<R: Read + Seek> SeekableBinReader<R> {
+    /// Creates a seekable reader, reading the file header if there is one.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader implementing the Read and Seek traits
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing the SeekableBinReader, or ParserError on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::InvalidMagic if the input is not a binary YPBank file,
+    /// or the errors of `BinFileHeader` for an unsupported version 2 header.
+    pub fn new(mut reader: R) -> Result<Self, ParserError> {
+        let len = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+
+        let mut magic = [0u8; 4];
+        let (version, header, data_start) = if len < magic.len() as u64 {
+            (BinVersion::V1, None, 0)
+        } else {
+            reader.read_exact(&mut magic)?;
+            if magic == FILE_MAGIC {
+                let (header, header_size) = BinFileHeader::read_after_magic(&mut reader)?;
+                (header.version, Some(header), header_size)
+            } else if magic == MAGIC {
+                (BinVersion::V1, None, 0)
+            } else {
+                return Err(ParserError::InvalidMagic(magic).at(SourceLocation {
+                    byte_offset: Some(0),
+                    ..SourceLocation::default()
+                }));
+            }
+        };
+
+        Ok(SeekableBinReader {
+            reader,
+            version,
+            header,
+            len,
+            checkpoints: vec![data_start],
+            known: (0, data_start),
+            count: None,
+        })
+    }
+
+    /// Returns the layout version of the file.
+    pub fn version(&self) -> BinVersion {
+        self.version
+    }
+
+    /// Returns the file header of a version 2 file.
+    pub fn header(&self) -> Option<&BinFileHeader> {
+        self.header.as_ref()
+    }
+
+    /// Reads the frame header at `offset`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the offset of the following frame, or None at end of input.
+    fn skip_frame(&mut self, offset: u64, record_index: u64) -> Result<Option<u64>, ParserError> {
+        let location = SourceLocation {
+            byte_offset: Some(offset),
+            record_index: Some(record_index),
+            ..SourceLocation::default()
+        };
+        let frame_header_size = self.version.frame_header_size();
+        if offset + frame_header_size as u64 > self.len {
+            return Ok(None);
+        }
+
+        let mut frame_header = [0u8; 16];
+        let frame_header = &mut frame_header[..frame_header_size];
+        self.reader.seek(SeekFrom::Start(offset))?;
+        self.reader.read_exact(frame_header)?;
+        let (_, frame_size) = parse_frame_header(frame_header, self.version).map_err(|e| e.at(location))?;
+
+        let next = offset + frame_size as u64;
+        if next > self.len {
+            return Err(ParserError::UnexpectedEof {
+                expected: frame_size,
+                actual: (self.len - offset) as usize,
+            }
+            .at(location));
+        }
+        Ok(Some(next))
+    }
+
+    /// Remembers that record `index` starts at `offset`.
+    fn remember(&mut self, index: u64, offset: u64) {
+        if index > self.known.0 {
+            self.known = (index, offset);
+            if index.is_multiple_of(CHECKPOINT_INTERVAL) {
+                self.checkpoints.push(offset);
+            }
+        }
+    }
+
+    /// Finds the byte offset of a record by walking frame headers from the nearest known frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Zero-based index of the record
+    ///
+    /// # Returns
+    ///
+    /// Returns the byte offset of the record, or None if the file has fewer records.
+    ///
+    /// # Errors
+    ///
+    /// Returns the framing errors of `BinRecordReader` met on the way, located at the bad frame.
+    pub fn offset_of(&mut self, index: u64) -> Result<Option<u64>, ParserError> {
+        if self.count.is_some_and(|count| index >= count) {
+            return Ok(None);
+        }
+
+        let (mut current, mut offset) = if index <= self.known.0 {
+            let checkpoint = index / CHECKPOINT_INTERVAL;
+            (checkpoint * CHECKPOINT_INTERVAL, self.checkpoints[checkpoint as usize])
+        } else {
+            self.known
+        };
+
+        loop {
+            match self.skip_frame(offset, current)? {
+                None => {
+                    self.count = Some(current);
+                    return Ok(None);
+                }
+                Some(_) if current == index => return Ok(Some(offset)),
+                Some(next) => {
+                    current += 1;
+                    offset = next;
+                    self.remember(current, offset);
+                }
+            }
+        }
+    }
+
+    /// Counts the records of the file without decoding them.
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing the number of records, or ParserError on a framing error.
+    pub fn count(&mut self) -> Result<u64, ParserError> {
+        while self.count.is_none() {
+            self.offset_of(self.known.0 + CHECKPOINT_INTERVAL)?;
+        }
+        Ok(self.count.unwrap_or_default())
+    }
+
+    /// Reads a single record by index.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Zero-based index of the record
+    ///
+    /// # Returns
+    ///
+    /// Returns the record, or None if the file has fewer records.
+    pub fn get(&mut self, index: u64) -> Result<Option<YPBankRecord>, ParserError> {
+        Ok(self.range(index..index.saturating_add(1))?.pop())
+    }
+
+    /// Reads the records with indices in `range`.
+    ///
+    /// The range is clipped to the records present in the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - Zero-based indices of the records to read
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing the records, or the first ParserError met.
+    pub fn range(&mut self, range: Range<u64>) -> Result<Vec<YPBankRecord>, ParserError> {
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        let Some(offset) = self.offset_of(range.start)? else {
+            return Ok(Vec::new());
+        };
+
+        self.reader.seek(SeekFrom::Start(offset))?;
+        let take = (range.end - range.start).min(usize::MAX as u64) as usize;
+        BinRecordReader::resume(&mut self.reader, self.version, offset, range.start)
+            .take(take)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::bin_format::{BinWriterOptions, YPBankBinParser};
+    use crate::test_helpers::create_test_records;
+    use std::io::Cursor;
+
+    fn write_bin(records: &[YPBankRecord], version: BinVersion) -> Vec<u8> {
+        let options = BinWriterOptions {
+            version,
+            checksums: version == BinVersion::V2,
+            ..BinWriterOptions::default()
+        };
+        let mut output = Vec::new();
+        YPBankBinParser::write_with_options(&mut output, records, options).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_seekable_count_get_and_range() {
+        let records = create_test_records(3000, 1900);
+        for version in [BinVersion::V1, BinVersion::V2] {
+            let mut reader = SeekableBinReader::new(Cursor::new(write_bin(&records, version))).unwrap();
+            assert_eq!(reader.version(), version);
+
+            assert_eq!(reader.get(2500).unwrap().as_ref(), Some(&records[2500]));
+            assert_eq!(reader.get(7).unwrap().as_ref(), Some(&records[7]));
+            assert_eq!(reader.range(1020..1030).unwrap(), records[1020..1030]);
+            assert_eq!(reader.range(2995..4000).unwrap(), records[2995..]);
+            assert_eq!(reader.count().unwrap(), 3000);
+            assert_eq!(reader.get(3000).unwrap(), None);
+            assert!(reader.range(3000..3010).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_seekable_empty_input() {
+        let mut reader = SeekableBinReader::new(Cursor::new(Vec::new())).unwrap();
+        assert_eq!(reader.count().unwrap(), 0);
+        assert_eq!(reader.get(0).unwrap(), None);
+    }
+
+    #[test]
+    fn test_seekable_reports_located_framing_error() {
+        let records = create_test_records(4, 1900);
+        let mut output = write_bin(&records, BinVersion::V1);
+        let second = crate::HEADER_SIZE + crate::MIN_BODY_SIZE + records[0].description.len();
+        output[second] = b'X';
+
+        let mut reader = SeekableBinReader::new(Cursor::new(output)).unwrap();
+        assert_eq!(reader.get(0).unwrap().as_ref(), Some(&records[0]));
+        let error = reader.get(3).unwrap_err();
+        assert_eq!(error.code(), "invalid_magic");
+        assert_eq!(error.location().and_then(|location| location.byte_offset), Some(second as u64));
+    }
+}
@@ -0,0 +1,320 @@
+use crate::parsers::bin_format::{BinFileHeader, BinVersion, decode_frame, parse_frame_header};
+use crate::parsers::error::ParserError;
+#[cfg(feature = "mmap")]
+use crate::parsers::parallel::{ParallelOptions, parse_bin_parallel};
+use crate::parsers::reader::SourceLocation;
+#[cfg(feature = "mmap")]
+use crate::parsers::types::YPBankRecord;
+use crate::parsers::types::YPBankRecordRef;
+use crate::{FILE_MAGIC, MAGIC};
+
+/// Zero-copy reader yielding borrowed records from a binary file held in memory.
+///
+/// Frames are decoded directly from the slice: descriptions are validated as
+/// UTF-8 in place and returned as `&str` pointing into it, so scanning does
+/// not allocate. Use `YPBankRecordRef::to_owned` to keep a record beyond the
+/// lifetime of the buffer. Errors match those of `BinRecordReader`.
+pub struct BinSliceReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    version: BinVersion,
+    header: Option<BinFileHeader>,
+    record_index: u64,
+    done: bool,
+    location: SourceLocation,
+}
+
+impl<'a> BinSliceReader<'a> {
+    /// Creates a slice reader, reading the file header if there is one.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The whole binary file
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing the BinSliceReader, or ParserError on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::InvalidMagic if the data is not a binary YPBank file,
+    /// or the errors of `BinFileHeader` for an unsupported version 2 header.
+    pub fn new(data: &'a [u8]) -> Result<Self, ParserError> {
+        let (version, header, position) = match data.get(0..4) {
+            Some(magic) if magic == FILE_MAGIC => {
+                let mut rest = &data[4..];
+                let (header, header_size) = BinFileHeader::read_after_magic(&mut rest)?;
+                (header.version, Some(header), header_size as usize)
+            }
+            Some(magic) if magic != MAGIC => {
+                return Err(ParserError::InvalidMagic(magic.try_into()?).at(SourceLocation {
+                    byte_offset: Some(0),
+                    ..SourceLocation::default()
+                }));
+            }
+            _ => (BinVersion::V1, None, 0),
+        };
+
+        Ok(BinSliceReader {
+            data,
+            position,
+            version,
+            header,
+            record_index: 0,
+            done: false,
+            location: SourceLocation::default(),
+        })
+    }
+
+    /// Creates a slice reader positioned at a frame boundary of data whose
+    /// layout version is already known.
+    ///
+    /// The record count of a file header is not checked at the end of the data.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The binary file, ending at a frame boundary
+    /// * `version` - Layout version of the file
+    /// * `position` - Byte offset of the frame in `data`
+    /// * `record_index` - Index of the record in the frame
+    pub(crate) fn resume(data: &'a [u8], version: BinVersion, position: usize, record_index: u64) -> Self {
+        BinSliceReader {
+            data,
+            position,
+            version,
+            header: None,
+            record_index,
+            done: false,
+            location: SourceLocation::default(),
+        }
+    }
+
+    /// Returns the layout version of the data.
+    pub fn version(&self) -> BinVersion {
+        self.version
+    }
+
+    /// Returns the file header of version 2 data.
+    pub fn header(&self) -> Option<&BinFileHeader> {
+        self.header.as_ref()
+    }
+
+    /// Returns the location of the most recently returned record.
+    pub fn location(&self) -> SourceLocation {
+        self.location
+    }
+
+    /// Ends the data, checking the record count declared by the file header.
+    fn end_of_input(&self) -> Option<ParserError> {
+        match self.header.as_ref().and_then(|header| header.record_count) {
+            Some(expected) if expected != self.record_index => Some(
+                ParserError::RecordCountMismatch {
+                    expected,
+                    actual: self.record_index,
+                }
+                .at(self.location),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Splits the next frame off the data.
+    ///
+    /// # Returns
+    ///
+    /// Returns the frame and its body size, or None at end of data.
+    fn next_frame(&mut self) -> Result<Option<(&'a [u8], u32)>, ParserError> {
+        let rest = &self.data[self.position..];
+        let frame_header_size = self.version.frame_header_size();
+        // A partial frame header is ignored, as by BinRecordReader
+        let Some(frame_header) = rest.get(..frame_header_size) else {
+            return Ok(None);
+        };
+
+        let (record_size, frame_size) = parse_frame_header(frame_header, self.version)?;
+        let frame = rest.get(..frame_size).ok_or(ParserError::UnexpectedEof {
+            expected: frame_size,
+            actual: rest.len(),
+        })?;
+
+        self.position += frame_size;
+        self.record_index += 1;
+        Ok(Some((frame, record_size)))
+    }
+
+    /// Splits the next frame off the data without decoding it.
+    ///
+    /// `location()` then points at the frame. Errors are those `next` reports,
+    /// except the errors of a malformed body.
+    ///
+    /// # Returns
+    ///
+    /// Returns the frame and its body size, or None at end of data.
+    pub(crate) fn split_frame(&mut self) -> Option<Result<(&'a [u8], u32), ParserError>> {
+        if self.done {
+            return None;
+        }
+
+        self.location = SourceLocation {
+            byte_offset: Some(self.position as u64),
+            record_index: Some(self.record_index),
+            ..SourceLocation::default()
+        };
+        match self.next_frame() {
+            Ok(Some(frame)) => Some(Ok(frame)),
+            Ok(None) => {
+                self.done = true;
+                self.end_of_input().map(Err)
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e.at(self.location)))
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for BinSliceReader<'a> {
+    type Item = Result<YPBankRecordRef<'a>, ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (frame, record_size) = match self.split_frame()? {
+            Ok(frame) => frame,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(decode_frame(frame, self.version, record_size, self.location).map_err(|e| e.at(self.location)))
+    }
+}
+
+/// Binary file mapped into memory, read through a `BinSliceReader`.
+///
+/// The file must not be modified by other processes while it is mapped.
+#[cfg(feature = "mmap")]
+pub struct MmapBinFile {
+    mmap: memmap2::Mmap,
+}
+
+#[cfg(feature = "mmap")]
+impl MmapBinFile {
+    /// Maps a binary file into memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the binary file
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing the MmapBinFile, or ParserError::Io on failure.
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self, ParserError> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: the mapping is read-only; as documented on the type, the file
+        // must not be truncated or rewritten while it is mapped.
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(MmapBinFile { mmap })
+    }
+
+    /// Returns the mapped bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.mmap
+    }
+
+    /// Returns a zero-copy reader over the mapped file.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `BinSliceReader::new`.
+    pub fn records(&self) -> Result<BinSliceReader<'_>, ParserError> {
+        BinSliceReader::new(&self.mmap)
+    }
+
+    /// Decodes all records of the mapped file on several threads, without copying it.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Number of threads and smallest chunk size
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `parse_bin_parallel`.
+    pub fn records_parallel(&self, options: &ParallelOptions) -> Result<Vec<YPBankRecord>, ParserError> {
+        parse_bin_parallel(&self.mmap, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::bin_format::{BinWriterOptions, YPBankBinParser};
+    use crate::parsers::types::YPBankRecord;
+    use crate::test_helpers::create_test_records;
+
+    fn write_bin(records: &[YPBankRecord], version: BinVersion) -> Vec<u8> {
+        let options = BinWriterOptions {
+            version,
+            checksums: version == BinVersion::V2,
+            ..BinWriterOptions::default()
+        };
+        let mut output = Vec::new();
+        YPBankBinParser::write_with_options(&mut output, records, options).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_slice_reader_borrows_descriptions() {
+        let records = create_test_records(20, 2100);
+        for version in [BinVersion::V1, BinVersion::V2] {
+            let data = write_bin(&records, version);
+            let reader = BinSliceReader::new(&data).unwrap();
+            assert_eq!(reader.version(), version);
+
+            let borrowed: Vec<YPBankRecordRef> = reader.collect::<Result<_, _>>().unwrap();
+            let range = data.as_ptr_range();
+            assert!(borrowed.iter().all(|record| range.contains(&record.description.as_ptr())));
+
+            let owned: Vec<YPBankRecord> = borrowed.iter().map(YPBankRecordRef::to_owned).collect();
+            assert_eq!(owned, records);
+        }
+    }
+
+    #[test]
+    fn test_slice_reader_matches_stream_errors() {
+        let records = create_test_records(3, 2100);
+        let mut data = write_bin(&records, BinVersion::V1);
+        let description_start = crate::HEADER_SIZE + crate::MIN_BODY_SIZE;
+        data[description_start] = 0xFF;
+
+        let from_slice: Vec<_> = BinSliceReader::new(&data).unwrap().collect();
+        let from_stream = YPBankBinParser::parse_bytes(&data).unwrap_err();
+        assert_eq!(from_slice.len(), 3);
+        let error = from_slice[0].as_ref().unwrap_err();
+        assert_eq!(error.code(), from_stream.code());
+        assert_eq!(error.location(), from_stream.location());
+        assert_eq!(from_slice[1].as_ref().unwrap().to_owned(), records[1]);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_mmap_reader() {
+        let path = std::env::temp_dir().join(format!("yp_bank_mmap_{}.bin", std::process::id()));
+        let records = create_test_records(10, 2100);
+        std::fs::write(&path, write_bin(&records, BinVersion::V2)).unwrap();
+
+        let file = MmapBinFile::open(&path).unwrap();
+        let owned: Vec<YPBankRecord> = file
+            .records()
+            .unwrap()
+            .map(|record| record.map(|record| record.to_owned()))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(owned, records);
+
+        let options = ParallelOptions {
+            threads: 4,
+            min_chunk_size: 64,
+        };
+        assert_eq!(file.records_parallel(&options).unwrap(), records);
+
+        drop(file);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,333 @@
+use std::io::{BufRead, Write};
+
+use crate::parsers::error::ParserError;
+
+/// Magic bytes of a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+/// Magic bytes of a zstd frame.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Stream compression applied on top of any record format.
+///
+/// Compressed input is recognised by its magic bytes, compressed output is
+/// chosen by the `.gz` or `.zst` extension of the file name. Reading and
+/// writing need the `gzip` and `zstd` features respectively.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Identifies the compression of an input from its first bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `head` - The first bytes of the input
+    pub fn detect(head: &[u8]) -> Compression {
+        if head.starts_with(&GZIP_MAGIC) {
+            Compression::Gzip
+        } else if head.starts_with(&ZSTD_MAGIC) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+
+    /// Returns the file extension of the compression, without the dot.
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some("gz"),
+            Compression::Zstd => Some("zst"),
+        }
+    }
+
+    /// Identifies the compression of a file from its extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - A string slice containing the file path
+    pub fn from_path(file_path: &str) -> Compression {
+        Self::split_path(file_path).1
+    }
+
+    /// Removes a `.gz` or `.zst` extension from a file path.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - A string slice containing the file path
+    ///
+    /// # Returns
+    ///
+    /// Returns the path of the uncompressed file, e.g. "records.bin" for "records.bin.zst".
+    pub fn strip_extension(file_path: &str) -> &str {
+        Self::split_path(file_path).0
+    }
+
+    fn split_path(file_path: &str) -> (&str, Compression) {
+        for compression in [Compression::Gzip, Compression::Zstd] {
+            let extension = compression.extension().unwrap_or_default();
+            if let Some((stem, ext)) = file_path.rsplit_once('.')
+                && ext.eq_ignore_ascii_case(extension)
+            {
+                return (stem, compression);
+            }
+        }
+        (file_path, Compression::None)
+    }
+}
+
+/// Wraps a reader in a decompressor if its content is compressed.
+///
+/// The input is peeked, not consumed, so uncompressed input is returned unchanged.
+///
+/// # Arguments
+///
+/// * `reader` - A reader implementing the BufRead trait
+///
+/// # Returns
+///
+/// Returns a reader yielding the uncompressed content.
+///
+/// # Errors
+///
+/// Returns ParserError::Io if peeking fails, or ParserError::UnsupportedFormat
+/// if the input is compressed and the matching feature is disabled.
+pub fn decompress<'a, R: BufRead + 'a>(mut reader: R) -> Result<Box<dyn BufRead + 'a>, ParserError> {
+    let compression = Compression::detect(reader.fill_buf()?);
+    decoder(reader, compression)
+}
+
+/// Wraps a reader in a decompressor for a known compression.
+///
+/// # Errors
+///
+/// Returns ParserError::UnsupportedFormat if the matching feature is disabled.
+pub(crate) fn decoder<'a, R: BufRead + 'a>(
+    reader: R,
+    compression: Compression,
+) -> Result<Box<dyn BufRead + 'a>, ParserError> {
+    match compression {
+        Compression::None => Ok(Box::new(reader)),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Ok(Box::new(std::io::BufReader::new(flate2::bufread::MultiGzDecoder::new(reader)))),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Ok(Box::new(std::io::BufReader::new(zstd::stream::read::Decoder::with_buffer(
+            reader,
+        )?))),
+        #[allow(unreachable_patterns)]
+        _ => Err(not_enabled(compression)),
+    }
+}
+
+/// Compressor of one of the supported compressions.
+enum Encoder<W: Write> {
+    None(W),
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<W>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, W>),
+}
+
+/// Writer compressing everything written to it, created by `compress`.
+///
+/// `finish` must be called after the last write: it writes the gzip trailer
+/// or the zstd end of frame and reports any error doing so. A stream dropped
+/// without `finish` is truncated.
+pub struct CompressedWriter<W: Write> {
+    encoder: Encoder<W>,
+}
+
+impl<W: Write> CompressedWriter<W> {
+    /// Returns the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        match &self.encoder {
+            Encoder::None(writer) => writer,
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(encoder) => encoder.get_ref(),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.get_ref(),
+        }
+    }
+
+    /// Completes the compressed stream and flushes the underlying writer.
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing the underlying writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::Io if the end of the stream cannot be written.
+    pub fn finish(self) -> Result<W, ParserError> {
+        // Only the None variant exists without the compression features
+        #[allow(clippy::infallible_destructuring_match)]
+        let mut writer = match self.encoder {
+            Encoder::None(writer) => writer,
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(encoder) => encoder.finish()?,
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.finish()?,
+        };
+        writer.flush()?;
+        Ok(writer)
+    }
+}
+
+impl<W: Write> Write for CompressedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match &mut self.encoder {
+            Encoder::None(writer) => writer.write(buf),
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(encoder) => encoder.write(buf),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.encoder {
+            Encoder::None(writer) => writer.flush(),
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(encoder) => encoder.flush(),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Wraps a writer in a compressor.
+///
+/// The compressed stream is only complete once `CompressedWriter::finish` has
+/// been called, so a `RecordWriter` writes to it through a mutable reference
+/// and is finished first.
+///
+/// # Arguments
+///
+/// * `writer` - A writer implementing the Write trait
+/// * `compression` - The compression to apply
+///
+/// # Returns
+///
+/// Returns a writer compressing everything written to it.
+///
+/// # Errors
+///
+/// Returns ParserError::UnsupportedFormat if the matching feature is disabled.
+pub fn compress<W: Write>(writer: W, compression: Compression) -> Result<CompressedWriter<W>, ParserError> {
+    let encoder = match compression {
+        Compression::None => Encoder::None(writer),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(writer, flate2::Compression::default())),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Encoder::Zstd(zstd::stream::write::Encoder::new(writer, 0)?),
+        #[allow(unreachable_patterns)]
+        _ => return Err(not_enabled(compression)),
+    };
+    Ok(CompressedWriter { encoder })
+}
+
+#[allow(dead_code)]
+fn not_enabled(compression: Compression) -> ParserError {
+    let feature = match compression {
+        Compression::Zstd => "zstd",
+        _ => "gzip",
+    };
+    ParserError::UnsupportedFormat(format!("{:?} compression (enable the `{}` feature)", compression, feature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_compression_from_path() {
+        assert_eq!(Compression::from_path("records.bin.zst"), Compression::Zstd);
+        assert_eq!(Compression::from_path("export.csv.GZ"), Compression::Gzip);
+        assert_eq!(Compression::from_path("export.csv"), Compression::None);
+        assert_eq!(Compression::strip_extension("records.bin.zst"), "records.bin");
+        assert_eq!(Compression::strip_extension("export.csv"), "export.csv");
+    }
+
+    #[test]
+    fn test_uncompressed_input_passes_through() {
+        let mut reader = decompress(Cursor::new(b"tx_id: 1\n".to_vec())).unwrap();
+        let mut text = String::new();
+        reader.read_line(&mut text).unwrap();
+        assert_eq!(text, "tx_id: 1\n");
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    #[test]
+    fn test_disabled_compression_is_reported() {
+        let error = decompress(Cursor::new(vec![0x1F, 0x8B, 0x08])).err().unwrap();
+        assert_eq!(error.code(), "unsupported_format");
+    }
+
+    #[cfg(all(feature = "gzip", feature = "zstd"))]
+    #[test]
+    fn test_compressed_roundtrip_all_formats() {
+        use crate::parsers::parser::Parser;
+        use crate::test_helpers::create_test_records;
+
+        let records = create_test_records(20, 2200);
+        for compression in [Compression::Gzip, Compression::Zstd] {
+            for format in ["csv", "txt", "bin"] {
+                let mut writer = compress(Vec::new(), compression).unwrap();
+                Parser::write_to(&mut writer, &records, format).unwrap();
+                let output = writer.finish().unwrap();
+                assert_eq!(Compression::detect(&output), compression);
+
+                let parsed = Parser::from_read(Cursor::new(&output), format).unwrap();
+                assert_eq!(parsed, records, "{:?} {}", compression, format);
+            }
+        }
+    }
+
+    /// Writer accepting at most `limit` bytes, failing once more are written.
+    #[cfg(all(feature = "gzip", feature = "zstd"))]
+    struct LimitedWriter {
+        written: usize,
+        limit: usize,
+    }
+
+    #[cfg(all(feature = "gzip", feature = "zstd"))]
+    impl Write for LimitedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.written + buf.len() > self.limit {
+                return Err(std::io::Error::new(std::io::ErrorKind::StorageFull, "disk full"));
+            }
+            self.written += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(all(feature = "gzip", feature = "zstd"))]
+    #[test]
+    fn test_failed_end_of_stream_is_reported() {
+        use crate::parsers::parser::Parser;
+        use crate::test_helpers::create_test_records;
+
+        let records = create_test_records(20, 2200);
+        for compression in [Compression::Gzip, Compression::Zstd] {
+            // Size of the output once all records are flushed, before the end of the stream
+            let mut writer = compress(Vec::new(), compression).unwrap();
+            Parser::write_to(&mut writer, &records, "csv").unwrap();
+            let flushed = writer.get_ref().len();
+            assert!(writer.finish().unwrap().len() > flushed);
+
+            let mut writer = compress(LimitedWriter { written: 0, limit: flushed }, compression).unwrap();
+            Parser::write_to(&mut writer, &records, "csv").unwrap();
+            assert_eq!(writer.finish().err().unwrap().code(), "io", "{:?}", compression);
+        }
+    }
+}
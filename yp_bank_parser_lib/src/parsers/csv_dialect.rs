@@ -0,0 +1,523 @@
+use crate::parsers::error::ParserError;
+use crate::parsers::reader::SourceLocation;
+use crate::parsers::types::YPBankRecord;
+
+/// A field of a YPBank record as stored in a CSV column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvField {
+    TxId,
+    TxType,
+    FromUserId,
+    ToUserId,
+    Amount,
+    Timestamp,
+    Status,
+    Description,
+}
+
+impl CsvField {
+    /// All fields in the column order of the standard YPBank CSV header.
+    pub const ALL: [CsvField; 8] = [
+        CsvField::TxId,
+        CsvField::TxType,
+        CsvField::FromUserId,
+        CsvField::ToUserId,
+        CsvField::Amount,
+        CsvField::Timestamp,
+        CsvField::Status,
+        CsvField::Description,
+    ];
+
+    /// Returns the column name used in the standard header (e.g. "TX_ID").
+    pub fn header_name(self) -> &'static str {
+        match self {
+            CsvField::TxId => "TX_ID",
+            CsvField::TxType => "TX_TYPE",
+            CsvField::FromUserId => "FROM_USER_ID",
+            CsvField::ToUserId => "TO_USER_ID",
+            CsvField::Amount => "AMOUNT",
+            CsvField::Timestamp => "TIMESTAMP",
+            CsvField::Status => "STATUS",
+            CsvField::Description => "DESCRIPTION",
+        }
+    }
+
+    /// Returns the record field name (e.g. "tx_id"), as used in error messages.
+    pub fn field_name(self) -> &'static str {
+        match self {
+            CsvField::TxId => "tx_id",
+            CsvField::TxType => "tx_type",
+            CsvField::FromUserId => "from_user_id",
+            CsvField::ToUserId => "to_user_id",
+            CsvField::Amount => "amount",
+            CsvField::Timestamp => "timestamp",
+            CsvField::Status => "status",
+            CsvField::Description => "description",
+        }
+    }
+
+    /// Returns the error reported when the field has no column.
+    fn missing_error(self) -> ParserError {
+        match self {
+            CsvField::TxId => ParserError::MissingTxId,
+            CsvField::TxType => ParserError::MissingTransactionType,
+            CsvField::FromUserId => ParserError::MissingFromUserId,
+            CsvField::ToUserId => ParserError::MissingToUserId,
+            CsvField::Amount => ParserError::MissingAmount,
+            CsvField::Timestamp => ParserError::MissingTimestamp,
+            CsvField::Status => ParserError::MissingStatus,
+            CsvField::Description => ParserError::MissingDescription,
+        }
+    }
+
+    /// Returns the position of the field in `CsvField::ALL`.
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Configuration of the CSV variant read and written by the CSV reader and writer.
+///
+/// `CsvDialect::default()` describes the standard YPBank CSV: comma-delimited,
+/// double-quoted, with the standard `TX_ID,TX_TYPE,...` header in its fixed column order.
+///
+/// # Examples
+///
+/// ```
+/// use yp_bank_parser_lib::parsers::csv_dialect::{CsvDialect, CsvField};
+///
+/// // Semicolon-delimited export with Russian column names in any order
+/// let dialect = CsvDialect {
+///     delimiter: ';',
+///     map_by_name: true,
+///     ignore_extra_columns: true,
+///     aliases: vec![("сумма".to_string(), CsvField::Amount)],
+///     ..CsvDialect::default()
+/// };
+/// assert_eq!(dialect.field_for_name(" Сумма "), Some(CsvField::Amount));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvDialect {
+    /// Character separating fields.
+    pub delimiter: char,
+    /// Character enclosing fields that contain special characters; doubled to escape it.
+    pub quote: char,
+    /// Whether the first line is a header naming the columns.
+    pub has_header: bool,
+    /// Column order used for writing, and for reading when the header is absent
+    /// or must match exactly.
+    pub columns: Vec<CsvField>,
+    /// Additional header names for fields, matched case-insensitively.
+    pub aliases: Vec<(String, CsvField)>,
+    /// Whether header columns are matched by name in any order rather than
+    /// required to follow `columns`.
+    pub map_by_name: bool,
+    /// Whether columns that do not map to a field are ignored instead of rejected.
+    pub ignore_extra_columns: bool,
+    /// Line terminator used by the writer, CRLF as in RFC 4180 by default; the
+    /// reader accepts both LF and CRLF.
+    pub line_terminator: String,
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        CsvDialect {
+            delimiter: ',',
+            quote: '"',
+            has_header: true,
+            columns: CsvField::ALL.to_vec(),
+            aliases: Vec::new(),
+            map_by_name: false,
+            ignore_extra_columns: false,
+            line_terminator: "\r\n".to_string(),
+        }
+    }
+}
+
+/// Mapping from record fields to the CSV columns holding them.
+#[derive(Debug, Clone)]
+pub(crate) struct CsvLayout {
+    positions: [Option<usize>; 8],
+    width: usize,
+    ignore_extra_columns: bool,
+}
+
+impl CsvDialect {
+    /// Looks up the field named by a header cell.
+    ///
+    /// Standard header names and aliases are matched ignoring case and
+    /// surrounding whitespace.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The header cell text
+    ///
+    /// # Returns
+    ///
+    /// Returns the field, or None if the name is unknown.
+    pub fn field_for_name(&self, name: &str) -> Option<CsvField> {
+        let name = name.trim().to_lowercase();
+        CsvField::ALL
+            .iter()
+            .copied()
+            .find(|f| f.header_name().to_lowercase() == name)
+            .or_else(|| {
+                self.aliases
+                    .iter()
+                    .find(|(alias, _)| alias.trim().to_lowercase() == name)
+                    .map(|(_, field)| *field)
+            })
+    }
+
+    /// Checks that fields can be told apart in this dialect.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::InvalidDialect if the delimiter and the quote character
+    /// are the same, or if either of them is a line break.
+    pub fn validate(&self) -> Result<(), ParserError> {
+        if self.delimiter == self.quote {
+            return Err(ParserError::InvalidDialect(format!(
+                "delimiter and quote are both {:?}",
+                self.delimiter
+            )));
+        }
+        if [self.delimiter, self.quote].iter().any(|c| matches!(c, '\n' | '\r')) {
+            return Err(ParserError::InvalidDialect(
+                "delimiter and quote must not be line breaks".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the header line for `columns`, without a line terminator.
+    pub fn header(&self) -> String {
+        self.columns
+            .iter()
+            .map(|f| f.header_name())
+            .collect::<Vec<_>>()
+            .join(&self.delimiter.to_string())
+    }
+
+    /// Returns the layout of `columns`, used when the input has no header.
+    pub(crate) fn default_layout(&self) -> CsvLayout {
+        let mut positions = [None; 8];
+        for (position, field) in self.columns.iter().enumerate() {
+            positions[field.index()].get_or_insert(position);
+        }
+        CsvLayout {
+            positions,
+            width: self.columns.len(),
+            ignore_extra_columns: self.ignore_extra_columns,
+        }
+    }
+
+    /// Resolves the column layout from a header line.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The header line, without line terminator
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::WrongCsvHeader if the header does not match the dialect,
+    /// names a field twice or, when mapping by name, lacks a field.
+    pub(crate) fn layout_from_header(&self, header: &str) -> Result<CsvLayout, ParserError> {
+        let wrong_header = || ParserError::WrongCsvHeader(header.to_string());
+        let (cells, _) = self.split_fields(header).map_err(|_| wrong_header())?;
+        let fields: Vec<Option<CsvField>> = cells.iter().map(|c| self.field_for_name(c)).collect();
+
+        if !self.map_by_name {
+            let expected = self.columns.iter().copied().map(Some);
+            let matches = if self.ignore_extra_columns {
+                fields.len() >= self.columns.len() && fields.iter().copied().zip(expected).all(|(a, b)| a == b)
+            } else {
+                fields.iter().copied().eq(expected)
+            };
+            return if matches { Ok(self.default_layout()) } else { Err(wrong_header()) };
+        }
+
+        let mut positions = [None; 8];
+        for (position, field) in fields.iter().enumerate() {
+            match field {
+                Some(field) if positions[field.index()].is_some() => return Err(wrong_header()),
+                Some(field) => positions[field.index()] = Some(position),
+                None if self.ignore_extra_columns => {}
+                None => return Err(wrong_header()),
+            }
+        }
+        if positions.contains(&None) {
+            return Err(wrong_header());
+        }
+        Ok(CsvLayout {
+            positions,
+            width: fields.len(),
+            ignore_extra_columns: self.ignore_extra_columns,
+        })
+    }
+
+    /// Splits a record into fields, unquoting them as in RFC 4180.
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - Text of one record
+    ///
+    /// # Returns
+    ///
+    /// Returns the fields together with the 1-based column where each starts.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::InvalidLine if a quoted field is not terminated.
+    pub(crate) fn split_fields(&self, s: &str) -> Result<(Vec<String>, Vec<u64>), ParserError> {
+        let mut parts = Vec::new();
+        // 1-based column where each field starts, used in error locations
+        let mut columns = vec![1u64];
+        let mut current_field = String::new();
+        let mut in_quotes = false;
+        let mut chars = s.chars().enumerate().peekable();
+
+        while let Some((index, ch)) = chars.next() {
+            if ch == self.quote {
+                if !in_quotes {
+                    in_quotes = true;
+                } else if chars.peek().is_some_and(|&(_, next)| next == self.quote) {
+                    // A doubled quote inside a quoted field is a literal quote
+                    current_field.push(ch);
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else if ch == self.delimiter && !in_quotes {
+                parts.push(std::mem::take(&mut current_field));
+                columns.push(index as u64 + 2);
+            } else {
+                current_field.push(ch);
+            }
+        }
+        parts.push(current_field);
+
+        if in_quotes {
+            return Err(ParserError::InvalidLine(format!("Unterminated quoted field: {}", s)));
+        }
+        Ok((parts, columns))
+    }
+
+    /// Parses a record laid out according to `layout`.
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - Text of one record
+    /// * `layout` - Positions of the fields
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::WrongFieldCount if the record does not have as many fields
+    /// as the layout, a Missing* error if the layout lacks a field, or
+    /// ParserError::InvalidField, located at the column of the field, if a field fails to parse.
+    pub(crate) fn parse_record(&self, s: &str, layout: &CsvLayout) -> Result<YPBankRecord, ParserError> {
+        let (parts, columns) = self.split_fields(s)?;
+
+        let count_ok = if layout.ignore_extra_columns {
+            parts.len() >= layout.width
+        } else {
+            parts.len() == layout.width
+        };
+        if !count_ok {
+            return Err(ParserError::WrongFieldCount {
+                expected: layout.width,
+                actual: parts.len(),
+            });
+        }
+
+        let field_text = |field: CsvField| -> Result<(&String, u64), ParserError> {
+            let index = layout.positions[field.index()].ok_or_else(|| field.missing_error())?;
+            Ok((&parts[index], columns[index]))
+        };
+
+        fn parse_field<T>(value: (&String, u64), field: CsvField) -> Result<T, ParserError>
+        where
+            T: std::str::FromStr,
+            T::Err: std::fmt::Display,
+        {
+            let (text, column) = value;
+            text.parse::<T>().map_err(|e| {
+                ParserError::InvalidField {
+                    field: field.field_name().to_string(),
+                    value: text.clone(),
+                    reason: e.to_string(),
+                }
+                .at(SourceLocation {
+                    column: Some(column),
+                    ..SourceLocation::default()
+                })
+            })
+        }
+
+        Ok(YPBankRecord {
+            tx_id: parse_field(field_text(CsvField::TxId)?, CsvField::TxId)?,
+            tx_type: parse_field(field_text(CsvField::TxType)?, CsvField::TxType)?,
+            from_user_id: parse_field(field_text(CsvField::FromUserId)?, CsvField::FromUserId)?,
+            to_user_id: parse_field(field_text(CsvField::ToUserId)?, CsvField::ToUserId)?,
+            amount: parse_field(field_text(CsvField::Amount)?, CsvField::Amount)?,
+            timestamp: parse_field(field_text(CsvField::Timestamp)?, CsvField::Timestamp)?,
+            status: parse_field(field_text(CsvField::Status)?, CsvField::Status)?,
+            description: field_text(CsvField::Description)?.0.clone(),
+        })
+    }
+
+    /// Formats a record as one line in `columns` order, including the line terminator.
+    ///
+    /// The description is quoted if it contains the delimiter, the quote character,
+    /// a colon or a line break, with embedded quotes doubled per RFC 4180.
+    pub(crate) fn format_record(&self, record: &YPBankRecord) -> String {
+        let description = &record.description;
+        let escaped_description = if description.contains([self.delimiter, self.quote, ',', ':', '\n', '\r']) {
+            let quote = self.quote.to_string();
+            format!(
+                "{quote}{}{quote}",
+                description.replace(self.quote, &quote.repeat(2))
+            )
+        } else {
+            description.clone()
+        };
+
+        let values: Vec<String> = self
+            .columns
+            .iter()
+            .map(|field| match field {
+                CsvField::TxId => record.tx_id.to_string(),
+                CsvField::TxType => format!("{:?}", record.tx_type),
+                CsvField::FromUserId => record.from_user_id.to_string(),
+                CsvField::ToUserId => record.to_user_id.to_string(),
+                CsvField::Amount => record.amount.to_string(),
+                CsvField::Timestamp => record.timestamp.to_string(),
+                CsvField::Status => format!("{:?}", record.status),
+                CsvField::Description => escaped_description.clone(),
+            })
+            .collect();
+
+        format!(
+            "{}{}",
+            values.join(&self.delimiter.to_string()),
+            self.line_terminator
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::csv_format::{CsvRecordReader, CsvRecordWriter};
+    use crate::parsers::writer::RecordWriter;
+    use crate::test_helpers::create_test_records;
+    use std::io::Cursor;
+
+    fn read_all(data: &str, dialect: CsvDialect) -> Result<Vec<YPBankRecord>, ParserError> {
+        CsvRecordReader::with_dialect(Cursor::new(data), dialect).collect()
+    }
+
+    #[test]
+    fn test_semicolon_dialect_roundtrip() {
+        let dialect = CsvDialect {
+            delimiter: ';',
+            line_terminator: "\r\n".to_string(),
+            ..CsvDialect::default()
+        };
+        let mut records = create_test_records(4, 300);
+        records[0].description = "Semicolon; inside".to_string();
+
+        let mut output = Vec::new();
+        let mut writer = CsvRecordWriter::with_dialect(&mut output, dialect.clone());
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.starts_with("TX_ID;TX_TYPE;"));
+        assert!(text.ends_with("\r\n"));
+        assert_eq!(read_all(&text, dialect).unwrap(), records);
+    }
+
+    #[test]
+    fn test_reordered_columns_with_aliases_and_extras() {
+        let dialect = CsvDialect {
+            delimiter: ';',
+            map_by_name: true,
+            ignore_extra_columns: true,
+            aliases: vec![
+                ("сумма".to_string(), CsvField::Amount),
+                ("описание".to_string(), CsvField::Description),
+            ],
+            ..CsvDialect::default()
+        };
+        let data = "Описание;status;Сумма;branch;tx_id;tx_type;from_user_id;to_user_id;timestamp\n\
+                    Salary;Success;1500;Moscow;7;Deposit;0;42;1700000000\n";
+
+        let records = read_all(data, dialect).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].tx_id, 7);
+        assert_eq!(records[0].amount, 1500);
+        assert_eq!(records[0].description, "Salary");
+    }
+
+    #[test]
+    fn test_default_dialect_rejects_reordered_header() {
+        let data = "TX_TYPE,TX_ID,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n";
+        let error = read_all(data, CsvDialect::default()).unwrap_err();
+        assert_eq!(error.code(), "wrong_csv_header");
+    }
+
+    #[test]
+    fn test_unknown_column_rejected_without_ignore_extra_columns() {
+        let dialect = CsvDialect {
+            map_by_name: true,
+            ..CsvDialect::default()
+        };
+        let data = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION,BRANCH\n";
+        assert_eq!(read_all(data, dialect).unwrap_err().code(), "wrong_csv_header");
+    }
+
+    #[test]
+    fn test_headerless_input_with_missing_field() {
+        let dialect = CsvDialect {
+            has_header: false,
+            columns: CsvField::ALL[..7].to_vec(),
+            ..CsvDialect::default()
+        };
+        let error = read_all("1,Deposit,0,2,100,5,Success\n", dialect).unwrap_err();
+        assert!(matches!(error.kind(), ParserError::MissingDescription));
+    }
+
+    #[test]
+    fn test_header_missing_field_rejected_when_mapping_by_name() {
+        let dialect = CsvDialect {
+            map_by_name: true,
+            ..CsvDialect::default()
+        };
+        let data = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,TIMESTAMP,STATUS,DESCRIPTION\n\
+                    1,Deposit,0,2,5,Success,First\n\
+                    2,Deposit,0,2,6,Success,Second\n";
+        let results: Vec<_> = CsvRecordReader::with_dialect(Cursor::new(data), dialect).collect();
+        assert_eq!(results.len(), 1);
+        let error = results[0].as_ref().unwrap_err();
+        assert_eq!(error.code(), "wrong_csv_header");
+        assert_eq!(error.location().unwrap().line, Some(1));
+    }
+
+    #[test]
+    fn test_dialect_with_delimiter_as_quote_rejected() {
+        let dialect = CsvDialect {
+            delimiter: '"',
+            ..CsvDialect::default()
+        };
+        assert_eq!(dialect.validate().unwrap_err().code(), "invalid_dialect");
+        let data = "TX_ID\"TX_TYPE\"FROM_USER_ID\"TO_USER_ID\"AMOUNT\"TIMESTAMP\"STATUS\"DESCRIPTION\n";
+        assert_eq!(read_all(data, dialect.clone()).unwrap_err().code(), "invalid_dialect");
+
+        let mut writer = CsvRecordWriter::with_dialect(Vec::new(), dialect);
+        let error = writer.write_record(&create_test_records(1, 300)[0]).unwrap_err();
+        assert_eq!(error.code(), "invalid_dialect");
+    }
+}
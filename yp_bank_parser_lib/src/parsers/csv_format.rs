@@ -1,14 +1,301 @@
 use std::io::{BufRead, Read, Write};
 
+use crate::parsers::csv_dialect::{CsvDialect, CsvLayout};
+use crate::parsers::detect::complete_lines;
 use crate::parsers::error::ParserError;
+use crate::parsers::format::{Format, FormatCapabilities};
+use crate::parsers::parallel::{ParallelOptions, parse_csv_parallel};
+use crate::parsers::reader::{RecordReader, RecordSource, SourceLocation};
 use crate::parsers::types::YPBankRecord;
+use crate::parsers::writer::RecordWriter;
 
 const PROPER_HEADER: &str =
     "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION";
 
+/// UTF-8 byte order mark, which is skipped if it starts the input.
+const BOM: char = '\u{feff}';
+
+/// Largest number of lines a quoted field may span before its quote is taken as unterminated.
+pub(crate) const MAX_QUOTED_LINES: usize = 1024;
+
+/// Largest size in bytes of a record with a quoted field spanning several lines.
+pub(crate) const MAX_QUOTED_BYTES: usize = 1024 * 1024;
+
+/// Reads the text of the next CSV record, following RFC 4180.
+///
+/// A record ends at the first line break outside a quoted field, so quoted
+/// fields may span several lines; line breaks inside quotes are kept as is.
+/// The terminating LF or CRLF is removed.
+///
+/// A quote still open at the end of input, or after `MAX_QUOTED_LINES` lines
+/// or `MAX_QUOTED_BYTES` bytes, is unterminated: only the line that opened it
+/// is returned, which then fails to parse, and the lines read after it are
+/// kept in `replay` so that reading resumes on the next line.
+///
+/// # Arguments
+///
+/// * `reader` - A reader implementing the BufRead trait
+/// * `quote` - Character enclosing quoted fields
+/// * `line_number` - Number of physical lines read so far, advanced by this call
+/// * `replay` - Lines to read before `reader`, last line first
+///
+/// # Returns
+///
+/// Returns the record text, or None at end of input.
+pub(crate) fn read_record_text<R: BufRead>(
+    reader: &mut R,
+    quote: char,
+    line_number: &mut u64,
+    replay: &mut Vec<String>,
+) -> Result<Option<String>, std::io::Error> {
+    let mut record = String::new();
+    // Offsets in `record` at which each line starts
+    let mut line_starts = Vec::new();
+    let mut quoted = false;
+    loop {
+        let start = record.len();
+        match replay.pop() {
+            Some(line) => record.push_str(&line),
+            None => {
+                if reader.read_line(&mut record)? == 0 {
+                    break;
+                }
+            }
+        }
+        *line_number += 1;
+        line_starts.push(start);
+
+        // Only the quotes of the new line change the parity of the record
+        quoted ^= !record[start..].chars().filter(|&c| c == quote).count().is_multiple_of(2);
+        if !quoted && record.ends_with('\n') {
+            trim_line_break(&mut record);
+            return Ok(Some(record));
+        }
+        if quoted && (line_starts.len() >= MAX_QUOTED_LINES || record.len() >= MAX_QUOTED_BYTES) {
+            break;
+        }
+    }
+
+    if line_starts.is_empty() {
+        return Ok(None);
+    }
+    if quoted {
+        // Replays every line after the one that opened the unterminated quote
+        let mut end = record.len();
+        for &start in line_starts[1..].iter().rev() {
+            replay.push(record[start..end].to_string());
+            end = start;
+        }
+        *line_number -= line_starts.len() as u64 - 1;
+        record.truncate(end);
+        trim_line_break(&mut record);
+    }
+    Ok(Some(record))
+}
+
+/// Removes a trailing LF or CRLF.
+fn trim_line_break(line: &mut String) {
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+}
+
 /// Parser for YPBank CSV format files.
 pub struct YPBankCsvParser;
 
+/// Streaming reader yielding YPBank records from a CSV source one line at a time.
+///
+/// The header is validated on the first call to `next`; a missing or wrong header
+/// is reported as the first item and ends the stream. Records follow RFC 4180:
+/// quoted fields may contain escaped `""` quotes and line breaks, lines may end
+/// with LF or CRLF, and a leading UTF-8 BOM is ignored.
+///
+/// The delimiter, quote character, header and column layout are taken from a
+/// `CsvDialect`, the standard YPBank CSV by default.
+pub struct CsvRecordReader<R> {
+    reader: R,
+    dialect: CsvDialect,
+    layout: Option<CsvLayout>,
+    done: bool,
+    line_number: u64,
+    record_index: u64,
+    location: SourceLocation,
+    raw: String,
+    replay: Vec<String>,
+}
+
+impl<R: BufRead> CsvRecordReader<R> {
+    /// Creates a new CSV record reader over the given buffered reader.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader implementing the BufRead trait
+    pub fn new(reader: R) -> Self {
+        Self::with_dialect(reader, CsvDialect::default())
+    }
+
+    /// Creates a new CSV record reader for the given dialect.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader implementing the BufRead trait
+    /// * `dialect` - The CSV variant of the input
+    pub fn with_dialect(reader: R, dialect: CsvDialect) -> Self {
+        CsvRecordReader {
+            reader,
+            dialect,
+            layout: None,
+            done: false,
+            line_number: 0,
+            record_index: 0,
+            location: SourceLocation::default(),
+            raw: String::new(),
+            replay: Vec::new(),
+        }
+    }
+
+    /// Creates a CSV record reader positioned at a record boundary after the header.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader positioned at the start of a record
+    /// * `dialect` - The CSV variant of the input
+    /// * `layout` - Column layout resolved from the header of the input
+    /// * `line_number` - Number of lines before the record
+    /// * `record_index` - Index of the record
+    pub(crate) fn resume(reader: R, dialect: CsvDialect, layout: CsvLayout, line_number: u64, record_index: u64) -> Self {
+        CsvRecordReader {
+            layout: Some(layout),
+            line_number,
+            record_index,
+            ..Self::with_dialect(reader, dialect)
+        }
+    }
+
+    /// Reads the header and returns the column layout, the number of lines
+    /// read and the reader positioned at the first record.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error that `next` reports for a missing or wrong header.
+    pub(crate) fn into_body(mut self) -> Result<(CsvLayout, u64, R), ParserError> {
+        let layout = self.read_header().map_err(|e| e.at(self.location))?;
+        Ok((layout, self.line_number, self.reader))
+    }
+
+    /// Returns the underlying reader.
+    #[cfg(feature = "async")]
+    pub(crate) fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns the lines read past an unterminated quote that are read again
+    /// before the underlying reader, in input order.
+    #[cfg(feature = "async")]
+    pub(crate) fn replayed(&self) -> impl Iterator<Item = &str> {
+        self.replay.iter().rev().map(String::as_str)
+    }
+
+    /// Reads the text of the next record, dropping a BOM at the start of the input.
+    fn read_text(&mut self) -> Result<Option<String>, std::io::Error> {
+        let at_start = self.line_number == 0;
+        let text = read_record_text(&mut self.reader, self.dialect.quote, &mut self.line_number, &mut self.replay)?;
+        Ok(text.map(|t| match t.strip_prefix(BOM) {
+            Some(stripped) if at_start => stripped.to_string(),
+            _ => t,
+        }))
+    }
+
+    /// Reads and validates the header line, if the dialect has one, and resolves the column layout.
+    fn read_header(&mut self) -> Result<CsvLayout, ParserError> {
+        self.dialect.validate()?;
+        if !self.dialect.has_header {
+            return Ok(self.dialect.default_layout());
+        }
+
+        self.location = SourceLocation {
+            line: Some(1),
+            ..SourceLocation::default()
+        };
+        let header = match self.read_text()? {
+            Some(h) => h,
+            None => return Err(ParserError::EmptyInput),
+        };
+
+        self.dialect.layout_from_header(&header).inspect_err(|_| {
+            self.raw = header.clone();
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for CsvRecordReader<R> {
+    type Item = Result<YPBankRecord, ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let layout = match self.layout.take() {
+            Some(layout) => layout,
+            None => match self.read_header() {
+                Ok(layout) => layout,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e.at(self.location)));
+                }
+            },
+        };
+
+        let first_line = self.line_number + 1;
+        let text = self.read_text();
+        let result = match text {
+            Ok(None) => None,
+            Ok(Some(line)) => {
+                self.location = SourceLocation {
+                    line: Some(first_line),
+                    record_index: Some(self.record_index),
+                    ..SourceLocation::default()
+                };
+                self.record_index += 1;
+                let record = self
+                    .dialect
+                    .parse_record(&line, &layout)
+                    .map_err(|e| e.at(self.location));
+                self.raw = line;
+                Some(record)
+            }
+            Err(e) => {
+                self.done = true;
+                self.location = SourceLocation {
+                    line: Some(self.line_number),
+                    ..SourceLocation::default()
+                };
+                Some(Err(ParserError::Io(e).at(self.location)))
+            }
+        };
+        self.layout = Some(layout);
+        result
+    }
+}
+
+impl<R: BufRead> RecordSource for CsvRecordReader<R> {
+    fn location(&self) -> SourceLocation {
+        self.location
+    }
+
+    fn raw(&self) -> &[u8] {
+        self.raw.as_bytes()
+    }
+
+    fn can_resume(&self) -> bool {
+        !self.done
+    }
+}
+
 impl YPBankCsvParser {
     /// Validates that the CSV header matches the expected format.
     ///
@@ -21,12 +308,14 @@ impl YPBankCsvParser {
     /// Returns true if the header matches the expected CSV format, false otherwise.
     fn check_header(header: &str) -> bool {
         let expected_header = PROPER_HEADER;
-        header.trim().to_uppercase() == expected_header
+        header.trim_start_matches(BOM).trim().to_uppercase() == expected_header
     }
 
     /// Reads YPBank records from a CSV format reader.
     ///
     /// Validates the CSV header and parses each line as a YPBankRecord.
+    /// Collects the output of a `CsvRecordReader`; use the reader directly
+    /// to process large inputs in constant memory.
     ///
     /// # Arguments
     ///
@@ -39,38 +328,60 @@ impl YPBankCsvParser {
     /// # Errors
     ///
     /// Returns ParserError::WrongCsvHeader if the header is invalid,
-    /// ParserError::ParseError for empty files or parsing failures.
+    /// ParserError::EmptyInput for empty files, or the parsing error of the first invalid record.
     pub fn from_read<R: Read + BufRead>(reader: R) -> Result<Vec<YPBankRecord>, ParserError> {
-        let mut lines = reader.lines();
-
-        let header = match lines.next() {
-            Some(Ok(h)) => h,
-            Some(Err(e)) => return Err(ParserError::ParseError(e.to_string())),
-            None => return Err(ParserError::ParseError("Empty file".to_string())),
-        };
+        CsvRecordReader::new(reader).collect()
+    }
 
-        if !Self::check_header(&header) {
-            return Err(ParserError::WrongCsvHeader(header.to_string()));
-        }
+    /// Reads YPBank records from a CSV format reader, parsing on several threads.
+    ///
+    /// The whole input is copied into memory and parsed by `parse_bytes_parallel`;
+    /// data already in memory or mapped should be passed to `parse_bytes_parallel`
+    /// directly. Records and parsing errors are those of `from_read`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader implementing the Read trait
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing a Vec<YPBankRecord> on success, or ParserError on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::WrongCsvHeader if the header is invalid,
+    /// ParserError::EmptyInput for empty files, the parsing error of the first invalid record,
+    /// or I/O errors during reading.
+    pub fn from_read_parallel<R: Read>(mut reader: R) -> Result<Vec<YPBankRecord>, ParserError> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Self::parse_bytes_parallel(&data)
+    }
 
-        let mut records = Vec::new();
-        for line in lines {
-            let line = match line {
-                Err(_) => {
-                    eprintln!("Error reading line");
-                    continue;
-                }
-                Ok(l) => l,
-            };
-            let record = YPBankRecord::from_string(&line)?;
-            records.push(record);
-        }
-        Ok(records)
+    /// Parses YPBank records from CSV bytes on several threads, without copying them.
+    ///
+    /// Runs `parse_csv_parallel` with the default dialect and `ParallelOptions`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Byte slice containing the whole CSV input
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing a Vec<YPBankRecord> on success, or ParserError on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::WrongCsvHeader if the header is invalid,
+    /// ParserError::EmptyInput for empty input, or the parsing error of the first invalid record.
+    pub fn parse_bytes_parallel(data: &[u8]) -> Result<Vec<YPBankRecord>, ParserError> {
+        parse_csv_parallel(data, &CsvDialect::default(), &ParallelOptions::default())
     }
 
     /// Writes YPBank records to a writer in CSV format.
     ///
-    /// Writes the CSV header followed by each record as a CSV line.
+    /// Writes the CSV header followed by each record as a CSV line,
+    /// using a `CsvRecordWriter`.
     ///
     /// # Arguments
     ///
@@ -83,23 +394,121 @@ impl YPBankCsvParser {
     ///
     /// # Errors
     ///
-    /// Returns ParserError::ParseError if no records are provided or writing fails.
-    pub fn write_to<W: Write>(mut writer: W, records: &[YPBankRecord]) -> Result<(), ParserError> {
-        if records.is_empty() {
-            return Err(ParserError::ParseError("No records to write".to_string()));
+    /// Returns ParserError::NoRecords if no records are provided, or I/O errors during writing.
+    pub fn write_to<W: Write>(writer: W, records: &[YPBankRecord]) -> Result<(), ParserError> {
+        let mut record_writer = CsvRecordWriter::new(writer);
+        for record in records {
+            record_writer.write_record(record)?;
         }
+        record_writer.finish()
+    }
+}
 
-        let header = format!("{}\n", PROPER_HEADER);
-        writer
-            .write_all(header.as_bytes())
-            .map_err(|e| ParserError::ParseError(format!("Failed to write header: {}", e)))?;
+impl Format for YPBankCsvParser {
+    fn name(&self) -> &str {
+        "csv"
+    }
 
-        for record in records {
-            record
-                .write_to(&mut writer)
-                .map_err(ParserError::ParseError)?;
+    fn extensions(&self) -> &[&str] {
+        &["csv"]
+    }
+
+    fn reader<'a>(&self, input: Box<dyn BufRead + 'a>) -> Result<RecordReader<'a>, ParserError> {
+        Ok(RecordReader::from_source(CsvRecordReader::new(input)))
+    }
+
+    fn writer<'a>(
+        &self,
+        output: Box<dyn Write + 'a>,
+    ) -> Result<Box<dyn RecordWriter + 'a>, ParserError> {
+        Ok(Box::new(CsvRecordWriter::new(output)))
+    }
+
+    fn capabilities(&self) -> FormatCapabilities {
+        FormatCapabilities {
+            read: true,
+            write: true,
+            binary: false,
+        }
+    }
+
+    /// Recognises the input by its `PROPER_HEADER` line.
+    fn sniff(&self, head: &[u8]) -> f32 {
+        match complete_lines(head).first() {
+            Some(header) if Self::check_header(header) => 1.0,
+            _ => 0.0,
         }
+    }
+}
+
+/// Incremental writer emitting YPBank records in CSV format.
+///
+/// The header is written lazily together with the first record. Delimiter,
+/// quoting, column order and line terminator follow a `CsvDialect`, the
+/// standard YPBank CSV by default.
+pub struct CsvRecordWriter<W> {
+    writer: W,
+    dialect: CsvDialect,
+    records_written: usize,
+}
+
+impl<W: Write> CsvRecordWriter<W> {
+    /// Creates a new CSV record writer over the given writer.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - A writer implementing the Write trait
+    pub fn new(writer: W) -> Self {
+        Self::with_dialect(writer, CsvDialect::default())
+    }
 
+    /// Creates a new CSV record writer for the given dialect.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - A writer implementing the Write trait
+    /// * `dialect` - The CSV variant to produce
+    pub fn with_dialect(writer: W, dialect: CsvDialect) -> Self {
+        CsvRecordWriter {
+            writer,
+            dialect,
+            records_written: 0,
+        }
+    }
+
+    /// Returns the underlying writer.
+    #[cfg(feature = "async")]
+    pub(crate) fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+}
+
+impl<W: Write> RecordWriter for CsvRecordWriter<W> {
+    fn write_record(&mut self, record: &YPBankRecord) -> Result<(), ParserError> {
+        if self.records_written == 0 {
+            self.dialect.validate()?;
+        }
+        if self.records_written == 0 && self.dialect.has_header {
+            let header = format!("{}{}", self.dialect.header(), self.dialect.line_terminator);
+            self.writer.write_all(header.as_bytes())?;
+        }
+
+        let line = self.dialect.format_record(record);
+        self.writer.write_all(line.as_bytes())?;
+        self.records_written += 1;
+        Ok(())
+    }
+
+    /// Flushes the writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::NoRecords if no records were written.
+    fn finish(&mut self) -> Result<(), ParserError> {
+        if self.records_written == 0 {
+            return Err(ParserError::NoRecords);
+        }
+        self.writer.flush()?;
         Ok(())
     }
 }
@@ -114,26 +523,35 @@ impl YPBankRecord {
     /// # Returns
     ///
     /// Returns a Result containing a Vec<YPBankRecord> on success, or ParserError on failure.
-    pub fn from_read<R: Read + BufRead>(reader: R) -> Result<Vec<YPBankRecord>, ParserError> {
+    pub fn from_read<R: Read + BufRead>(mut reader: R) -> Result<Vec<YPBankRecord>, ParserError> {
         // Implementation for reading CSV and parsing into YPBankCsvRecord structs
 
         let mut records = Vec::new();
-        for line in reader.lines() {
-            let line = match line {
-                Err(_) => {
-                    eprintln!("Error reading line");
-                    continue;
-                }
-                Ok(l) => l,
+        let mut line_number = 0;
+        let mut replay = Vec::new();
+        loop {
+            let first_line = line_number + 1;
+            let line = match read_record_text(&mut reader, '"', &mut line_number, &mut replay)? {
+                Some(l) => l,
+                None => break,
             };
-            let record = Self::from_string(&line)?;
+            let record = Self::from_string(&line).map_err(|e| {
+                e.at(SourceLocation {
+                    line: Some(first_line),
+                    record_index: Some(records.len() as u64),
+                    ..SourceLocation::default()
+                })
+            })?;
             records.push(record);
         }
 
         Ok(records)
     }
 
-    /// Parses a YPBankRecord from a CSV string.
+    /// Parses a YPBankRecord from a CSV string in the default `CsvDialect`.
+    ///
+    /// Fields may be quoted as in RFC 4180: a quoted field may contain commas,
+    /// line breaks and quotes escaped as `""`.
     ///
     /// # Arguments
     ///
@@ -145,84 +563,17 @@ impl YPBankRecord {
     ///
     /// # Errors
     ///
-    /// Returns ParserError::ParseError if the string format is invalid or parsing fails.
+    /// Returns ParserError::WrongFieldCount if the line does not have 8 fields, or
+    /// ParserError::InvalidField, located at the column of the field, if a field fails to parse.
     pub fn from_string(s: &str) -> Result<Self, ParserError> {
-        let mut parts = Vec::new();
-        let mut current_field = String::new();
-        let mut in_quotes = false;
-        
-        for ch in s.chars() {
-            match ch {
-                '"' => {
-                    in_quotes = !in_quotes;
-                }
-                ',' if !in_quotes => {
-                    parts.push(current_field.clone());
-                    current_field.clear();
-                }
-                _ => {
-                    current_field.push(ch);
-                }
-            }
-        }
-        parts.push(current_field);
-        
-        if parts.len() == 8 {
-            Ok(YPBankRecord {
-                tx_id: parts[0].parse().map_err(|e| {
-                    ParserError::ParseError(format!(
-                        "Failed to parse tx_id: {} error: {}",
-                        parts[0], e
-                    ))
-                })?,
-                tx_type: parts[1].parse().map_err(|e| {
-                    ParserError::ParseError(format!(
-                        "Failed to parse tx_type: {} error: {}",
-                        parts[1], e
-                    ))
-                })?,
-                from_user_id: parts[2].parse().map_err(|e| {
-                    ParserError::ParseError(format!(
-                        "Failed to parse from_user_id: {} error: {}",
-                        parts[2], e
-                    ))
-                })?,
-                to_user_id: parts[3].parse().map_err(|e| {
-                    ParserError::ParseError(format!(
-                        "Failed to parse to_user_id: {} error: {}",
-                        parts[3], e
-                    ))
-                })?,
-                amount: parts[4].parse().map_err(|e| {
-                    ParserError::ParseError(format!(
-                        "Failed to parse amount: {} error: {}",
-                        parts[4], e
-                    ))
-                })?,
-                timestamp: parts[5].parse().map_err(|e| {
-                    ParserError::ParseError(format!(
-                        "Failed to parse timestamp: {} error: {}",
-                        parts[5], e
-                    ))
-                })?,
-                status: parts[6].parse().map_err(|e| {
-                    ParserError::ParseError(format!(
-                        "Failed to parse status: {} error: {}",
-                        parts[6], e
-                    ))
-                })?,
-                description: parts[7].to_string(),
-            })
-        } else {
-            eprintln!("Invalid record, expect 8 fields, got: {}", s);
-            Err(ParserError::ParseError(format!(
-                "Invalid record, expect 8 fields, got: {}",
-                s
-            )))
-        }
+        let dialect = CsvDialect::default();
+        dialect.parse_record(s, &dialect.default_layout())
     }
 
-    /// Writes this YPBankRecord to a writer in CSV format.
+    /// Writes this YPBankRecord to a writer in the default `CsvDialect`.
+    ///
+    /// The description is quoted if it contains a comma, colon, quote or line
+    /// break, with embedded quotes escaped as `""` per RFC 4180; the line ends with CRLF.
     ///
     /// # Arguments
     ///
@@ -230,28 +581,10 @@ impl YPBankRecord {
     ///
     /// # Returns
     ///
-    /// Returns a Result with () on success, or String error message on failure.
-    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), String> {
-        let escaped_description = if self.description.contains(',') || self.description.contains(':') {
-            format!("\"{}\"", self.description)
-        } else {
-            self.description.clone()
-        };
-        
-        let record = format!(
-            "{},{:?},{},{},{},{},{:?},{}\n",
-            self.tx_id,
-            self.tx_type,
-            self.from_user_id,
-            self.to_user_id,
-            self.amount,
-            self.timestamp,
-            self.status,
-            escaped_description
-        );
-        writer
-            .write_all(record.as_bytes())
-            .map_err(|e| format!("Failed to write record: {}", e))?;
+    /// Returns a Result with () on success, or ParserError::Io on failure.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), ParserError> {
+        let record = CsvDialect::default().format_record(self);
+        writer.write_all(record.as_bytes())?;
         Ok(())
     }
 }
@@ -339,4 +672,150 @@ mod tests {
         let parsed_record = YPBankRecord::from_string(csv_line.trim()).unwrap();
         assert_eq!(original_record.description, parsed_record.description);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_csv_parse_invalid_field() {
+        let csv_line = "123,Deposit,456,789,abc,1640995200,Success,\"Payment\"";
+        let error = YPBankRecord::from_string(csv_line).unwrap_err();
+        assert_eq!(error.code(), "invalid_field");
+        match error.kind() {
+            ParserError::InvalidField { field, value, .. } => {
+                assert_eq!(field, "amount");
+                assert_eq!(value, "abc");
+            }
+            other => panic!("Unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_csv_parse_wrong_field_count() {
+        let error = YPBankRecord::from_string("123,Deposit,456").unwrap_err();
+        assert_eq!(error.code(), "wrong_field_count");
+        assert!(matches!(
+            error.kind(),
+            ParserError::WrongFieldCount { expected: 8, actual: 3 }
+        ));
+    }
+
+    #[test]
+    fn test_csv_writer_without_records_is_no_records() {
+        let mut writer = CsvRecordWriter::new(Vec::new());
+        let error = writer.finish().unwrap_err();
+        assert_eq!(error.code(), "no_records");
+    }
+
+    fn record_with_description(description: &str) -> YPBankRecord {
+        YPBankRecord {
+            tx_id: 7,
+            tx_type: TransactionType::Withdrawal,
+            from_user_id: 1,
+            to_user_id: 2,
+            amount: -300,
+            timestamp: 1640995200,
+            status: Status::Pending,
+            description: description.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_csv_parse_escaped_quotes() {
+        let csv_line = "123,Deposit,456,789,1000,1640995200,Success,\"Refund for \"\"Premium\"\" plan\"";
+        let record = YPBankRecord::from_string(csv_line).unwrap();
+        assert_eq!(record.description, "Refund for \"Premium\" plan");
+    }
+
+    #[test]
+    fn test_csv_parse_unterminated_quote() {
+        let csv_line = "123,Deposit,456,789,1000,1640995200,Success,\"Open quote";
+        let error = YPBankRecord::from_string(csv_line).unwrap_err();
+        assert_eq!(error.code(), "invalid_line");
+    }
+
+    #[test]
+    fn test_csv_unterminated_quote_resumes_on_next_line() {
+        let csv_data = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n\
+                        1,Deposit,0,2,100,5,Success,\"Open quote\n\
+                        2,Deposit,0,2,200,5,Success,Second\n\
+                        3,Deposit,0,2,300,5,Success,\"Third, quoted\"\n";
+        let reader = CsvRecordReader::new(std::io::Cursor::new(csv_data));
+        let result = RecordReader::from_source(reader).collect_lenient(10).unwrap();
+
+        assert_eq!(result.records.iter().map(|r| r.tx_id).collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(result.records[1].description, "Third, quoted");
+        assert_eq!(result.rejects.len(), 1);
+        assert_eq!(result.rejects[0].error.code(), "invalid_line");
+        assert_eq!(result.rejects[0].location.line, Some(2));
+        assert_eq!(result.rejects[0].raw_text(), "1,Deposit,0,2,100,5,Success,\"Open quote");
+    }
+
+    #[test]
+    fn test_csv_unterminated_quote_line_limit() {
+        let mut csv_data = String::from("TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n");
+        csv_data.push_str("1,Deposit,0,2,100,5,Success,\"Open quote\n");
+        for tx_id in 2..=MAX_QUOTED_LINES as u64 + 1 {
+            csv_data.push_str(&format!("{},Deposit,0,2,100,5,Success,Line\n", tx_id));
+        }
+        csv_data.push_str("0,Deposit,0,2,100,5,Success,Closing\"\n");
+
+        let mut line_number = 1;
+        let mut replay = Vec::new();
+        let mut reader = std::io::Cursor::new(&csv_data.as_bytes()[csv_data.find('\n').unwrap() + 1..]);
+        let first = read_record_text(&mut reader, '"', &mut line_number, &mut replay).unwrap();
+        assert_eq!(first.as_deref(), Some("1,Deposit,0,2,100,5,Success,\"Open quote"));
+        assert_eq!(line_number, 2);
+        assert_eq!(replay.len(), MAX_QUOTED_LINES - 1);
+        let second = read_record_text(&mut reader, '"', &mut line_number, &mut replay).unwrap();
+        assert_eq!(second.as_deref(), Some("2,Deposit,0,2,100,5,Success,Line"));
+        assert_eq!(line_number, 3);
+    }
+
+    #[test]
+    fn test_csv_roundtrip_quotes_and_line_breaks() {
+        let records = vec![
+            record_with_description("Refund for \"Premium\" plan"),
+            record_with_description("Multi-line\nmemo, with comma"),
+            record_with_description("Windows\r\nline break"),
+            record_with_description("\"\""),
+            record_with_description(""),
+        ];
+
+        let mut output = Vec::new();
+        YPBankCsvParser::write_to(&mut output, &records).unwrap();
+        let parsed = YPBankCsvParser::from_read(std::io::Cursor::new(output)).unwrap();
+        assert_eq!(parsed, records);
+    }
+
+    #[test]
+    fn test_csv_writer_uses_crlf() {
+        let records = vec![record_with_description("First"), record_with_description("Second\nline")];
+        let mut output = Vec::new();
+        YPBankCsvParser::write_to(&mut output, &records).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\r\n\
+             7,Withdrawal,1,2,-300,1640995200,Pending,First\r\n\
+             7,Withdrawal,1,2,-300,1640995200,Pending,\"Second\nline\"\r\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_read_crlf_and_bom() {
+        let csv_data = "\u{feff}TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\r\n\
+                        1,Deposit,0,2,100,5,Success,First\r\n\
+                        2,Transfer,2,3,50,6,Pending,\"Second\r\nline\"\r\n";
+        let records = YPBankCsvParser::from_read(std::io::Cursor::new(csv_data)).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].description, "First");
+        assert_eq!(records[1].description, "Second\r\nline");
+    }
+
+    #[test]
+    fn test_csv_multi_line_record_location() {
+        let csv_data = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n\
+                        1,Deposit,0,2,100,5,Success,\"Spans\ntwo lines\"\n\
+                        2,Deposit,0,2,oops,5,Success,Bad\n";
+        let error = YPBankCsvParser::from_read(std::io::Cursor::new(csv_data)).unwrap_err();
+        assert_eq!(error.location().unwrap().line, Some(4));
+        assert_eq!(error.location().unwrap().record_index, Some(1));
+    }
+}
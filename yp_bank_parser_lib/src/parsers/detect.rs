@@ -0,0 +1,146 @@
+use std::io::BufRead;
+
+use crate::parsers::error::ParserError;
+use crate::parsers::format::FormatRegistry;
+
+/// Minimum confidence at which a detection should be trusted without other hints.
+pub const DETECTION_THRESHOLD: f32 = 0.5;
+
+/// Result of content-based format detection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    /// Name of the detected format.
+    pub format: String,
+    /// Confidence between 0.0 and 1.0.
+    pub confidence: f32,
+}
+
+impl Detection {
+    /// Returns true if the confidence reaches `DETECTION_THRESHOLD`.
+    pub fn is_confident(&self) -> bool {
+        self.confidence >= DETECTION_THRESHOLD
+    }
+}
+
+impl FormatRegistry {
+    /// Identifies the format of the given leading bytes of an input.
+    ///
+    /// Every registered format is asked to sniff the bytes and the one with the
+    /// highest confidence wins.
+    ///
+    /// # Arguments
+    ///
+    /// * `head` - The first bytes of the input
+    ///
+    /// # Returns
+    ///
+    /// Returns the best Detection, or None if no format recognises the data.
+    pub fn detect(&self, head: &[u8]) -> Option<Detection> {
+        self.names()
+            .into_iter()
+            .filter_map(|name| {
+                let confidence = self.get(&name)?.sniff(head);
+                Some(Detection {
+                    format: name,
+                    confidence,
+                })
+            })
+            .filter(|d| d.confidence > 0.0)
+            .max_by(|a, b| a.confidence.total_cmp(&b.confidence))
+    }
+}
+
+/// Identifies the format of a buffered input without consuming it.
+///
+/// Peeks at the currently buffered bytes of the reader (filling the buffer if
+/// it is empty) and asks the process-wide `FormatRegistry` to recognise them.
+///
+/// # Arguments
+///
+/// * `reader` - A reader implementing the BufRead trait
+///
+/// # Returns
+///
+/// Returns the best Detection, or None if the format could not be recognised.
+///
+/// # Errors
+///
+/// Returns ParserError::Io if filling the buffer fails.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use yp_bank_parser_lib::parsers::detect::detect_format;
+///
+/// let mut reader = Cursor::new("tx_id: 1\ntx_type: Deposit\n");
+/// let detection = detect_format(&mut reader).unwrap().unwrap();
+/// assert_eq!(detection.format, "txt");
+/// ```
+pub fn detect_format<R: BufRead + ?Sized>(reader: &mut R) -> Result<Option<Detection>, ParserError> {
+    let head = reader.fill_buf()?;
+    Ok(FormatRegistry::read_global().detect(head))
+}
+
+/// Returns the complete lines contained in a possibly truncated buffer.
+///
+/// The last line is dropped if the buffer does not end with a newline, unless
+/// it is the only line available.
+pub(crate) fn complete_lines(head: &[u8]) -> Vec<String> {
+    let text = String::from_utf8_lossy(head);
+    let mut lines: Vec<String> = text.lines().map(|l| l.to_string()).collect();
+    if lines.len() > 1 && !text.ends_with('\n') {
+        lines.pop();
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::parser::Parser;
+    use crate::test_helpers::create_test_records;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_detect_written_formats() {
+        let records = create_test_records(4, 700);
+
+        for format in ["csv", "txt", "bin"] {
+            let mut output = Vec::new();
+            Parser::write_to(&mut output, &records, format).unwrap();
+
+            let mut reader = Cursor::new(output);
+            let detection = detect_format(&mut reader).unwrap().unwrap();
+            assert_eq!(detection.format, format);
+            assert!(detection.is_confident());
+
+            // Detection must not consume any input
+            assert_eq!(Parser::from_read(reader, format).unwrap(), records);
+        }
+    }
+
+    #[test]
+    fn test_detect_unknown_content() {
+        let mut reader = Cursor::new("<xml>data</xml>");
+        assert_eq!(detect_format(&mut reader).unwrap(), None);
+
+        let mut reader = Cursor::new("");
+        assert_eq!(detect_format(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn test_detect_truncated_bin_magic() {
+        let registry = FormatRegistry::default();
+        assert_eq!(registry.detect(&[0x59, 0x50]), None);
+        assert_eq!(registry.detect(&[0x59, 0x50, 0x42, 0x4E]).unwrap().format, "bin");
+    }
+
+    #[test]
+    fn test_detect_txt_with_unknown_keys_is_not_confident() {
+        let registry = FormatRegistry::default();
+        let detection = registry.detect(b"# comment\nfoo: bar\ntx_id: 1\nbaz: qux\n").unwrap();
+        assert_eq!(detection.format, "txt");
+        assert!(!detection.is_confident());
+    }
+}
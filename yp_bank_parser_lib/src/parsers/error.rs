@@ -1,3 +1,5 @@
+use crate::parsers::reader::SourceLocation;
+
 #[derive(Debug, thiserror::Error)]
 pub enum ParserError {
     #[error("IO error: {0}")]
@@ -6,8 +8,35 @@ pub enum ParserError {
     Utf8Error(#[from] std::string::FromUtf8Error),
     #[error("Try From Slice error: {0}")]
     TryFromSliceError(#[from] std::array::TryFromSliceError),
-    #[error("Parse Error: {0}")]    
-    ParseError(String),    
+    /// Generic parse failure, intended for formats registered by downstream crates.
+    #[error("Parse Error: {0}")]
+    ParseError(String),
+    #[error("Invalid value {value:?} for field {field}: {reason}")]
+    InvalidField {
+        field: String,
+        value: String,
+        reason: String,
+    },
+    #[error("Wrong field count: expected {expected}, got {actual}")]
+    WrongFieldCount { expected: usize, actual: usize },
+    #[error("Duplicate key: {0}")]
+    DuplicateKey(String),
+    #[error("Unknown key: {0}")]
+    UnknownKey(String),
+    #[error("Invalid line format: {0}")]
+    InvalidLine(String),
+    #[error("Invalid JSON: {0}")]
+    InvalidJson(String),
+    #[error("Invalid import profile: {0}")]
+    InvalidProfile(String),
+    #[error("Invalid CSV dialect: {0}")]
+    InvalidDialect(String),
+    #[error("Empty input")]
+    EmptyInput,
+    #[error("No records to write")]
+    NoRecords,
+    #[error("Record has {0} unread trailing bytes")]
+    TrailingBytes(u64),
     #[error("Wrong CSV Header: {0}")]
     WrongCsvHeader(String),
     #[error("Unsupported fromat: {0}")]
@@ -41,5 +70,142 @@ pub enum ParserError {
     #[error("Record size too small: {0} bytes (minimum: {1})")]
     RecordTooSmall(u32, usize),
     #[error("Record size too large: {0} bytes (maximum: {1})")]
-    RecordTooLarge(u32, usize),   
+    RecordTooLarge(u32, usize),
+    #[error("Invalid binary file header: {0}")]
+    InvalidFileHeader(String),
+    #[error("Unsupported binary format version: {0}")]
+    UnsupportedBinVersion(u16),
+    #[error("Unsupported binary format flags: {0:#06x}")]
+    UnsupportedBinFlags(u16),
+    #[error("Checksum mismatch in record {record_index} at byte offset {offset}")]
+    ChecksumMismatch { record_index: u64, offset: u64 },
+    #[error("Record count mismatch: header declares {expected}, found {actual}")]
+    RecordCountMismatch { expected: u64, actual: u64 },
+    #[error("Invalid index file: {0}")]
+    InvalidIndex(String),
+    #[error("Index does not match the data file: {0}")]
+    StaleIndex(String),
+    #[error("Error budget exceeded: more than {0} records rejected")]
+    ErrorBudgetExceeded(usize),
+    /// Wraps an error with the place in the source where it occurred.
+    ///
+    /// Readers return most errors wrapped in this variant, so match on
+    /// `kind()` (or compare `code()`) rather than on the error itself:
+    /// `matches!(e.kind(), ParserError::MissingAmount)`.
+    #[error("{source} (at {location})")]
+    Located {
+        location: SourceLocation,
+        source: Box<ParserError>,
+    },
+}
+
+impl ParserError {
+    /// Attaches a source location to this error.
+    ///
+    /// If the error already carries a location, only the fields it does not
+    /// set are taken from `location`.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` - Where in the source the error occurred
+    pub fn at(self, location: SourceLocation) -> ParserError {
+        match self {
+            ParserError::Located {
+                location: own,
+                source,
+            } => ParserError::Located {
+                location: own.or(location),
+                source,
+            },
+            other => ParserError::Located {
+                location,
+                source: Box::new(other),
+            },
+        }
+    }
+
+    /// Returns the source location of this error, if known.
+    pub fn location(&self) -> Option<&SourceLocation> {
+        match self {
+            ParserError::Located { location, .. } => Some(location),
+            _ => None,
+        }
+    }
+
+    /// Returns the underlying error without its location.
+    pub fn kind(&self) -> &ParserError {
+        match self {
+            ParserError::Located { source, .. } => source.kind(),
+            other => other,
+        }
+    }
+
+    /// Returns a stable, machine-readable code identifying the kind of error.
+    ///
+    /// The code ignores any attached location and does not change between releases,
+    /// so it can be used to classify errors without matching on messages.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use yp_bank_parser_lib::parsers::error::ParserError;
+    /// use yp_bank_parser_lib::parsers::reader::SourceLocation;
+    ///
+    /// assert_eq!(ParserError::MissingAmount.code(), "missing_amount");
+    /// let located = ParserError::MissingAmount.at(SourceLocation::default());
+    /// assert_eq!(located.code(), "missing_amount");
+    /// ```
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParserError::Io(_) => "io",
+            ParserError::Utf8Error(_) => "invalid_utf8",
+            ParserError::TryFromSliceError(_) => "invalid_slice",
+            ParserError::ParseError(_) => "parse_error",
+            ParserError::InvalidField { .. } => "invalid_field",
+            ParserError::WrongFieldCount { .. } => "wrong_field_count",
+            ParserError::DuplicateKey(_) => "duplicate_key",
+            ParserError::UnknownKey(_) => "unknown_key",
+            ParserError::InvalidLine(_) => "invalid_line",
+            ParserError::InvalidJson(_) => "invalid_json",
+            ParserError::InvalidProfile(_) => "invalid_profile",
+            ParserError::InvalidDialect(_) => "invalid_dialect",
+            ParserError::EmptyInput => "empty_input",
+            ParserError::NoRecords => "no_records",
+            ParserError::TrailingBytes(_) => "trailing_bytes",
+            ParserError::WrongCsvHeader(_) => "wrong_csv_header",
+            ParserError::UnsupportedFormat(_) => "unsupported_format",
+            ParserError::MissingTxId => "missing_tx_id",
+            ParserError::MissingFromUserId => "missing_from_user_id",
+            ParserError::MissingToUserId => "missing_to_user_id",
+            ParserError::MissingAmount => "missing_amount",
+            ParserError::MissingTimestamp => "missing_timestamp",
+            ParserError::MissingStatus => "missing_status",
+            ParserError::MissingTransactionType => "missing_tx_type",
+            ParserError::MissingDescription => "missing_description",
+            ParserError::WrongTransactionType(_) => "wrong_tx_type",
+            ParserError::WrongStatusType(_) => "wrong_status",
+            ParserError::UnexpectedEof { .. } => "unexpected_eof",
+            ParserError::DescriptionOverflow { .. } => "description_overflow",
+            ParserError::InvalidMagic(_) => "invalid_magic",
+            ParserError::RecordTooSmall(_, _) => "record_too_small",
+            ParserError::RecordTooLarge(_, _) => "record_too_large",
+            ParserError::InvalidFileHeader(_) => "invalid_file_header",
+            ParserError::UnsupportedBinVersion(_) => "unsupported_bin_version",
+            ParserError::UnsupportedBinFlags(_) => "unsupported_bin_flags",
+            ParserError::ChecksumMismatch { .. } => "checksum_mismatch",
+            ParserError::RecordCountMismatch { .. } => "record_count_mismatch",
+            ParserError::InvalidIndex(_) => "invalid_index",
+            ParserError::StaleIndex(_) => "stale_index",
+            ParserError::ErrorBudgetExceeded(_) => "error_budget_exceeded",
+            ParserError::Located { source, .. } => source.code(),
+        }
+    }
+
+    /// Consumes the error, returning it without its location.
+    pub fn into_kind(self) -> ParserError {
+        match self {
+            ParserError::Located { source, .. } => source.into_kind(),
+            other => other,
+        }
+    }
 }
@@ -0,0 +1,366 @@
+use std::io::{BufRead, Write};
+use std::sync::{Arc, OnceLock, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::parsers::bin_block::YPBankBlockParser;
+use crate::parsers::bin_format::YPBankBinParser;
+use crate::parsers::compression::{Compression, decompress};
+use crate::parsers::csv_format::YPBankCsvParser;
+use crate::parsers::error::ParserError;
+#[cfg(feature = "json")]
+use crate::parsers::json_format::{YPBankJsonParser, YPBankJsonlParser};
+use crate::parsers::reader::RecordReader;
+use crate::parsers::txt_format::YPBankTxtParser;
+use crate::parsers::writer::RecordWriter;
+
+/// Name of the format used when a file extension is not recognised.
+pub const DEFAULT_FORMAT: &str = "csv";
+
+/// Operations and properties supported by a format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatCapabilities {
+    /// Records can be read from this format.
+    pub read: bool,
+    /// Records can be written in this format.
+    pub write: bool,
+    /// The format is binary rather than human-readable text.
+    pub binary: bool,
+}
+
+/// A record format that can be plugged into a `FormatRegistry`.
+///
+/// Implement this trait to make an in-house format available to `Parser`,
+/// `extract_format` and the CLI utilities.
+pub trait Format: Send + Sync {
+    /// Unique, lower-case name of the format (e.g. "csv").
+    fn name(&self) -> &str;
+
+    /// File extensions, without the leading dot, associated with the format.
+    fn extensions(&self) -> &[&str];
+
+    /// Creates a streaming record reader over the given input.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - A boxed reader implementing the BufRead trait
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing a RecordReader, or ParserError on failure.
+    fn reader<'a>(&self, input: Box<dyn BufRead + 'a>) -> Result<RecordReader<'a>, ParserError>;
+
+    /// Creates an incremental record writer over the given output.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - A boxed writer implementing the Write trait
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing a boxed RecordWriter, or ParserError on failure.
+    fn writer<'a>(
+        &self,
+        output: Box<dyn Write + 'a>,
+    ) -> Result<Box<dyn RecordWriter + 'a>, ParserError>;
+
+    /// Describes what the format supports.
+    fn capabilities(&self) -> FormatCapabilities;
+
+    /// Estimates how likely it is that the input is in this format.
+    ///
+    /// # Arguments
+    ///
+    /// * `head` - The first bytes of the input; may be truncated at any point
+    ///
+    /// # Returns
+    ///
+    /// Returns a confidence between 0.0 (not this format) and 1.0 (certainly this format).
+    /// The default implementation never claims the input.
+    fn sniff(&self, _head: &[u8]) -> f32 {
+        0.0
+    }
+}
+
+/// Registry of known formats, looked up by name or file extension.
+///
+/// `FormatRegistry::default()` is pre-populated with the built-in csv, txt,
+/// bin and binz formats, plus json and jsonl with the `json` feature. A process-wide registry used by `Parser` and `extract_format`
+/// is available through `FormatRegistry::global()`.
+pub struct FormatRegistry {
+    formats: Vec<Arc<dyn Format>>,
+}
+
+impl FormatRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        FormatRegistry {
+            formats: Vec::new(),
+        }
+    }
+
+    /// Creates a registry containing the built-in csv, txt, bin and binz formats,
+    /// plus json and jsonl with the `json` feature.
+    pub fn with_builtin_formats() -> Self {
+        let mut registry = Self::new();
+        registry.register(YPBankCsvParser);
+        registry.register(YPBankTxtParser);
+        registry.register(YPBankBinParser::default());
+        registry.register(YPBankBlockParser);
+        #[cfg(feature = "json")]
+        {
+            registry.register(YPBankJsonParser);
+            registry.register(YPBankJsonlParser);
+        }
+        registry
+    }
+
+    /// Returns the process-wide registry used by `Parser` and `extract_format`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use yp_bank_parser_lib::parsers::format::FormatRegistry;
+    ///
+    /// assert!(FormatRegistry::read_global().get("csv").is_some());
+    /// ```
+    pub fn global() -> &'static RwLock<FormatRegistry> {
+        static GLOBAL: OnceLock<RwLock<FormatRegistry>> = OnceLock::new();
+        GLOBAL.get_or_init(|| RwLock::new(FormatRegistry::with_builtin_formats()))
+    }
+
+    /// Locks the process-wide registry for reading.
+    ///
+    /// A registry poisoned by a panicking writer is still returned, since
+    /// registration never leaves it half-updated.
+    pub fn read_global() -> RwLockReadGuard<'static, FormatRegistry> {
+        Self::global().read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Locks the process-wide registry for writing, recovering from poisoning like `read_global`.
+    pub fn write_global() -> RwLockWriteGuard<'static, FormatRegistry> {
+        Self::global().write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Registers a format, replacing any format previously registered under the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - The format to register
+    pub fn register<F: Format + 'static>(&mut self, format: F) {
+        let format: Arc<dyn Format> = Arc::new(format);
+        self.formats
+            .retain(|f| !f.name().eq_ignore_ascii_case(format.name()));
+        self.formats.push(format);
+    }
+
+    /// Looks up a format by name, ignoring case.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Format name (e.g. "csv")
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Format>> {
+        self.formats
+            .iter()
+            .find(|f| f.name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+
+    /// Looks up a format by file extension, ignoring case.
+    ///
+    /// # Arguments
+    ///
+    /// * `extension` - File extension without the leading dot (e.g. "bin")
+    pub fn by_extension(&self, extension: &str) -> Option<Arc<dyn Format>> {
+        self.formats
+            .iter()
+            .find(|f| f.extensions().iter().any(|e| e.eq_ignore_ascii_case(extension)))
+            .cloned()
+    }
+
+    /// Looks up a format by the extension of a file path.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - A string slice containing the file path
+    ///
+    /// # Returns
+    ///
+    /// Returns the matching format, or None if the path has no known extension.
+    /// A trailing `.gz` or `.zst` extension is ignored, so "records.bin.zst" is "bin".
+    pub fn format_for_path(&self, file_path: &str) -> Option<Arc<dyn Format>> {
+        let file_path = Compression::strip_extension(file_path);
+        let split_path: Vec<&str> = file_path.split(".").collect();
+        if split_path.len() > 1
+            && let Some(ext) = split_path.last()
+        {
+            return self.by_extension(ext);
+        }
+        None
+    }
+
+    /// Extracts the format name from a file path based on its extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - A string slice containing the file path
+    ///
+    /// # Returns
+    ///
+    /// Returns the name of the matching format, or `DEFAULT_FORMAT` for unknown extensions.
+    pub fn extract_format(&self, file_path: &str) -> String {
+        self.format_for_path(file_path)
+            .map(|f| f.name().to_string())
+            .unwrap_or_else(|| DEFAULT_FORMAT.to_string())
+    }
+
+    /// Returns the names of all registered formats in registration order.
+    pub fn names(&self) -> Vec<String> {
+        self.formats.iter().map(|f| f.name().to_string()).collect()
+    }
+
+    /// Creates a streaming record reader for the named format.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader implementing the BufRead trait
+    /// * `format` - Name of a registered format
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::UnsupportedFormat if no such format is registered.
+    pub fn reader<'a, R: BufRead + 'a>(
+        &self,
+        reader: R,
+        format: &str,
+    ) -> Result<RecordReader<'a>, ParserError> {
+        let format_impl = self
+            .get(format)
+            .ok_or_else(|| ParserError::UnsupportedFormat(format.to_string()))?;
+        format_impl.reader(decompress(reader)?)
+    }
+
+    /// Creates an incremental record writer for the named format.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - A writer implementing the Write trait
+    /// * `format` - Name of a registered format
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::UnsupportedFormat if no such format is registered.
+    pub fn writer<'a, W: Write + 'a>(
+        &self,
+        writer: W,
+        format: &str,
+    ) -> Result<Box<dyn RecordWriter + 'a>, ParserError> {
+        let format_impl = self
+            .get(format)
+            .ok_or_else(|| ParserError::UnsupportedFormat(format.to_string()))?;
+        format_impl.writer(Box::new(writer))
+    }
+}
+
+impl Default for FormatRegistry {
+    fn default() -> Self {
+        Self::with_builtin_formats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::types::YPBankRecord;
+    use crate::test_helpers::create_test_records;
+    use std::io::Cursor;
+
+    /// Toy format writing one tx_id per line, used to exercise registration.
+    struct TxIdFormat;
+
+    struct TxIdWriter<'a> {
+        output: Box<dyn Write + 'a>,
+    }
+
+    impl RecordWriter for TxIdWriter<'_> {
+        fn write_record(&mut self, record: &YPBankRecord) -> Result<(), ParserError> {
+            writeln!(self.output, "{}", record.tx_id)?;
+            Ok(())
+        }
+
+        fn finish(&mut self) -> Result<(), ParserError> {
+            self.output.flush()?;
+            Ok(())
+        }
+    }
+
+    impl Format for TxIdFormat {
+        fn name(&self) -> &str {
+            "txid"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["ids"]
+        }
+
+        fn reader<'a>(&self, _input: Box<dyn BufRead + 'a>) -> Result<RecordReader<'a>, ParserError> {
+            Err(ParserError::UnsupportedFormat("txid".to_string()))
+        }
+
+        fn writer<'a>(
+            &self,
+            output: Box<dyn Write + 'a>,
+        ) -> Result<Box<dyn RecordWriter + 'a>, ParserError> {
+            Ok(Box::new(TxIdWriter { output }))
+        }
+
+        fn capabilities(&self) -> FormatCapabilities {
+            FormatCapabilities {
+                read: false,
+                write: true,
+                binary: false,
+            }
+        }
+    }
+
+    #[test]
+    fn test_builtin_formats_registered() {
+        let registry = FormatRegistry::default();
+        assert_eq!(registry.names()[..3], ["csv", "txt", "bin"]);
+        assert!(registry.get("BIN").is_some());
+        assert!(registry.get("xml").is_none());
+        assert_eq!(registry.extract_format("records.TXT"), "txt");
+        assert_eq!(registry.extract_format("records.ids"), DEFAULT_FORMAT);
+        assert_eq!(registry.extract_format("records.bin.zst"), "bin");
+        assert_eq!(registry.extract_format("export.csv.gz"), "csv");
+    }
+
+    #[test]
+    fn test_register_custom_format() {
+        let mut registry = FormatRegistry::default();
+        registry.register(TxIdFormat);
+
+        assert_eq!(registry.extract_format("export.ids"), "txid");
+        assert!(!registry.get("txid").unwrap().capabilities().read);
+
+        let records = create_test_records(3, 10);
+        let mut output = Vec::new();
+        {
+            let mut writer = registry.writer(&mut output, "txid").unwrap();
+            for record in &records {
+                writer.write_record(record).unwrap();
+            }
+            writer.finish().unwrap();
+        }
+
+        let expected: String = records.iter().map(|r| format!("{}\n", r.tx_id)).collect();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+        assert!(registry.reader(Cursor::new(""), "txid").is_err());
+    }
+
+    #[test]
+    fn test_register_replaces_format_with_same_name() {
+        let mut registry = FormatRegistry::default();
+        let count = registry.names().len();
+        registry.register(YPBankCsvParser);
+        assert_eq!(registry.names().len(), count);
+    }
+}
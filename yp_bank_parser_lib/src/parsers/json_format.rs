@@ -0,0 +1,512 @@
+use std::io::{BufRead, Lines, Write};
+
+use serde_json::{Map, Value};
+
+use crate::parsers::detect::complete_lines;
+use crate::parsers::error::ParserError;
+use crate::parsers::format::{Format, FormatCapabilities};
+use crate::parsers::reader::{RecordReader, RecordSource, SourceLocation};
+use crate::parsers::types::{Status, TransactionType, YPBankRecord};
+use crate::parsers::writer::RecordWriter;
+
+/// Parser for YPBank JSON files holding an array of record objects.
+pub struct YPBankJsonParser;
+
+/// Parser for YPBank JSON Lines files holding one record object per line.
+pub struct YPBankJsonlParser;
+
+/// Builds a YPBankRecord from a JSON object.
+///
+/// Field names match the CSV header columns in lower case; `tx_type` and
+/// `status` are matched case-insensitively as in the CSV and TXT formats.
+///
+/// # Arguments
+///
+/// * `value` - A JSON value expected to be an object
+///
+/// # Returns
+///
+/// Returns a Result containing the YPBankRecord, or ParserError for missing or invalid fields.
+fn record_from_value(value: &Value) -> Result<YPBankRecord, ParserError> {
+    fn field<'v>(
+        object: &'v Map<String, Value>,
+        key: &str,
+        error: ParserError,
+    ) -> Result<&'v Value, ParserError> {
+        match object.get(key) {
+            None | Some(Value::Null) => Err(error),
+            Some(value) => Ok(value),
+        }
+    }
+
+    fn invalid(key: &str, value: &Value, reason: &str) -> ParserError {
+        ParserError::InvalidField {
+            field: key.to_string(),
+            value: value.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    fn parse_u64(object: &Map<String, Value>, key: &str, error: ParserError) -> Result<u64, ParserError> {
+        let value = field(object, key, error)?;
+        value
+            .as_u64()
+            .ok_or_else(|| invalid(key, value, "expected an unsigned integer"))
+    }
+
+    fn parse_i64(object: &Map<String, Value>, key: &str, error: ParserError) -> Result<i64, ParserError> {
+        let value = field(object, key, error)?;
+        value.as_i64().ok_or_else(|| invalid(key, value, "expected an integer"))
+    }
+
+    fn parse_str<'v>(
+        object: &'v Map<String, Value>,
+        key: &str,
+        error: ParserError,
+    ) -> Result<&'v str, ParserError> {
+        let value = field(object, key, error)?;
+        value.as_str().ok_or_else(|| invalid(key, value, "expected a string"))
+    }
+
+    fn parse_enum<T>(object: &Map<String, Value>, key: &str, error: ParserError) -> Result<T, ParserError>
+    where
+        T: std::str::FromStr,
+        T::Err: std::fmt::Display,
+    {
+        let value = field(object, key, error)?;
+        let text = value.as_str().ok_or_else(|| invalid(key, value, "expected a string"))?;
+        text.parse::<T>()
+            .map_err(|e| invalid(key, value, &e.to_string()))
+    }
+
+    let object = value
+        .as_object()
+        .ok_or_else(|| ParserError::InvalidJson(format!("expected an object, got {}", value)))?;
+
+    Ok(YPBankRecord {
+        tx_id: parse_u64(object, "tx_id", ParserError::MissingTxId)?,
+        tx_type: parse_enum::<TransactionType>(object, "tx_type", ParserError::MissingTransactionType)?,
+        from_user_id: parse_u64(object, "from_user_id", ParserError::MissingFromUserId)?,
+        to_user_id: parse_u64(object, "to_user_id", ParserError::MissingToUserId)?,
+        amount: parse_i64(object, "amount", ParserError::MissingAmount)?,
+        timestamp: parse_u64(object, "timestamp", ParserError::MissingTimestamp)?,
+        status: parse_enum::<Status>(object, "status", ParserError::MissingStatus)?,
+        description: parse_str(object, "description", ParserError::MissingDescription)?.to_string(),
+    })
+}
+
+/// Converts a serde_json error into a ParserError located at its line and column.
+fn json_error(error: serde_json::Error) -> ParserError {
+    let location = SourceLocation {
+        line: Some(error.line() as u64),
+        column: Some(error.column() as u64),
+        ..SourceLocation::default()
+    };
+    ParserError::InvalidJson(error.to_string()).at(location)
+}
+
+/// Reader yielding YPBank records from a JSON array of objects.
+///
+/// The whole document is parsed on the first call to `next`, so a malformed
+/// document is reported as the first item and ends the stream; use the
+/// JSON Lines format to process large inputs in constant memory.
+pub struct JsonRecordReader<R> {
+    reader: Option<R>,
+    values: std::vec::IntoIter<Value>,
+    failed: bool,
+    record_index: u64,
+    location: SourceLocation,
+    raw: String,
+}
+
+impl<R: BufRead> JsonRecordReader<R> {
+    /// Creates a new JSON record reader over the given buffered reader.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader implementing the BufRead trait
+    pub fn new(reader: R) -> Self {
+        JsonRecordReader {
+            reader: Some(reader),
+            values: Vec::new().into_iter(),
+            failed: false,
+            record_index: 0,
+            location: SourceLocation::default(),
+            raw: String::new(),
+        }
+    }
+
+    /// Parses the document into the array of values to iterate over.
+    fn read_document(mut reader: R) -> Result<Vec<Value>, ParserError> {
+        let mut content = Vec::new();
+        reader.read_to_end(&mut content)?;
+        if content.iter().all(u8::is_ascii_whitespace) {
+            return Err(ParserError::EmptyInput);
+        }
+
+        match serde_json::from_slice::<Value>(&content).map_err(json_error)? {
+            Value::Array(values) => Ok(values),
+            other => Err(ParserError::InvalidJson(format!(
+                "expected an array of records, got {}",
+                other
+            ))),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for JsonRecordReader<R> {
+    type Item = Result<YPBankRecord, ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(reader) = self.reader.take() {
+            match Self::read_document(reader) {
+                Ok(values) => self.values = values.into_iter(),
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+
+        let value = self.values.next()?;
+        self.location = SourceLocation {
+            record_index: Some(self.record_index),
+            ..SourceLocation::default()
+        };
+        self.record_index += 1;
+        self.raw = value.to_string();
+        Some(record_from_value(&value).map_err(|e| e.at(self.location)))
+    }
+}
+
+impl<R: BufRead> RecordSource for JsonRecordReader<R> {
+    fn location(&self) -> SourceLocation {
+        self.location
+    }
+
+    fn raw(&self) -> &[u8] {
+        self.raw.as_bytes()
+    }
+
+    fn can_resume(&self) -> bool {
+        !self.failed
+    }
+}
+
+/// Streaming reader yielding YPBank records from a JSON Lines source one line at a time.
+///
+/// Empty lines are ignored.
+pub struct JsonlRecordReader<R> {
+    lines: Lines<R>,
+    failed: bool,
+    line_number: u64,
+    record_index: u64,
+    location: SourceLocation,
+    raw: String,
+}
+
+impl<R: BufRead> JsonlRecordReader<R> {
+    /// Creates a new JSON Lines record reader over the given buffered reader.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader implementing the BufRead trait
+    pub fn new(reader: R) -> Self {
+        JsonlRecordReader {
+            lines: reader.lines(),
+            failed: false,
+            line_number: 0,
+            record_index: 0,
+            location: SourceLocation::default(),
+            raw: String::new(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for JsonlRecordReader<R> {
+    type Item = Result<YPBankRecord, ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(ParserError::Io(e)));
+                }
+            };
+            self.line_number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            self.location = SourceLocation {
+                line: Some(self.line_number),
+                record_index: Some(self.record_index),
+                ..SourceLocation::default()
+            };
+            self.record_index += 1;
+            let record = serde_json::from_str::<Value>(&line)
+                .map_err(|e| {
+                    // Errors of a single line are reported against the file line
+                    ParserError::InvalidJson(e.to_string()).at(SourceLocation {
+                        column: Some(e.column() as u64),
+                        ..SourceLocation::default()
+                    })
+                })
+                .and_then(|value| record_from_value(&value))
+                .map_err(|e| e.at(self.location));
+            self.raw = line;
+            return Some(record);
+        }
+    }
+}
+
+impl<R: BufRead> RecordSource for JsonlRecordReader<R> {
+    fn location(&self) -> SourceLocation {
+        self.location
+    }
+
+    fn raw(&self) -> &[u8] {
+        self.raw.as_bytes()
+    }
+
+    fn can_resume(&self) -> bool {
+        !self.failed
+    }
+}
+
+/// Incremental writer emitting YPBank records as a JSON array, one object per line.
+///
+/// An empty array is written if `finish` is called without any records.
+pub struct JsonRecordWriter<W> {
+    writer: W,
+    records_written: usize,
+}
+
+impl<W: Write> JsonRecordWriter<W> {
+    /// Creates a new JSON record writer over the given writer.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - A writer implementing the Write trait
+    pub fn new(writer: W) -> Self {
+        JsonRecordWriter {
+            writer,
+            records_written: 0,
+        }
+    }
+}
+
+impl<W: Write> RecordWriter for JsonRecordWriter<W> {
+    fn write_record(&mut self, record: &YPBankRecord) -> Result<(), ParserError> {
+        let separator = if self.records_written == 0 { "[\n  " } else { ",\n  " };
+        self.writer.write_all(separator.as_bytes())?;
+        serde_json::to_writer(&mut self.writer, record).map_err(std::io::Error::from)?;
+        self.records_written += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), ParserError> {
+        let closing = if self.records_written == 0 { "[]\n" } else { "\n]\n" };
+        self.writer.write_all(closing.as_bytes())?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Incremental writer emitting YPBank records in JSON Lines format.
+pub struct JsonlRecordWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> JsonlRecordWriter<W> {
+    /// Creates a new JSON Lines record writer over the given writer.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - A writer implementing the Write trait
+    pub fn new(writer: W) -> Self {
+        JsonlRecordWriter { writer }
+    }
+}
+
+impl<W: Write> RecordWriter for JsonlRecordWriter<W> {
+    fn write_record(&mut self, record: &YPBankRecord) -> Result<(), ParserError> {
+        serde_json::to_writer(&mut self.writer, record).map_err(std::io::Error::from)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), ParserError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl Format for YPBankJsonParser {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["json"]
+    }
+
+    fn reader<'a>(&self, input: Box<dyn BufRead + 'a>) -> Result<RecordReader<'a>, ParserError> {
+        Ok(RecordReader::from_source(JsonRecordReader::new(input)))
+    }
+
+    fn writer<'a>(
+        &self,
+        output: Box<dyn Write + 'a>,
+    ) -> Result<Box<dyn RecordWriter + 'a>, ParserError> {
+        Ok(Box::new(JsonRecordWriter::new(output)))
+    }
+
+    fn capabilities(&self) -> FormatCapabilities {
+        FormatCapabilities {
+            read: true,
+            write: true,
+            binary: false,
+        }
+    }
+
+    /// Recognises the input by an opening '[' followed by an object or the end of the array.
+    fn sniff(&self, head: &[u8]) -> f32 {
+        let mut bytes = head.iter().filter(|b| !b.is_ascii_whitespace());
+        if bytes.next() != Some(&b'[') {
+            return 0.0;
+        }
+        match bytes.next() {
+            Some(b'{') => 0.9,
+            Some(b']') => 0.6,
+            None => 0.5,
+            Some(_) => 0.0,
+        }
+    }
+}
+
+impl Format for YPBankJsonlParser {
+    fn name(&self) -> &str {
+        "jsonl"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["jsonl", "ndjson"]
+    }
+
+    fn reader<'a>(&self, input: Box<dyn BufRead + 'a>) -> Result<RecordReader<'a>, ParserError> {
+        Ok(RecordReader::from_source(JsonlRecordReader::new(input)))
+    }
+
+    fn writer<'a>(
+        &self,
+        output: Box<dyn Write + 'a>,
+    ) -> Result<Box<dyn RecordWriter + 'a>, ParserError> {
+        Ok(Box::new(JsonlRecordWriter::new(output)))
+    }
+
+    fn capabilities(&self) -> FormatCapabilities {
+        FormatCapabilities {
+            read: true,
+            write: true,
+            binary: false,
+        }
+    }
+
+    /// Recognises the input by a first line holding a JSON object.
+    ///
+    /// The confidence is highest if the object has a `tx_id` key.
+    fn sniff(&self, head: &[u8]) -> f32 {
+        let lines = complete_lines(head);
+        let Some(first) = lines.iter().find(|l| !l.trim().is_empty()) else {
+            return 0.0;
+        };
+        if !first.trim_start().starts_with('{') {
+            return 0.0;
+        }
+        match serde_json::from_str::<Map<String, Value>>(first) {
+            Ok(object) if object.contains_key("tx_id") => 1.0,
+            Ok(_) => 0.8,
+            Err(_) => 0.5,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::parser::Parser;
+    use crate::test_helpers::create_test_records;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_json_roundtrip() {
+        let records = create_test_records(5, 900);
+
+        for format in ["json", "jsonl"] {
+            let mut output = Vec::new();
+            Parser::write_to(&mut output, &records, format).unwrap();
+            let parsed = Parser::from_read(Cursor::new(output), format).unwrap();
+            assert_eq!(parsed, records);
+        }
+    }
+
+    #[test]
+    fn test_json_empty_array() {
+        let mut output = Vec::new();
+        Parser::write_to(&mut output, &[], "json").unwrap();
+        assert_eq!(String::from_utf8(output.clone()).unwrap(), "[]\n");
+        assert!(Parser::from_read(Cursor::new(output), "json").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_jsonl_missing_field() {
+        let input = r#"{"tx_id":1,"tx_type":"Deposit","from_user_id":0,"to_user_id":2,"timestamp":5,"status":"Success","description":"x"}"#;
+        let error = Parser::from_read(Cursor::new(input), "jsonl").unwrap_err();
+        assert!(matches!(error.kind(), ParserError::MissingAmount));
+        assert_eq!(error.location().unwrap().line, Some(1));
+    }
+
+    #[test]
+    fn test_jsonl_unknown_enum_value() {
+        let input = concat!(
+            r#"{"tx_id":1,"tx_type":"Deposit","from_user_id":0,"to_user_id":2,"amount":3,"timestamp":5,"status":"Success","description":""}"#,
+            "\n\n",
+            r#"{"tx_id":2,"tx_type":"Refund","from_user_id":0,"to_user_id":2,"amount":3,"timestamp":5,"status":"Success","description":""}"#,
+            "\n",
+        );
+        let error = Parser::from_read(Cursor::new(input), "jsonl").unwrap_err();
+        assert_eq!(error.code(), "invalid_field");
+        assert_eq!(error.location().unwrap().line, Some(3));
+        assert_eq!(error.location().unwrap().record_index, Some(1));
+    }
+
+    #[test]
+    fn test_json_malformed_document() {
+        let error = Parser::from_read(Cursor::new("[{\"tx_id\": 1,"), "json").unwrap_err();
+        assert_eq!(error.code(), "invalid_json");
+
+        let error = Parser::from_read(Cursor::new("{}"), "json").unwrap_err();
+        assert_eq!(error.code(), "invalid_json");
+    }
+
+    #[test]
+    fn test_detect_json_formats() {
+        let registry = crate::parsers::format::FormatRegistry::default();
+        let records = create_test_records(3, 40);
+
+        for format in ["json", "jsonl"] {
+            let mut output = Vec::new();
+            Parser::write_to(&mut output, &records, format).unwrap();
+            let detection = registry.detect(&output).unwrap();
+            assert_eq!(detection.format, format);
+            assert!(detection.is_confident());
+        }
+    }
+}
@@ -1,6 +1,24 @@
+#[cfg(feature = "async")]
+pub mod async_io;
+pub mod bin_block;
 pub mod bin_format;
+pub mod bin_index;
+pub mod bin_seekable;
+pub mod bin_slice;
+pub mod compression;
+pub mod csv_dialect;
 pub mod csv_format;
+pub mod detect;
 pub mod error;
+pub mod format;
+#[cfg(feature = "json")]
+pub mod json_format;
+pub mod parallel;
 pub mod parser;
+#[cfg(feature = "profiles")]
+pub mod profile;
+pub mod reader;
+pub mod txt_document;
 pub mod txt_format;
 pub mod types;
+pub mod writer;
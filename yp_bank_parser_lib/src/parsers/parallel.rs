@@ -0,0 +1,433 @@
+use crate::parsers::bin_slice::BinSliceReader;
+use crate::parsers::csv_dialect::CsvDialect;
+use crate::parsers::csv_format::CsvRecordReader;
+use crate::parsers::error::ParserError;
+use crate::parsers::types::YPBankRecord;
+
+/// Default smallest chunk, in bytes, that is given a thread of its own.
+const DEFAULT_MIN_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Options of `parse_csv_parallel` and `parse_bin_parallel`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParallelOptions {
+    /// Maximum number of threads, and so of chunks; the available parallelism by default.
+    pub threads: usize,
+    /// Smallest chunk in bytes worth a thread of its own, so that small inputs stay on one thread.
+    pub min_chunk_size: usize,
+}
+
+impl Default for ParallelOptions {
+    fn default() -> Self {
+        ParallelOptions {
+            threads: std::thread::available_parallelism().map_or(1, |threads| threads.get()),
+            min_chunk_size: DEFAULT_MIN_CHUNK_SIZE,
+        }
+    }
+}
+
+impl ParallelOptions {
+    /// Returns the number of chunks to split `len` bytes of input into.
+    fn chunk_count(&self, len: usize) -> usize {
+        (len / self.min_chunk_size.max(1)).clamp(1, self.threads.max(1))
+    }
+}
+
+/// Runs `task` on every chunk, each on its own scoped thread.
+///
+/// # Returns
+///
+/// Returns the results of the chunks in chunk order.
+fn run_chunks<C: Sync, T: Send>(chunks: &[C], task: impl Fn(&C) -> T + Sync) -> Vec<T> {
+    if chunks.len() <= 1 {
+        return chunks.iter().map(task).collect();
+    }
+
+    let task = &task;
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = chunks.iter().map(|chunk| scope.spawn(move || task(chunk))).collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+            .collect()
+    })
+}
+
+/// Joins the records of consecutive chunks, returning the first error in input order.
+fn concat_chunks(parts: Vec<Result<Vec<YPBankRecord>, ParserError>>) -> Result<Vec<YPBankRecord>, ParserError> {
+    let mut records = Vec::with_capacity(parts.iter().map(|part| part.as_ref().map_or(0, Vec::len)).sum());
+    for part in parts {
+        records.extend(part?);
+    }
+    Ok(records)
+}
+
+/// Quote and line feed counts of a run of CSV lines.
+#[derive(Debug, Default, Clone, Copy)]
+struct CsvSegment {
+    start: usize,
+    end: usize,
+    /// Whether the segment holds an odd number of quote characters.
+    odd_quotes: bool,
+    /// Number of line feeds.
+    lines: u64,
+    /// Line feeds outside quotes, which end a record, if the segment starts
+    /// outside quotes (index 0) or inside a quoted field (index 1).
+    records: [u64; 2],
+}
+
+impl CsvSegment {
+    /// Counts the quotes and line feeds of `data[start..end]`.
+    ///
+    /// A line feed ends a record when the quotes before it are balanced, as in
+    /// `read_record_text`.
+    fn scan(data: &[u8], start: usize, end: usize, quote: &[u8]) -> CsvSegment {
+        let mut segment = CsvSegment {
+            start,
+            end,
+            ..CsvSegment::default()
+        };
+        let mut i = start;
+        while i < end {
+            if data[i] == quote[0] && data[i..end].starts_with(quote) {
+                segment.odd_quotes = !segment.odd_quotes;
+                i += quote.len();
+                continue;
+            }
+            if data[i] == b'\n' {
+                segment.lines += 1;
+                segment.records[segment.odd_quotes as usize] += 1;
+            }
+            i += 1;
+        }
+        segment
+    }
+}
+
+/// Part of a CSV body starting at a record boundary.
+#[derive(Debug, Clone, Copy)]
+struct CsvChunk {
+    start: usize,
+    end: usize,
+    /// Number of lines before the chunk, including the header.
+    line_number: u64,
+    /// Index of the first record of the chunk.
+    record_index: u64,
+}
+
+/// Splits a CSV body into chunks that start at record boundaries.
+///
+/// The body is first cut after the line feed following each of `parts` evenly
+/// spaced offsets. The segments are scanned in parallel, and a cut is kept if
+/// the quotes before it are balanced; otherwise it falls inside a quoted field
+/// and its segment is joined to the previous chunk.
+///
+/// # Arguments
+///
+/// * `body` - The input after the header
+/// * `quote` - Character enclosing quoted fields
+/// * `line_number` - Number of lines before the body
+/// * `parts` - Number of chunks to aim for
+fn split_csv(body: &[u8], quote: char, line_number: u64, parts: usize) -> Vec<CsvChunk> {
+    if parts <= 1 {
+        return vec![CsvChunk {
+            start: 0,
+            end: body.len(),
+            line_number,
+            record_index: 0,
+        }];
+    }
+
+    let mut starts = vec![0];
+    for part in 1..parts {
+        let nominal = body.len() * part / parts;
+        if nominal < starts[starts.len() - 1] {
+            continue;
+        }
+        match body[nominal..].iter().position(|&b| b == b'\n') {
+            Some(line_feed) if nominal + line_feed + 1 < body.len() => starts.push(nominal + line_feed + 1),
+            _ => break,
+        }
+    }
+    let bounds: Vec<(usize, usize)> = starts
+        .iter()
+        .zip(starts.iter().skip(1).chain(std::iter::once(&body.len())))
+        .map(|(&start, &end)| (start, end))
+        .collect();
+
+    let mut quote_bytes = [0u8; 4];
+    let quote = quote.encode_utf8(&mut quote_bytes).as_bytes();
+    let segments = run_chunks(&bounds, |&(start, end)| CsvSegment::scan(body, start, end, quote));
+
+    let mut chunks: Vec<CsvChunk> = Vec::new();
+    let (mut line_number, mut record_index, mut quoted) = (line_number, 0, false);
+    for segment in segments {
+        match chunks.last_mut() {
+            Some(chunk) if quoted => chunk.end = segment.end,
+            _ => chunks.push(CsvChunk {
+                start: segment.start,
+                end: segment.end,
+                line_number,
+                record_index,
+            }),
+        }
+        line_number += segment.lines;
+        record_index += segment.records[quoted as usize];
+        quoted ^= segment.odd_quotes;
+    }
+    chunks
+}
+
+/// Parses CSV data held in memory on several threads.
+///
+/// The header is read first; the records after it are split into chunks at
+/// line feeds outside quoted fields and decoded by `CsvRecordReader`s on
+/// scoped threads, at most one chunk per thread. Records are returned in input
+/// order, and errors, including their lines and record indices, are those of
+/// reading the data with `CsvRecordReader::with_dialect` and collecting it.
+///
+/// # Arguments
+///
+/// * `data` - The whole CSV input
+/// * `dialect` - The CSV variant of the input
+/// * `options` - Number of threads and smallest chunk size
+///
+/// # Returns
+///
+/// Returns a Result containing all records, or the ParserError of the first invalid record.
+///
+/// # Errors
+///
+/// Returns ParserError::WrongCsvHeader if the header is invalid, ParserError::EmptyInput
+/// for empty input, or the parsing error of the first invalid record.
+pub fn parse_csv_parallel(
+    data: &[u8],
+    dialect: &CsvDialect,
+    options: &ParallelOptions,
+) -> Result<Vec<YPBankRecord>, ParserError> {
+    let (layout, line_number, body) = CsvRecordReader::with_dialect(data, dialect.clone()).into_body()?;
+    let chunks = split_csv(body, dialect.quote, line_number, options.chunk_count(body.len()));
+
+    let parts = run_chunks(&chunks, |chunk| {
+        CsvRecordReader::resume(
+            &body[chunk.start..chunk.end],
+            dialect.clone(),
+            layout.clone(),
+            chunk.line_number,
+            chunk.record_index,
+        )
+        .collect()
+    });
+    concat_chunks(parts)
+}
+
+/// Part of a binary file made of whole frames.
+#[derive(Debug, Clone, Copy)]
+struct BinChunk {
+    start: usize,
+    end: usize,
+    /// Index of the record in the first frame.
+    record_index: u64,
+}
+
+/// Parses a binary file held in memory on several threads.
+///
+/// Frame boundaries are found by following the size of each frame header,
+/// without decoding bodies; the frames are then split into runs of about
+/// equal size and decoded on scoped threads, at most one run per thread.
+/// Records are returned in file order, and errors, including their byte
+/// offsets and record indices, are those of `BinSliceReader`. The data may
+/// come from a `MmapBinFile` (feature `mmap`).
+///
+/// # Arguments
+///
+/// * `data` - The whole binary file
+/// * `options` - Number of threads and smallest chunk size
+///
+/// # Returns
+///
+/// Returns a Result containing all records, or the ParserError of the first invalid frame.
+///
+/// # Errors
+///
+/// Returns the errors of `BinSliceReader`, such as ParserError::InvalidMagic,
+/// ChecksumMismatch or RecordCountMismatch.
+pub fn parse_bin_parallel(data: &[u8], options: &ParallelOptions) -> Result<Vec<YPBankRecord>, ParserError> {
+    let mut frames = BinSliceReader::new(data)?;
+    let version = frames.version();
+    let chunk_size = data.len() / options.chunk_count(data.len());
+
+    let mut chunks: Vec<BinChunk> = Vec::new();
+    let mut framing_error = None;
+    while let Some(frame) = frames.split_frame() {
+        let frame = match frame {
+            Ok((frame, _)) => frame,
+            Err(e) => {
+                framing_error = Some(e);
+                continue;
+            }
+        };
+        let location = frames.location();
+        let start = location.byte_offset.unwrap_or_default() as usize;
+        match chunks.last_mut() {
+            Some(chunk) if start - chunk.start < chunk_size => chunk.end = start + frame.len(),
+            _ => chunks.push(BinChunk {
+                start,
+                end: start + frame.len(),
+                record_index: location.record_index.unwrap_or_default(),
+            }),
+        }
+    }
+
+    let parts = run_chunks(&chunks, |chunk| {
+        BinSliceReader::resume(&data[..chunk.end], version, chunk.start, chunk.record_index)
+            .map(|record| record.map(|record| record.to_owned()))
+            .collect()
+    });
+    let records = concat_chunks(parts)?;
+    match framing_error {
+        Some(e) => Err(e),
+        None => Ok(records),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::bin_format::{BinRecordReader, BinVersion, BinWriterOptions, YPBankBinParser};
+    use crate::parsers::csv_format::{CsvRecordWriter, YPBankCsvParser};
+    use crate::parsers::writer::RecordWriter;
+    use crate::test_helpers::create_test_records;
+    use crate::MAGIC;
+    use std::io::Cursor;
+
+    /// Options splitting even tiny inputs into `threads` chunks.
+    fn small_chunks(threads: usize) -> ParallelOptions {
+        ParallelOptions {
+            threads,
+            min_chunk_size: 1,
+        }
+    }
+
+    fn assert_same_result(
+        parallel: Result<Vec<YPBankRecord>, ParserError>,
+        serial: Result<&Vec<YPBankRecord>, &ParserError>,
+    ) {
+        match (parallel, serial) {
+            (Ok(parallel), Ok(serial)) => assert_eq!(&parallel, serial),
+            (Err(parallel), Err(serial)) => {
+                assert_eq!(parallel.code(), serial.code());
+                assert_eq!(parallel.location(), serial.location());
+            }
+            (parallel, serial) => panic!("{:?} != {:?}", parallel, serial),
+        }
+    }
+
+    #[test]
+    fn test_parallel_csv_matches_serial() {
+        let mut records = create_test_records(200, 2500);
+        // Quoted fields with line feeds, so that some cuts fall inside quotes
+        for record in records.iter_mut().step_by(3) {
+            record.description = format!("first \"line\"\nsecond, {}\r\n\"\"\n", record.tx_id);
+        }
+        let mut data = Vec::new();
+        YPBankCsvParser::write_to(&mut data, &records).unwrap();
+
+        for threads in [1, 2, 3, 8, 64] {
+            let parsed = parse_csv_parallel(&data, &CsvDialect::default(), &small_chunks(threads)).unwrap();
+            assert_eq!(parsed, records, "{} threads", threads);
+        }
+        assert_eq!(YPBankCsvParser::from_read_parallel(Cursor::new(&data)).unwrap(), records);
+        assert_eq!(YPBankCsvParser::parse_bytes_parallel(&data).unwrap(), records);
+
+        let dialect = CsvDialect {
+            delimiter: ';',
+            quote: '\'',
+            has_header: false,
+            line_terminator: "\r\n".to_string(),
+            ..CsvDialect::default()
+        };
+        let mut data = "\u{feff}".as_bytes().to_vec();
+        let mut writer = CsvRecordWriter::with_dialect(&mut data, dialect.clone());
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        writer.finish().unwrap();
+        assert_eq!(parse_csv_parallel(&data, &dialect, &small_chunks(7)).unwrap(), records);
+    }
+
+    #[test]
+    fn test_parallel_csv_errors_match_serial() {
+        let mut records = create_test_records(100, 2500);
+        for record in records.iter_mut().step_by(4) {
+            record.description = "quoted\nacross \"lines\"".to_string();
+        }
+        let mut data = Vec::new();
+        YPBankCsvParser::write_to(&mut data, &records).unwrap();
+        let text = String::from_utf8(data).unwrap();
+        // Inserts `insert` after the n-th occurrence of `pattern`
+        let insert_after = |pattern: &str, n: usize, insert: &str| {
+            let (at, _) = text.match_indices(pattern).nth(n).unwrap();
+            let mut damaged = text.clone();
+            damaged.insert_str(at + pattern.len(), insert);
+            damaged
+        };
+
+        let damaged = [
+            insert_after(",", 500, "x"),
+            insert_after("\n", 60, "\""),
+            format!("{}\n\n", text),
+            text.replacen("TX_ID", "ID", 1),
+            String::new(),
+        ];
+        let mut invalid_utf8 = text.clone().into_bytes();
+        let middle = invalid_utf8.len() * 3 / 4;
+        invalid_utf8[middle] = 0xFF;
+
+        for input in damaged.iter().map(|text| text.as_bytes()).chain([invalid_utf8.as_slice()]) {
+            let serial = YPBankCsvParser::from_read(Cursor::new(input));
+            assert!(serial.is_err());
+            for threads in [5, 16, 64] {
+                let parallel = parse_csv_parallel(input, &CsvDialect::default(), &small_chunks(threads));
+                assert_same_result(parallel, serial.as_ref());
+            }
+        }
+    }
+
+    #[test]
+    fn test_parallel_bin_matches_serial() {
+        let records = create_test_records(150, 2500);
+        let options = BinWriterOptions {
+            version: BinVersion::V2,
+            record_count: Some(records.len() as u64),
+            checksums: true,
+            ..BinWriterOptions::default()
+        };
+        for options in [BinWriterOptions::default(), options] {
+            let mut data = Vec::new();
+            YPBankBinParser::write_with_options(&mut data, &records, options.clone()).unwrap();
+            for threads in [1, 4, 64] {
+                assert_eq!(parse_bin_parallel(&data, &small_chunks(threads)).unwrap(), records);
+            }
+            assert_eq!(YPBankBinParser::from_read_parallel(Cursor::new(&data)).unwrap(), records);
+            assert_eq!(YPBankBinParser::parse_bytes_parallel(&data).unwrap(), records);
+
+            // Offset of the first frame at or after `at`
+            let frame_at = |at: usize| at + data[at..].windows(4).position(|w| w == MAGIC).unwrap();
+            let frame_header_size = options.version.frame_header_size();
+            let mut bad_tx_type = data.clone();
+            bad_tx_type[frame_at(data.len() / 3) + frame_header_size + 8] = 0xFF;
+            let mut bad_magic = data.clone();
+            bad_magic[frame_at(data.len() / 2)] = b'X';
+            let mut too_large = data.clone();
+            too_large[frame_at(data.len() * 3 / 4) + 4] = 0xFF;
+            let truncated = data[..data.len() - 5].to_vec();
+
+            for input in [bad_tx_type, bad_magic, too_large, truncated] {
+                let serial: Result<Vec<_>, _> = BinRecordReader::new(Cursor::new(&input)).collect();
+                assert!(serial.is_err());
+                assert_same_result(parse_bin_parallel(&input, &small_chunks(6)), serial.as_ref());
+            }
+        }
+        assert_eq!(parse_bin_parallel(&[], &ParallelOptions::default()).unwrap(), vec![]);
+    }
+}
@@ -1,21 +1,59 @@
 use std::io::{BufRead, Read, Write};
+use std::sync::Arc;
 
-use crate::parsers::bin_format::YPBankBinParser;
-use crate::parsers::csv_format::YPBankCsvParser;
+use crate::parsers::compression::decompress;
 use crate::parsers::error::ParserError;
-use crate::parsers::txt_format::YPBankTxtParser;
+use crate::parsers::format::{Format, FormatRegistry};
+use crate::parsers::reader::{LenientParse, RecordReader};
 use crate::parsers::types::YPBankRecord;
+use crate::parsers::writer::RecordWriter;
 
 /// Parser for reading and writing YPBank records in various formats.
+///
+/// Formats are looked up by name in the process-wide `FormatRegistry`.
 pub struct Parser;
 
 impl Parser {
+    /// Looks up a format by name in the process-wide registry.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::UnsupportedFormat if no such format is registered.
+    fn format(format: &str) -> Result<Arc<dyn Format>, ParserError> {
+        FormatRegistry::read_global()
+            .get(format)
+            .ok_or_else(|| ParserError::UnsupportedFormat(format.to_string()))
+    }
+
+    /// Creates a streaming record reader for the specified format.
+    ///
+    /// Gzip or zstd compressed input is decompressed transparently.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader implementing the BufRead trait
+    /// * `format` - Name of a registered format ("csv", "txt", "bin", ...)
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing a RecordReader yielding records one at a time.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::UnsupportedFormat if the format is not supported.
+    pub fn reader<'a, R: BufRead + 'a>(
+        reader: R,
+        format: &str,
+    ) -> Result<RecordReader<'a>, ParserError> {
+        Self::format(format)?.reader(decompress(reader)?)
+    }
+
     /// Reads YPBank records from a reader in the specified format.
     ///
     /// # Arguments
     ///
     /// * `reader` - A reader implementing Read + BufRead traits
-    /// * `format` - Format string ("csv", "txt", or "bin")
+    /// * `format` - Name of a registered format ("csv", "txt", "bin", ...)
     ///
     /// # Returns
     ///
@@ -29,12 +67,54 @@ impl Parser {
         reader: R,
         format: &str,
     ) -> Result<Vec<YPBankRecord>, ParserError> {
-        match format.to_lowercase().as_str() {
-            "csv" => YPBankCsvParser::from_read(reader),
-            "txt" => YPBankTxtParser::from_read(reader),
-            "bin" => YPBankBinParser::from_read(reader),
-            _ => Err(ParserError::UnsupportedFormat(format.to_string())),
-        }
+        Self::reader(reader, format)?.collect()
+    }
+
+    /// Reads YPBank records leniently, skipping and collecting malformed records.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader implementing Read + BufRead traits
+    /// * `format` - Name of a registered format ("csv", "txt", "bin", ...)
+    /// * `max_errors` - Maximum number of rejected records tolerated
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing the valid records together with the rejected
+    /// ones, each carrying its error, source location and raw input.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::UnsupportedFormat if the format is not supported,
+    /// ParserError::ErrorBudgetExceeded if more than `max_errors` records are rejected,
+    /// or the error that made the rest of the input unreadable.
+    pub fn from_read_lenient<R: Read + BufRead>(
+        reader: R,
+        format: &str,
+        max_errors: usize,
+    ) -> Result<LenientParse, ParserError> {
+        Self::reader(reader, format)?.collect_lenient(max_errors)
+    }
+
+    /// Creates an incremental record writer for the specified format.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - A writer implementing the Write trait
+    /// * `format` - Name of a registered format ("csv", "txt", "bin", ...)
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing a boxed RecordWriter; call `finish` after the last record.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::UnsupportedFormat if the format is not supported.
+    pub fn writer<'a, W: Write + 'a>(
+        writer: W,
+        format: &str,
+    ) -> Result<Box<dyn RecordWriter + 'a>, ParserError> {
+        Self::format(format)?.writer(Box::new(writer))
     }
 
     /// Writes YPBank records to a writer in the specified format.
@@ -43,7 +123,7 @@ impl Parser {
     ///
     /// * `writer` - A writer implementing the Write trait
     /// * `records` - Slice of YPBankRecord to write
-    /// * `format` - Format string ("csv", "txt", or "bin")
+    /// * `format` - Name of a registered format ("csv", "txt", "bin", ...)
     ///
     /// # Returns
     ///
@@ -58,11 +138,10 @@ impl Parser {
         records: &[YPBankRecord],
         format: &str,
     ) -> Result<(), ParserError> {
-        match format.to_lowercase().as_str() {
-            "csv" => YPBankCsvParser::write_to(writer, records),
-            "txt" => YPBankTxtParser::write_to(writer, records),
-            "bin" => YPBankBinParser::write_to(writer, records),
-            _ => Err(ParserError::UnsupportedFormat(format.to_string())),
+        let mut record_writer = Self::writer(writer, format)?;
+        for record in records {
+            record_writer.write_record(record)?;
         }
+        record_writer.finish()
     }
 }
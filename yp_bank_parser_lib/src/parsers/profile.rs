@@ -0,0 +1,859 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::parsers::csv_dialect::CsvDialect;
+use crate::parsers::csv_format::read_record_text;
+use crate::parsers::error::ParserError;
+use crate::parsers::format::{Format, FormatCapabilities};
+use crate::parsers::reader::{RecordReader, RecordSource, SourceLocation};
+use crate::parsers::types::{Status, TransactionType, YPBankRecord};
+use crate::parsers::writer::RecordWriter;
+
+/// Declarative mapping of a foreign bank's CSV export onto YPBank records.
+///
+/// A profile is loaded from TOML and implements `Format`, so once registered
+/// in a `FormatRegistry` it can be used by name like any built-in format.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use yp_bank_parser_lib::parsers::profile::ImportProfile;
+/// use yp_bank_parser_lib::parsers::format::Format;
+///
+/// let profile = ImportProfile::from_toml_str(r#"
+///     name = "partner"
+///     delimiter = ";"
+///
+///     [tx_id]
+///     column = "Id"
+///     [tx_type]
+///     value = "Deposit"
+///     [from_user_id]
+///     value = "0"
+///     [to_user_id]
+///     column = "Account"
+///     [amount]
+///     column = "Sum"
+///     decimal_separator = ","
+///     scale = 2
+///     [timestamp]
+///     column = "Date"
+///     date_format = "%d.%m.%Y"
+///     [status]
+///     value = "Success"
+///     [description]
+///     column = "Memo"
+/// "#).unwrap();
+///
+/// let input = "Id;Date;Account;Sum;Memo\n7;02.01.2024;42;15,50;Salary\n";
+/// let records: Vec<_> = profile.reader(Box::new(Cursor::new(input))).unwrap().collect();
+/// let record = records[0].as_ref().unwrap();
+/// assert_eq!(record.amount, 1550);
+/// assert_eq!(record.timestamp, 1704153600);
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ImportProfile {
+    /// Format name under which the profile is registered.
+    pub name: String,
+    /// Character separating fields.
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    /// Character enclosing quoted fields.
+    #[serde(default = "default_quote")]
+    pub quote: char,
+    /// Whether the first line (after `skip_lines`) is a header naming the columns.
+    #[serde(default = "default_has_header")]
+    pub has_header: bool,
+    /// Number of leading lines, such as statement titles, to skip.
+    #[serde(default)]
+    pub skip_lines: usize,
+    #[serde(default)]
+    pub tx_id: FieldSpec,
+    #[serde(default)]
+    pub tx_type: FieldSpec,
+    #[serde(default)]
+    pub from_user_id: FieldSpec,
+    #[serde(default)]
+    pub to_user_id: FieldSpec,
+    #[serde(default)]
+    pub amount: AmountSpec,
+    #[serde(default)]
+    pub timestamp: FieldSpec,
+    #[serde(default)]
+    pub status: FieldSpec,
+    #[serde(default)]
+    pub description: FieldSpec,
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+fn default_quote() -> char {
+    '"'
+}
+
+fn default_has_header() -> bool {
+    true
+}
+
+/// Source and conversion of a single record field.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldSpec {
+    /// Header name of the column holding the value, matched case-insensitively.
+    pub column: Option<String>,
+    /// 0-based index of the column holding the value, for files without a header.
+    pub index: Option<usize>,
+    /// Constant value used for every record instead of a column.
+    pub value: Option<String>,
+    /// Value used when the column is empty.
+    pub default: Option<String>,
+    /// Replacements applied to the column value (e.g. "Исполнено" = "Success").
+    #[serde(default)]
+    pub map: HashMap<String, String>,
+    /// `strftime`-like format (`%Y`, `%m`, `%d`, `%H`, `%M`, `%S`) for dates,
+    /// converted to a UTC Unix timestamp.
+    pub date_format: Option<String>,
+}
+
+/// Source and conversion of the amount.
+///
+/// The amount is taken either from a single signed `column`, or from separate
+/// `debit` and `credit` columns, debits becoming negative amounts.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AmountSpec {
+    /// Header name of a column holding the signed amount.
+    pub column: Option<String>,
+    /// 0-based index of a column holding the signed amount.
+    pub index: Option<usize>,
+    /// Header name of the column holding debited (outgoing) amounts.
+    pub debit: Option<String>,
+    /// Header name of the column holding credited (incoming) amounts.
+    pub credit: Option<String>,
+    /// Decimal separator of the amounts.
+    #[serde(default = "default_decimal_separator")]
+    pub decimal_separator: char,
+    /// Number of decimal places converted into the integer amount (2 for cents).
+    #[serde(default)]
+    pub scale: u32,
+}
+
+fn default_decimal_separator() -> char {
+    '.'
+}
+
+impl Default for AmountSpec {
+    fn default() -> Self {
+        AmountSpec {
+            column: None,
+            index: None,
+            debit: None,
+            credit: None,
+            decimal_separator: default_decimal_separator(),
+            scale: 0,
+        }
+    }
+}
+
+impl ImportProfile {
+    /// Parses a profile from TOML text.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::InvalidProfile if the TOML is malformed, has unknown keys
+    /// or sets the same character as delimiter and quote.
+    pub fn from_toml_str(text: &str) -> Result<Self, ParserError> {
+        let profile: ImportProfile = toml::from_str(text).map_err(|e| ParserError::InvalidProfile(e.to_string()))?;
+        profile
+            .dialect()
+            .validate()
+            .map_err(|e| ParserError::InvalidProfile(e.to_string()))?;
+        Ok(profile)
+    }
+
+    /// Loads a profile from a TOML file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the TOML file
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::Io if the file cannot be read, or ParserError::InvalidProfile
+    /// if it is not a valid profile.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ParserError> {
+        Self::from_toml_str(&std::fs::read_to_string(path)?)
+    }
+
+    /// Returns the CSV dialect used to split the input into fields.
+    fn dialect(&self) -> CsvDialect {
+        CsvDialect {
+            delimiter: self.delimiter,
+            quote: self.quote,
+            ..CsvDialect::default()
+        }
+    }
+}
+
+impl Format for ImportProfile {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[]
+    }
+
+    fn reader<'a>(&self, input: Box<dyn BufRead + 'a>) -> Result<RecordReader<'a>, ParserError> {
+        Ok(RecordReader::from_source(ProfileRecordReader::new(input, self.clone())))
+    }
+
+    fn writer<'a>(
+        &self,
+        _output: Box<dyn Write + 'a>,
+    ) -> Result<Box<dyn RecordWriter + 'a>, ParserError> {
+        Err(ParserError::UnsupportedFormat(format!("{} (import only)", self.name)))
+    }
+
+    fn capabilities(&self) -> FormatCapabilities {
+        FormatCapabilities {
+            read: true,
+            write: false,
+            binary: false,
+        }
+    }
+}
+
+/// Where the value of a field comes from, once the header is known.
+#[derive(Debug, Clone)]
+enum Source {
+    Column(usize),
+    Constant(String),
+    Absent,
+}
+
+/// Field sources resolved against the header of an input.
+struct Resolved {
+    tx_id: Source,
+    tx_type: Source,
+    from_user_id: Source,
+    to_user_id: Source,
+    amount: Source,
+    debit: Source,
+    credit: Source,
+    timestamp: Source,
+    status: Source,
+    description: Source,
+}
+
+/// Streaming reader converting a foreign CSV export into YPBank records with an `ImportProfile`.
+pub struct ProfileRecordReader<R> {
+    reader: R,
+    profile: ImportProfile,
+    dialect: CsvDialect,
+    resolved: Option<Resolved>,
+    done: bool,
+    line_number: u64,
+    record_index: u64,
+    location: SourceLocation,
+    raw: String,
+    replay: Vec<String>,
+}
+
+impl<R: BufRead> ProfileRecordReader<R> {
+    /// Creates a new reader applying the given profile.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader implementing the BufRead trait
+    /// * `profile` - The import profile describing the input
+    pub fn new(reader: R, profile: ImportProfile) -> Self {
+        ProfileRecordReader {
+            reader,
+            dialect: profile.dialect(),
+            profile,
+            resolved: None,
+            done: false,
+            line_number: 0,
+            record_index: 0,
+            location: SourceLocation::default(),
+            raw: String::new(),
+            replay: Vec::new(),
+        }
+    }
+
+    /// Skips leading lines, reads the header if any and resolves the field sources.
+    fn read_header(&mut self) -> Result<Resolved, ParserError> {
+        for _ in 0..self.profile.skip_lines {
+            if read_record_text(&mut self.reader, self.dialect.quote, &mut self.line_number, &mut self.replay)?.is_none() {
+                return Err(ParserError::EmptyInput);
+            }
+        }
+
+        let mut columns = HashMap::new();
+        if self.profile.has_header {
+            self.location = SourceLocation {
+                line: Some(self.line_number + 1),
+                ..SourceLocation::default()
+            };
+            let header = read_record_text(&mut self.reader, self.dialect.quote, &mut self.line_number, &mut self.replay)?
+                .ok_or(ParserError::EmptyInput)?;
+            let header = header.trim_start_matches('\u{feff}');
+            let (cells, _) = self.dialect.split_fields(header)?;
+            for (index, cell) in cells.iter().enumerate() {
+                columns.entry(cell.trim().to_lowercase()).or_insert(index);
+            }
+        }
+
+        let resolve_column = |name: &Option<String>, index: Option<usize>| -> Result<Source, ParserError> {
+            if let Some(name) = name {
+                return columns
+                    .get(&name.trim().to_lowercase())
+                    .map(|&i| Source::Column(i))
+                    .ok_or_else(|| ParserError::WrongCsvHeader(format!("missing column {:?}", name)));
+            }
+            Ok(index.map_or(Source::Absent, Source::Column))
+        };
+        let resolve = |spec: &FieldSpec| -> Result<Source, ParserError> {
+            if let Some(value) = &spec.value {
+                return Ok(Source::Constant(value.clone()));
+            }
+            resolve_column(&spec.column, spec.index)
+        };
+
+        let profile = &self.profile;
+        Ok(Resolved {
+            tx_id: resolve(&profile.tx_id)?,
+            tx_type: resolve(&profile.tx_type)?,
+            from_user_id: resolve(&profile.from_user_id)?,
+            to_user_id: resolve(&profile.to_user_id)?,
+            amount: resolve_column(&profile.amount.column, profile.amount.index)?,
+            debit: resolve_column(&profile.amount.debit, None)?,
+            credit: resolve_column(&profile.amount.credit, None)?,
+            timestamp: resolve(&profile.timestamp)?,
+            status: resolve(&profile.status)?,
+            description: resolve(&profile.description)?,
+        })
+    }
+
+    /// Converts one line of input into a record.
+    fn convert(&self, resolved: &Resolved, line: &str) -> Result<YPBankRecord, ParserError> {
+        let (parts, columns) = self.dialect.split_fields(line)?;
+        let profile = &self.profile;
+
+        // Raw text of a source together with its 1-based column, if any
+        let cell = |source: &Source| -> Result<Option<(String, Option<u64>)>, ParserError> {
+            match source {
+                Source::Absent => Ok(None),
+                Source::Constant(value) => Ok(Some((value.clone(), None))),
+                Source::Column(index) => match parts.get(*index) {
+                    Some(text) => Ok(Some((text.trim().to_string(), Some(columns[*index])))),
+                    None => Err(ParserError::WrongFieldCount {
+                        expected: index + 1,
+                        actual: parts.len(),
+                    }),
+                },
+            }
+        };
+
+        // Value of a field after applying the default and the value map
+        let value = |source: &Source, spec: &FieldSpec, missing: ParserError| {
+            let (text, column) = match (cell(source)?, &spec.default) {
+                (Some((text, column)), _) if !text.is_empty() => (text, column),
+                // Empty or absent values fall back to the default
+                (_, Some(default)) => (default.clone(), None),
+                (Some((text, column)), None) => (text, column),
+                (None, None) => return Err(missing),
+            };
+            let text = spec.map.get(&text).cloned().unwrap_or(text);
+            Ok::<_, ParserError>((text, column))
+        };
+
+        fn convert_field<T>(
+            field: &str,
+            (text, column): (String, Option<u64>),
+            parse: impl FnOnce(&str) -> Result<T, String>,
+        ) -> Result<T, ParserError> {
+            parse(&text).map_err(|reason| {
+                ParserError::InvalidField {
+                    field: field.to_string(),
+                    value: text.clone(),
+                    reason,
+                }
+                .at(SourceLocation {
+                    column,
+                    ..SourceLocation::default()
+                })
+            })
+        }
+
+        fn parse_from_str<T>(text: &str) -> Result<T, String>
+        where
+            T: std::str::FromStr,
+            T::Err: std::fmt::Display,
+        {
+            text.parse::<T>().map_err(|e| e.to_string())
+        }
+
+        let timestamp_value = value(&resolved.timestamp, &profile.timestamp, ParserError::MissingTimestamp)?;
+        let timestamp = match &profile.timestamp.date_format {
+            Some(format) => convert_field("timestamp", timestamp_value, |t| parse_date(t, format))?,
+            None => convert_field("timestamp", timestamp_value, parse_from_str)?,
+        };
+
+        let amount_spec = &profile.amount;
+        let parse_amount = |t: &str| parse_decimal(t, amount_spec.decimal_separator, amount_spec.scale);
+        let amount = match (&resolved.amount, &resolved.debit, &resolved.credit) {
+            (Source::Absent, Source::Absent, Source::Absent) => return Err(ParserError::MissingAmount),
+            (Source::Absent, debit, credit) => {
+                let out_of_range = || "amount out of range".to_string();
+                let mut amount = 0i64;
+                if let Some((text, column)) = cell(debit)?.filter(|(t, _)| !t.is_empty()) {
+                    amount = convert_field("amount", (text, column), |t| {
+                        parse_amount(t).and_then(|debit| amount.checked_sub(debit).ok_or_else(out_of_range))
+                    })?;
+                }
+                if let Some((text, column)) = cell(credit)?.filter(|(t, _)| !t.is_empty()) {
+                    amount = convert_field("amount", (text, column), |t| {
+                        parse_amount(t).and_then(|credit| amount.checked_add(credit).ok_or_else(out_of_range))
+                    })?;
+                }
+                amount
+            }
+            (source, _, _) => match cell(source)? {
+                Some(value) => convert_field("amount", value, parse_amount)?,
+                None => return Err(ParserError::MissingAmount),
+            },
+        };
+
+        Ok(YPBankRecord {
+            tx_id: convert_field(
+                "tx_id",
+                value(&resolved.tx_id, &profile.tx_id, ParserError::MissingTxId)?,
+                parse_from_str,
+            )?,
+            tx_type: convert_field(
+                "tx_type",
+                value(&resolved.tx_type, &profile.tx_type, ParserError::MissingTransactionType)?,
+                parse_from_str::<TransactionType>,
+            )?,
+            from_user_id: convert_field(
+                "from_user_id",
+                value(&resolved.from_user_id, &profile.from_user_id, ParserError::MissingFromUserId)?,
+                parse_from_str,
+            )?,
+            to_user_id: convert_field(
+                "to_user_id",
+                value(&resolved.to_user_id, &profile.to_user_id, ParserError::MissingToUserId)?,
+                parse_from_str,
+            )?,
+            amount,
+            timestamp,
+            status: convert_field(
+                "status",
+                value(&resolved.status, &profile.status, ParserError::MissingStatus)?,
+                parse_from_str::<Status>,
+            )?,
+            description: value(&resolved.description, &profile.description, ParserError::MissingDescription)?.0,
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for ProfileRecordReader<R> {
+    type Item = Result<YPBankRecord, ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let resolved = match self.resolved.take() {
+            Some(resolved) => resolved,
+            None => match self.read_header() {
+                Ok(resolved) => resolved,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e.at(self.location)));
+                }
+            },
+        };
+
+        let result = loop {
+            let first_line = self.line_number + 1;
+            break match read_record_text(&mut self.reader, self.dialect.quote, &mut self.line_number, &mut self.replay) {
+                Ok(None) => None,
+                // Blank lines, such as the trailing line of many bank exports, hold no record
+                Ok(Some(line)) if line.trim().is_empty() => continue,
+                Ok(Some(line)) => {
+                    self.location = SourceLocation {
+                        line: Some(first_line),
+                        record_index: Some(self.record_index),
+                        ..SourceLocation::default()
+                    };
+                    self.record_index += 1;
+                    let record = self.convert(&resolved, &line).map_err(|e| e.at(self.location));
+                    self.raw = line;
+                    Some(record)
+                }
+                Err(e) => {
+                    self.done = true;
+                    self.location = SourceLocation {
+                        line: Some(first_line),
+                        record_index: Some(self.record_index),
+                        ..SourceLocation::default()
+                    };
+                    Some(Err(ParserError::Io(e).at(self.location)))
+                }
+            };
+        };
+        self.resolved = Some(resolved);
+        result
+    }
+}
+
+impl<R: BufRead> RecordSource for ProfileRecordReader<R> {
+    fn location(&self) -> SourceLocation {
+        self.location
+    }
+
+    fn raw(&self) -> &[u8] {
+        self.raw.as_bytes()
+    }
+
+    fn can_resume(&self) -> bool {
+        !self.done
+    }
+}
+
+/// Parses a decimal number into an integer scaled by `10^scale`.
+///
+/// Spaces (including non-breaking ones) used as thousands separators are ignored.
+fn parse_decimal(text: &str, separator: char, scale: u32) -> Result<i64, String> {
+    let cleaned: String = text
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '\u{a0}')
+        .collect();
+    let (negative, digits) = match cleaned.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, cleaned.strip_prefix('+').unwrap_or(&cleaned)),
+    };
+
+    let (integer, fraction) = digits.split_once(separator).unwrap_or((digits, ""));
+    if fraction.len() > scale as usize {
+        return Err(format!("more than {} decimal places", scale));
+    }
+    if integer.is_empty() && fraction.is_empty() {
+        return Err("empty number".to_string());
+    }
+    if !integer.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+        return Err("invalid number".to_string());
+    }
+
+    let padded = format!("{}{:0<width$}", integer, fraction, width = scale as usize);
+    let value = padded
+        .parse::<i64>()
+        .map_err(|e| e.to_string())?;
+    Ok(if negative { -value } else { value })
+}
+
+/// Parses a date according to a `strftime`-like format into a UTC Unix timestamp.
+///
+/// Supports `%Y`, `%m`, `%d`, `%H`, `%M`, `%S` and `%%`; other characters must
+/// match literally. Missing time components default to zero.
+fn parse_date(text: &str, format: &str) -> Result<u64, String> {
+    let mut year = None;
+    let (mut month, mut day, mut hour, mut minute, mut second) = (1u64, 1u64, 0u64, 0u64, 0u64);
+
+    let mut input = text.chars().peekable();
+    let mut spec = format.chars();
+    while let Some(f) = spec.next() {
+        if f != '%' {
+            if input.next() != Some(f) {
+                return Err(format!("does not match date format {:?}", format));
+            }
+            continue;
+        }
+
+        let directive = spec.next().ok_or("dangling '%' in date format")?;
+        if directive == '%' {
+            if input.next() != Some('%') {
+                return Err(format!("does not match date format {:?}", format));
+            }
+            continue;
+        }
+
+        let max_digits = if directive == 'Y' { 4 } else { 2 };
+        let mut digits = String::new();
+        while digits.len() < max_digits && input.peek().is_some_and(|c| c.is_ascii_digit()) {
+            digits.extend(input.next());
+        }
+        let number: u64 = digits
+            .parse()
+            .map_err(|_| format!("does not match date format {:?}", format))?;
+        match directive {
+            'Y' => year = Some(number),
+            'm' => month = number,
+            'd' => day = number,
+            'H' => hour = number,
+            'M' => minute = number,
+            'S' => second = number,
+            other => return Err(format!("unsupported date directive %{}", other)),
+        }
+    }
+    if input.next().is_some() {
+        return Err(format!("does not match date format {:?}", format));
+    }
+
+    let year = year.ok_or("date format has no year")?;
+    if !(1..=12).contains(&month) || hour > 23 || minute > 59 || second > 59 {
+        return Err("date out of range".to_string());
+    }
+    if !(1..=days_in_month(year, month)).contains(&day) {
+        return Err(format!("day {} does not exist in month {} of {}", day, month, year));
+    }
+    if year < 1970 {
+        return Err("date before 1970".to_string());
+    }
+
+    Ok(days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+/// Returns the number of days in a month of the Gregorian calendar.
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Returns the number of days from 1970-01-01 to the given date of the Gregorian calendar.
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::format::FormatRegistry;
+    use std::io::Cursor;
+
+    const DEBIT_CREDIT_PROFILE: &str = r#"
+        name = "partner_bank"
+        delimiter = ";"
+        skip_lines = 1
+
+        [tx_id]
+        column = "Номер"
+
+        [tx_type]
+        column = "Операция"
+        map = { "Пополнение" = "Deposit", "Списание" = "Withdrawal" }
+
+        [from_user_id]
+        value = "0"
+
+        [to_user_id]
+        column = "Счёт"
+
+        [amount]
+        debit = "Расход"
+        credit = "Приход"
+        decimal_separator = ","
+        scale = 2
+
+        [timestamp]
+        column = "Дата"
+        date_format = "%d.%m.%Y %H:%M"
+
+        [status]
+        column = "Статус"
+        default = "Pending"
+        map = { "Исполнено" = "Success" }
+
+        [description]
+        column = "Назначение"
+        default = ""
+    "#;
+
+    const STATEMENT: &str = "Выписка по счёту\n\
+        Номер;Дата;Операция;Счёт;Расход;Приход;Статус;Назначение\n\
+        1;01.02.2024 10:30;Пополнение;42;;1 500,25;Исполнено;\"Зарплата; январь\"\n\
+        2;03.02.2024 00:00;Списание;42;99,9;;;\n";
+
+    #[test]
+    fn test_profile_debit_credit_statement() {
+        let profile = ImportProfile::from_toml_str(DEBIT_CREDIT_PROFILE).unwrap();
+        let records: Vec<YPBankRecord> = profile
+            .reader(Box::new(Cursor::new(STATEMENT)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].tx_type, TransactionType::Deposit);
+        assert_eq!(records[0].amount, 150025);
+        assert_eq!(records[0].timestamp, 1706783400);
+        assert_eq!(records[0].status, Status::Success);
+        assert_eq!(records[0].description, "Зарплата; январь");
+
+        assert_eq!(records[1].tx_type, TransactionType::Withdrawal);
+        assert_eq!(records[1].amount, -9990);
+        assert_eq!(records[1].status, Status::Pending);
+        assert_eq!(records[1].description, "");
+    }
+
+    #[test]
+    fn test_profile_skips_blank_lines() {
+        let profile = ImportProfile::from_toml_str(DEBIT_CREDIT_PROFILE).unwrap();
+        let input = format!("{}\n  \r\n", STATEMENT.replace("\n2;", "\n\n2;"));
+        let records: Vec<YPBankRecord> = profile
+            .reader(Box::new(Cursor::new(input)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].tx_id, 2);
+    }
+
+    #[test]
+    fn test_profile_through_registry() {
+        let mut registry = FormatRegistry::default();
+        registry.register(ImportProfile::from_toml_str(DEBIT_CREDIT_PROFILE).unwrap());
+
+        let records: Result<Vec<_>, _> = registry
+            .reader(Cursor::new(STATEMENT), "partner_bank")
+            .unwrap()
+            .collect();
+        assert_eq!(records.unwrap().len(), 2);
+        assert!(!registry.get("partner_bank").unwrap().capabilities().write);
+    }
+
+    #[test]
+    fn test_profile_missing_column() {
+        let profile = ImportProfile::from_toml_str(DEBIT_CREDIT_PROFILE).unwrap();
+        let input = "title\nНомер;Дата\n1;01.02.2024 10:30\n";
+        let error = profile
+            .reader(Box::new(Cursor::new(input)))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.code(), "wrong_csv_header");
+    }
+
+    #[test]
+    fn test_profile_invalid_value_location() {
+        let profile = ImportProfile::from_toml_str(DEBIT_CREDIT_PROFILE).unwrap();
+        let input = "title\n\
+            Номер;Дата;Операция;Счёт;Расход;Приход;Статус;Назначение\n\
+            1;31.13.2024 10:30;Пополнение;42;;1;;\n";
+        let error = profile
+            .reader(Box::new(Cursor::new(input)))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.code(), "invalid_field");
+        assert_eq!(error.location().unwrap().line, Some(3));
+        assert_eq!(error.location().unwrap().column, Some(3));
+    }
+
+    #[test]
+    fn test_profile_debit_credit_overflow() {
+        let profile = ImportProfile::from_toml_str(DEBIT_CREDIT_PROFILE).unwrap();
+        let input = "title\n\
+            Номер;Дата;Операция;Счёт;Расход;Приход;Статус;Назначение\n\
+            1;01.02.2024 10:30;Пополнение;42;-92233720368547758,07;0,01;;\n";
+        let error = profile
+            .reader(Box::new(Cursor::new(input)))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.code(), "invalid_field");
+        assert_eq!(error.location().unwrap().column, Some(56));
+        assert!(matches!(error.kind(), ParserError::InvalidField { field, .. } if field == "amount"));
+    }
+
+    #[test]
+    fn test_profile_read_error_location() {
+        let profile = ImportProfile::from_toml_str(DEBIT_CREDIT_PROFILE).unwrap();
+        let mut input = "title\n\
+            Номер;Дата;Операция;Счёт;Расход;Приход;Статус;Назначение\n"
+            .as_bytes()
+            .to_vec();
+        input.extend_from_slice(b"1;01.02.2024 10:30;\xff;42;;1;;\n");
+        let error = profile
+            .reader(Box::new(Cursor::new(input)))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.code(), "io");
+        assert_eq!(error.location().unwrap().line, Some(3));
+        assert_eq!(error.location().unwrap().record_index, Some(0));
+    }
+
+    #[test]
+    fn test_profile_rejects_delimiter_equal_to_quote() {
+        let error = ImportProfile::from_toml_str("name = \"x\"\ndelimiter = '\"'\n").unwrap_err();
+        assert_eq!(error.code(), "invalid_profile");
+    }
+
+    #[test]
+    fn test_profile_rejects_unknown_keys() {
+        let error = ImportProfile::from_toml_str("name = \"x\"\ndelimeter = \";\"\n").unwrap_err();
+        assert_eq!(error.code(), "invalid_profile");
+    }
+
+    #[test]
+    fn test_parse_decimal_and_date() {
+        assert_eq!(parse_decimal("-12.3", '.', 2), Ok(-1230));
+        assert_eq!(parse_decimal("7", '.', 0), Ok(7));
+        assert!(parse_decimal("1.234", '.', 2).is_err());
+        assert!(parse_decimal("abc", '.', 2).is_err());
+
+        assert_eq!(parse_date("1970-01-01", "%Y-%m-%d"), Ok(0));
+        assert_eq!(parse_date("2000-03-01 12:00:05", "%Y-%m-%d %H:%M:%S"), Ok(951912005));
+        assert!(parse_date("2000/03/01", "%Y-%m-%d").is_err());
+    }
+
+    #[test]
+    fn test_parse_date_checks_month_length() {
+        assert_eq!(parse_date("29.02.2024", "%d.%m.%Y"), Ok(1709164800));
+        assert_eq!(parse_date("29.02.2000", "%d.%m.%Y"), Ok(951782400));
+        assert!(parse_date("29.02.2023", "%d.%m.%Y").is_err());
+        assert!(parse_date("29.02.2100", "%d.%m.%Y").is_err());
+        assert!(parse_date("31.02.2024", "%d.%m.%Y").is_err());
+        assert!(parse_date("31.04.2024", "%d.%m.%Y").is_err());
+        assert_eq!(parse_date("30.04.2024", "%d.%m.%Y"), Ok(1714435200));
+        assert_eq!(parse_date("31.12.2024", "%d.%m.%Y"), Ok(1735603200));
+
+        let profile = ImportProfile::from_toml_str(DEBIT_CREDIT_PROFILE).unwrap();
+        let input = "title\n\
+            Номер;Дата;Операция;Счёт;Расход;Приход;Статус;Назначение\n\
+            1;31.04.2024 10:30;Пополнение;42;;1;;\n";
+        let error = profile
+            .reader(Box::new(Cursor::new(input)))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.code(), "invalid_field");
+    }
+}
@@ -0,0 +1,468 @@
+use std::fmt;
+
+use crate::parsers::error::ParserError;
+use crate::parsers::types::YPBankRecord;
+
+/// Position of a record or field in its source.
+///
+/// Fields that do not apply to a format are left as None: CSV and TXT report
+/// lines and columns, BIN reports byte offsets, TXT additionally reports the
+/// index of the `#`-separated section.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// 1-based line number (text formats).
+    pub line: Option<u64>,
+    /// 1-based column, in characters, within the line (text formats).
+    pub column: Option<u64>,
+    /// Byte offset where the record starts (binary format).
+    pub byte_offset: Option<u64>,
+    /// 0-based index of the record in the source.
+    pub record_index: Option<u64>,
+    /// 0-based index of the section in the source (text format).
+    pub section: Option<u64>,
+}
+
+impl SourceLocation {
+    /// Fills the fields that are not set in this location from another one.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - Location providing the missing fields
+    pub fn or(self, other: SourceLocation) -> SourceLocation {
+        SourceLocation {
+            line: self.line.or(other.line),
+            column: self.column.or(other.column),
+            byte_offset: self.byte_offset.or(other.byte_offset),
+            record_index: self.record_index.or(other.record_index),
+            section: self.section.or(other.section),
+        }
+    }
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(line) = self.line {
+            parts.push(format!("line {}", line));
+        }
+        if let Some(column) = self.column {
+            parts.push(format!("column {}", column));
+        }
+        if let Some(byte_offset) = self.byte_offset {
+            parts.push(format!("byte {}", byte_offset));
+        }
+        if let Some(record_index) = self.record_index {
+            parts.push(format!("record {}", record_index));
+        }
+        if let Some(section) = self.section {
+            parts.push(format!("section {}", section));
+        }
+
+        if parts.is_empty() {
+            write!(f, "unknown location")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+/// A stream of parsed records that can describe the item it returned last.
+///
+/// Implemented by the built-in per-format readers; the extra information is
+/// what allows lenient parsing to report and skip malformed records.
+pub trait RecordSource: Iterator<Item = Result<YPBankRecord, ParserError>> {
+    /// Location of the item most recently returned by `next`.
+    fn location(&self) -> SourceLocation {
+        SourceLocation::default()
+    }
+
+    /// Raw input of the item most recently returned by `next`.
+    fn raw(&self) -> &[u8] {
+        &[]
+    }
+
+    /// Whether reading can continue after the error most recently returned by `next`.
+    ///
+    /// Record-level errors (a malformed line or section) are resumable, while
+    /// errors that break the framing of the input are not.
+    fn can_resume(&self) -> bool {
+        false
+    }
+
+    /// Located, non-fatal problems found in the input read so far.
+    fn warnings(&self) -> &[ParserError] {
+        &[]
+    }
+}
+
+/// Adapter turning a plain iterator into a RecordSource without location information.
+struct IterSource<I> {
+    records: I,
+}
+
+impl<I: Iterator<Item = Result<YPBankRecord, ParserError>>> Iterator for IterSource<I> {
+    type Item = Result<YPBankRecord, ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.records.next()
+    }
+}
+
+impl<I: Iterator<Item = Result<YPBankRecord, ParserError>>> RecordSource for IterSource<I> {}
+
+/// Format-independent streaming reader over YPBank records.
+///
+/// Wraps any of the per-format readers (`CsvRecordReader`, `TxtRecordReader`,
+/// `BinRecordReader`) and yields records one at a time, so that arbitrarily
+/// large inputs can be processed in constant memory.
+pub struct RecordReader<'a> {
+    inner: Box<dyn RecordSource + 'a>,
+}
+
+impl<'a> RecordReader<'a> {
+    /// Creates a new record reader from any iterator of parsed records.
+    ///
+    /// Errors from such a reader are never resumable; use `from_source` to
+    /// provide locations and support lenient parsing.
+    ///
+    /// # Arguments
+    ///
+    /// * `records` - An iterator yielding `Result<YPBankRecord, ParserError>`
+    pub fn new<I>(records: I) -> Self
+    where
+        I: Iterator<Item = Result<YPBankRecord, ParserError>> + 'a,
+    {
+        Self::from_source(IterSource { records })
+    }
+
+    /// Creates a new record reader from a RecordSource.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - A record source such as `CsvRecordReader`
+    pub fn from_source<S: RecordSource + 'a>(source: S) -> Self {
+        RecordReader {
+            inner: Box::new(source),
+        }
+    }
+
+    /// Location of the item most recently returned by `next`.
+    pub fn location(&self) -> SourceLocation {
+        self.inner.location()
+    }
+
+    /// Raw input of the item most recently returned by `next`.
+    pub fn raw(&self) -> &[u8] {
+        self.inner.raw()
+    }
+
+    /// Whether reading can continue after the error most recently returned by `next`.
+    pub fn can_resume(&self) -> bool {
+        self.inner.can_resume()
+    }
+
+    /// Located, non-fatal problems found in the input read so far.
+    pub fn warnings(&self) -> &[ParserError] {
+        self.inner.warnings()
+    }
+
+    /// Turns this reader into a lenient one that skips malformed records.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_errors` - Maximum number of rejected records tolerated
+    ///
+    /// # Returns
+    ///
+    /// Returns a Lenient iterator yielding only valid records.
+    pub fn lenient(self, max_errors: usize) -> Lenient<'a> {
+        Lenient {
+            reader: self,
+            max_errors,
+            rejects: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Reads all records, collecting malformed records instead of failing.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_errors` - Maximum number of rejected records tolerated
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing the valid records and the rejected ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::ErrorBudgetExceeded if more than `max_errors` records
+    /// are rejected, or the error that made the input unreadable.
+    pub fn collect_lenient(self, max_errors: usize) -> Result<LenientParse, ParserError> {
+        let mut lenient = self.lenient(max_errors);
+        let records = lenient.by_ref().collect::<Result<Vec<_>, _>>()?;
+        Ok(LenientParse {
+            records,
+            rejects: lenient.into_rejects(),
+        })
+    }
+}
+
+impl Iterator for RecordReader<'_> {
+    type Item = Result<YPBankRecord, ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+/// A record that could not be parsed in lenient mode.
+#[derive(Debug)]
+pub struct RejectedRecord {
+    /// Why the record was rejected.
+    pub error: ParserError,
+    /// Where the record starts in the source.
+    pub location: SourceLocation,
+    /// Raw input of the record, suitable for quarantining.
+    pub raw: Vec<u8>,
+}
+
+impl RejectedRecord {
+    /// Returns the raw input as text, replacing invalid UTF-8 sequences.
+    pub fn raw_text(&self) -> String {
+        String::from_utf8_lossy(&self.raw).into_owned()
+    }
+}
+
+/// Outcome of lenient parsing: valid records plus rejected ones.
+#[derive(Debug)]
+pub struct LenientParse {
+    /// Records parsed successfully, in source order.
+    pub records: Vec<YPBankRecord>,
+    /// Records that were skipped, in source order.
+    pub rejects: Vec<RejectedRecord>,
+}
+
+/// Iterator over the valid records of a RecordReader, skipping malformed ones.
+///
+/// Yields an error only when the input cannot be read any further or when
+/// the error budget is exhausted; both end the iteration.
+pub struct Lenient<'a> {
+    reader: RecordReader<'a>,
+    max_errors: usize,
+    rejects: Vec<RejectedRecord>,
+    done: bool,
+}
+
+impl Lenient<'_> {
+    /// Records rejected so far.
+    pub fn rejects(&self) -> &[RejectedRecord] {
+        &self.rejects
+    }
+
+    /// Consumes the iterator, returning the records rejected so far.
+    pub fn into_rejects(self) -> Vec<RejectedRecord> {
+        self.rejects
+    }
+}
+
+impl Iterator for Lenient<'_> {
+    type Item = Result<YPBankRecord, ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        loop {
+            match self.reader.next() {
+                Some(Ok(record)) => return Some(Ok(record)),
+                None => {
+                    self.done = true;
+                    return None;
+                }
+                Some(Err(error)) if self.reader.can_resume() => {
+                    self.rejects.push(RejectedRecord {
+                        error,
+                        location: self.reader.location(),
+                        raw: self.reader.raw().to_vec(),
+                    });
+                    if self.rejects.len() > self.max_errors {
+                        self.done = true;
+                        return Some(Err(ParserError::ErrorBudgetExceeded(self.max_errors)));
+                    }
+                }
+                Some(Err(error)) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::parser::Parser;
+    use crate::test_helpers::create_test_records;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_lenient_csv_skips_malformed_lines() {
+        let csv_data = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n\
+            1,Deposit,2,3,100,1640995200,Success,First\n\
+            2,Deposit,2,3,abc,1640995200,Success,Bad amount\n\
+            3,Deposit,2,3,300,1640995200,Success,Third\n\
+            4,Deposit,2\n";
+
+        let result = Parser::from_read_lenient(Cursor::new(csv_data), "csv", 10).unwrap();
+
+        assert_eq!(result.records.iter().map(|r| r.tx_id).collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(result.rejects.len(), 2);
+        assert_eq!(result.rejects[0].location.line, Some(3));
+        assert_eq!(result.rejects[0].location.record_index, Some(1));
+        assert_eq!(result.rejects[0].raw_text(), "2,Deposit,2,3,abc,1640995200,Success,Bad amount");
+        assert_eq!(result.rejects[1].location.line, Some(5));
+    }
+
+    #[test]
+    fn test_lenient_txt_skips_bad_sections() {
+        let records = create_test_records(3, 50);
+        let mut output = Vec::new();
+        Parser::write_to(&mut output, &records, "txt").unwrap();
+        let txt = String::from_utf8(output).unwrap().replacen("amount:", "ammount:", 1);
+
+        let result = Parser::from_read_lenient(Cursor::new(txt), "txt", 1).unwrap();
+
+        assert_eq!(result.records, records[1..]);
+        assert_eq!(result.rejects.len(), 1);
+        assert!(matches!(result.rejects[0].error.kind(), ParserError::UnknownKey(_)));
+        assert_eq!(result.rejects[0].location.line, Some(2));
+        assert!(result.rejects[0].raw_text().contains("ammount:"));
+    }
+
+    #[test]
+    fn test_lenient_bin_skips_bad_body() {
+        let records = create_test_records(3, 60);
+        let mut output = Vec::new();
+        Parser::write_to(&mut output, &records, "bin").unwrap();
+
+        // Corrupt the TX_TYPE byte of the second record
+        let first_frame_len = crate::HEADER_SIZE + 46 + records[0].description.len();
+        output[first_frame_len + crate::HEADER_SIZE + 8] = 9;
+
+        let result = Parser::from_read_lenient(Cursor::new(output), "bin", 1).unwrap();
+
+        assert_eq!(result.records, vec![records[0].clone(), records[2].clone()]);
+        assert!(matches!(result.rejects[0].error.kind(), ParserError::WrongTransactionType(9)));
+        assert_eq!(result.rejects[0].location.byte_offset, Some(first_frame_len as u64));
+        assert_eq!(result.rejects[0].location.record_index, Some(1));
+        assert_eq!(result.rejects[0].raw.len(), crate::HEADER_SIZE + 46 + records[1].description.len());
+    }
+
+    #[test]
+    fn test_lenient_error_budget() {
+        let csv_data = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\nbad\nworse\n";
+
+        let result = Parser::from_read_lenient(Cursor::new(csv_data), "csv", 1);
+        assert!(matches!(result, Err(ParserError::ErrorBudgetExceeded(1))));
+    }
+
+    #[test]
+    fn test_lenient_fatal_error() {
+        let error = Parser::from_read_lenient(Cursor::new("WRONG,HEADER\n"), "csv", 10).unwrap_err();
+        assert!(matches!(error.kind(), ParserError::WrongCsvHeader(_)));
+
+        let error = Parser::from_read_lenient(Cursor::new(vec![0u8; 16]), "bin", 10).unwrap_err();
+        assert!(matches!(error.kind(), ParserError::InvalidMagic(_)));
+    }
+
+    #[test]
+    fn test_csv_error_location() {
+        let csv_data = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n\
+            1,Deposit,2,3,100,1640995200,Success,First\n\
+            2,Deposit,2,3,abc,1640995200,Success,Bad amount\n";
+
+        let error = Parser::from_read(Cursor::new(csv_data), "csv").unwrap_err();
+        let location = error.location().unwrap();
+        assert_eq!(location.line, Some(3));
+        assert_eq!(location.column, Some(15));
+        assert_eq!(location.record_index, Some(1));
+        assert!(error.to_string().contains("line 3, column 15"));
+
+        let error = Parser::from_read(Cursor::new(""), "csv").unwrap_err();
+        assert_eq!(error.location().unwrap().line, Some(1));
+    }
+
+    #[test]
+    fn test_txt_error_location() {
+        let txt_data = "# Record 0 (Deposit)\ntx_id: 1\ntx_type: Deposit\nfrom_user_id: 2\n\
+            to_user_id: 3\namount: 100\ntimestamp: 1640995200\nstatus: Success\ndescription: First\n\
+            \n# Record 1 (Deposit)\ntx_id: 2\ntx_type: Unknown\n";
+
+        let error = Parser::from_read(Cursor::new(txt_data), "txt").unwrap_err();
+        let location = error.location().unwrap();
+        assert_eq!(location.line, Some(13));
+        assert_eq!(location.column, Some(10));
+        assert_eq!(location.section, Some(1));
+
+        let txt_data = "tx_id: 1\n\n# Record 1\ntx_id: 2\n";
+        let error = Parser::from_read(Cursor::new(txt_data), "txt").unwrap_err();
+        assert!(matches!(error.kind(), ParserError::MissingTransactionType));
+        assert_eq!(error.location().unwrap().line, Some(1));
+        assert_eq!(error.location().unwrap().section, Some(0));
+    }
+
+    const TXT_WITH_KEY_PROBLEMS: &str = "# Record 0 (Deposit)\ntx_id: 1\ntx_type: Deposit\n\
+        from_user_id: 2\nto_user_id: 3\namount: 100\n  ammount: 200\ntimestamp: 1640995200\n\
+        status: Success\ndescription: First\namount: 150\n";
+
+    #[test]
+    fn test_txt_strict_keys() {
+        let error = Parser::from_read(Cursor::new(TXT_WITH_KEY_PROBLEMS), "txt").unwrap_err();
+        assert!(matches!(error.kind(), ParserError::UnknownKey(key) if key == "ammount"));
+        let location = error.location().unwrap();
+        assert_eq!(location.line, Some(7));
+        assert_eq!(location.column, Some(3));
+        assert_eq!(location.section, Some(0));
+
+        let duplicate = TXT_WITH_KEY_PROBLEMS.replace("  ammount: 200\n", "");
+        let error = Parser::from_read(Cursor::new(duplicate), "txt").unwrap_err();
+        assert_eq!(error.code(), "duplicate_key");
+        assert_eq!(error.location().unwrap().line, Some(10));
+    }
+
+    #[test]
+    fn test_txt_lenient_keys_warn() {
+        let source = crate::parsers::txt_format::TxtRecordReader::with_strict_keys(
+            Cursor::new(TXT_WITH_KEY_PROBLEMS),
+            false,
+        );
+        let mut reader = RecordReader::from_source(source);
+        let record = reader.next().unwrap().unwrap();
+        assert_eq!(record.amount, 150);
+        assert!(reader.next().is_none());
+
+        let codes: Vec<_> = reader.warnings().iter().map(|w| w.code()).collect();
+        assert_eq!(codes, vec!["unknown_key", "duplicate_key"]);
+        assert_eq!(reader.warnings()[1].location().unwrap().line, Some(11));
+    }
+
+    #[test]
+    fn test_bin_error_location() {
+        let records = create_test_records(3, 80);
+        let mut output = Vec::new();
+        Parser::write_to(&mut output, &records, "bin").unwrap();
+
+        let first_frame_len = crate::HEADER_SIZE + 46 + records[0].description.len();
+        output[first_frame_len] = 0;
+
+        let error = Parser::from_read(Cursor::new(output), "bin").unwrap_err();
+        assert!(matches!(error.kind(), ParserError::InvalidMagic(_)));
+        let location = error.location().unwrap();
+        assert_eq!(location.byte_offset, Some(first_frame_len as u64));
+        assert_eq!(location.record_index, Some(1));
+        assert_eq!(location.line, None);
+    }
+}
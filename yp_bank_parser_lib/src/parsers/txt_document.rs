@@ -0,0 +1,220 @@
+use std::io::{BufRead, Write};
+
+use crate::parsers::error::ParserError;
+use crate::parsers::txt_format::{TxtRecordReader, write_fields};
+use crate::parsers::types::YPBankRecord;
+
+/// A record of a TXT document together with the lines written before it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TxtEntry {
+    /// Comment and blank lines preceding the record, written back verbatim.
+    ///
+    /// `None` means the entry was not read from a document; a
+    /// `# Record N (Type)` header is generated for it on write.
+    pub leading: Option<Vec<String>>,
+    /// The record itself.
+    pub record: YPBankRecord,
+}
+
+impl TxtEntry {
+    /// Creates an entry with a generated header.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - The record of the entry
+    pub fn new(record: YPBankRecord) -> Self {
+        TxtEntry { leading: None, record }
+    }
+}
+
+/// Editable model of a YPBank text file that keeps comments and layout.
+///
+/// Unlike `YPBankTxtParser::from_read`, which returns plain records, a
+/// document remembers the `#` lines and blank lines around each section, so
+/// a hand-annotated file can be read, edited and written back with its notes
+/// intact. Record fields are always written in canonical key order; blank
+/// lines between the keys of a section are not preserved.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TxtDocument {
+    /// Records in file order with their leading lines.
+    pub entries: Vec<TxtEntry>,
+    /// Comment and blank lines after the last record.
+    pub trailing: Vec<String>,
+}
+
+impl TxtDocument {
+    /// Reads a document from a text format reader.
+    ///
+    /// Sections are parsed with the same strict rules as `TxtRecordReader::new`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader implementing the BufRead trait
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing the TxtDocument, or ParserError on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first located ParserError of the underlying reader.
+    pub fn from_read<R: BufRead>(reader: R) -> Result<Self, ParserError> {
+        let mut records = TxtRecordReader::new(reader);
+        let mut entries = Vec::new();
+        while let Some(record) = records.next() {
+            entries.push(TxtEntry {
+                record: record?,
+                leading: Some(records.take_leading_lines()),
+            });
+        }
+        Ok(TxtDocument {
+            entries,
+            trailing: records.take_trailing_lines(),
+        })
+    }
+
+    /// Appends a record with a generated header.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - The record to append
+    pub fn push(&mut self, record: YPBankRecord) {
+        self.entries.push(TxtEntry::new(record));
+    }
+
+    /// Returns an iterator over the records of the document.
+    pub fn records(&self) -> impl Iterator<Item = &YPBankRecord> {
+        self.entries.iter().map(|entry| &entry.record)
+    }
+
+    /// Consumes the document, returning its records without comments.
+    pub fn into_records(self) -> Vec<YPBankRecord> {
+        self.entries.into_iter().map(|entry| entry.record).collect()
+    }
+
+    /// Writes the document in text format.
+    ///
+    /// Leading and trailing lines are written verbatim, so a document read
+    /// from a file and written unchanged reproduces the file, as long as its
+    /// sections use canonical key order.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - A writer implementing the Write trait
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result with () on success, or ParserError on failure.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<(), ParserError> {
+        for (index, entry) in self.entries.iter().enumerate() {
+            match &entry.leading {
+                Some(lines) => {
+                    for line in lines {
+                        writeln!(writer, "{}", line)?;
+                    }
+                }
+                None => {
+                    if index > 0 {
+                        writeln!(writer)?;
+                    }
+                    writeln!(writer, "# Record {} ({:?})", index, entry.record.tx_type)?;
+                }
+            }
+            write_fields(&mut writer, &entry.record)?;
+        }
+        for line in &self.trailing {
+            writeln!(writer, "{}", line)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl From<Vec<YPBankRecord>> for TxtDocument {
+    fn from(records: Vec<YPBankRecord>) -> Self {
+        TxtDocument {
+            entries: records.into_iter().map(TxtEntry::new).collect(),
+            trailing: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::txt_format::YPBankTxtParser;
+    use crate::test_helpers::create_test_record;
+    use std::io::Cursor;
+
+    const ANNOTATED: &str = "\
+# Monthly statement, checked by hand
+# Record 0 (Deposit)
+tx_id: 1
+tx_type: Deposit
+from_user_id: 0
+to_user_id: 7
+amount: 100
+timestamp: 1633036800000
+status: Success
+description: Salary
+
+# TODO: confirm with the bank
+
+# Record 1 (Withdrawal)
+tx_id: 2
+tx_type: Withdrawal
+from_user_id: 7
+to_user_id: 0
+amount: 40
+timestamp: 1633036900000
+status: Pending
+description: ATM
+
+# end of statement
+";
+
+    #[test]
+    fn test_document_roundtrip_preserves_comments() {
+        let document = TxtDocument::from_read(Cursor::new(ANNOTATED)).unwrap();
+        assert_eq!(document.entries.len(), 2);
+        assert_eq!(document.trailing, vec!["", "# end of statement"]);
+
+        let mut output = Vec::new();
+        document.write_to(&mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), ANNOTATED);
+    }
+
+    #[test]
+    fn test_document_edit_keeps_comments() {
+        let mut document = TxtDocument::from_read(Cursor::new(ANNOTATED)).unwrap();
+        document.entries[1].record.amount = 45;
+        document.push(create_test_record(3));
+
+        let mut output = Vec::new();
+        document.write_to(&mut output).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.contains("# TODO: confirm with the bank\n\n# Record 1 (Withdrawal)\n"));
+        assert!(text.contains("amount: 45\n"));
+        assert!(text.contains(&format!("\n# Record 2 ({:?})\n", create_test_record(3).tx_type)));
+
+        let records = YPBankTxtParser::from_read(Cursor::new(text)).unwrap();
+        assert_eq!(records, document.into_records());
+    }
+
+    #[test]
+    fn test_document_matches_writer_output() {
+        let records: Vec<YPBankRecord> = (0..3).map(create_test_record).collect();
+        let mut expected = Vec::new();
+        YPBankTxtParser::write_to(&mut expected, &records).unwrap();
+
+        let mut from_records = Vec::new();
+        TxtDocument::from(records.clone()).write_to(&mut from_records).unwrap();
+        assert_eq!(from_records, expected);
+
+        let document = TxtDocument::from_read(Cursor::new(&expected)).unwrap();
+        let mut reread = Vec::new();
+        document.write_to(&mut reread).unwrap();
+        assert_eq!(reread, expected);
+        assert_eq!(document.into_records(), records);
+    }
+}
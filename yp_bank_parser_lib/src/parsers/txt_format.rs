@@ -2,92 +2,430 @@ use std::collections::HashMap;
 use std::io::BufRead;
 use std::io::{Read, Write};
 
+use crate::parsers::detect::complete_lines;
 use crate::parsers::error::ParserError;
+use crate::parsers::format::{Format, FormatCapabilities};
+use crate::parsers::reader::{RecordReader, RecordSource, SourceLocation};
 use crate::parsers::types::{YPBankRecord, TransactionType, Status};
+use crate::parsers::writer::RecordWriter;
+
+/// Keys of a record section in YPBank text format.
+pub const TXT_KEYS: [&str; 8] = [
+    "tx_id",
+    "tx_type",
+    "from_user_id",
+    "to_user_id",
+    "amount",
+    "timestamp",
+    "status",
+    "description",
+];
 
 /// Parser for YPBank text format files.
 pub struct YPBankTxtParser;
 
-impl YPBankTxtParser {
-    /// Reads sections from a text reader, splitting on lines starting with '#'.
+/// Encodes a description as the value of its `key: value` line.
+///
+/// A description that would not survive being read back as a plain value,
+/// because it holds a line break, starts with a quote or has whitespace at
+/// either end (values are trimmed when read), is enclosed in quotes, with
+/// backslashes, quotes and line breaks inside escaped as `\\`, `\"`, `\n`
+/// and `\r`. Any other description is written as is.
+///
+/// # Arguments
+///
+/// * `description` - The description to encode
+///
+/// # Returns
+///
+/// Returns the value to write after `description: `.
+fn encode_description(description: &str) -> String {
+    let needs_quotes = description.contains(['\n', '\r'])
+        || description.starts_with('"')
+        || description.trim() != description;
+    if !needs_quotes {
+        return description.to_string();
+    }
+
+    let mut encoded = String::with_capacity(description.len() + 2);
+    encoded.push('"');
+    for ch in description.chars() {
+        match ch {
+            '\\' => encoded.push_str("\\\\"),
+            '"' => encoded.push_str("\\\""),
+            '\n' => encoded.push_str("\\n"),
+            '\r' => encoded.push_str("\\r"),
+            c => encoded.push(c),
+        }
+    }
+    encoded.push('"');
+    encoded
+}
+
+/// Reverses `encode_description`.
+///
+/// Only a quoted value exactly as `encode_description` writes it is unescaped;
+/// any other value, including every description written before quoting was
+/// introduced (such as `C:\new\table`), is read unchanged.
+///
+/// # Arguments
+///
+/// * `value` - The trimmed value of the `description` key
+///
+/// # Returns
+///
+/// Returns the original description.
+fn decode_description(value: &str) -> String {
+    let unescaped = value
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .and_then(unescape_quoted);
+    match unescaped {
+        Some(description) if encode_description(&description) == value => description,
+        _ => value.to_string(),
+    }
+}
+
+/// Unescapes the inside of a quoted description.
+///
+/// # Returns
+///
+/// Returns None if the text has an unknown escape sequence or an unescaped quote.
+fn unescape_quoted(text: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => unescaped.push(match chars.next()? {
+                '\\' => '\\',
+                '"' => '"',
+                'n' => '\n',
+                'r' => '\r',
+                _ => return None,
+            }),
+            '"' => return None,
+            c => unescaped.push(c),
+        }
+    }
+    Some(unescaped)
+}
+
+/// Streaming reader yielding YPBank records from a text source one section at a time.
+///
+/// Sections are separated by lines starting with '#'; empty lines are ignored.
+/// Keys may appear in any order. In strict mode (the default) a section with a
+/// duplicate or unknown key is rejected; otherwise such keys are reported as
+/// warnings, the last value of a duplicate key winning.
+pub struct TxtRecordReader<R> {
+    reader: R,
+    strict: bool,
+    warnings: Vec<ParserError>,
+    layout: Vec<String>,
+    leading: Vec<String>,
+    done: bool,
+    failed: bool,
+    line_number: u64,
+    record_index: u64,
+    location: SourceLocation,
+    raw: String,
+}
+
+impl<R: BufRead> TxtRecordReader<R> {
+    /// Creates a new text record reader over the given buffered reader.
     ///
     /// # Arguments
     ///
-    /// * `reader` - A reader implementing Read + BufRead traits
+    /// * `reader` - A reader implementing the BufRead trait
+    pub fn new(reader: R) -> Self {
+        Self::with_strict_keys(reader, true)
+    }
+
+    /// Creates a new text record reader, choosing how duplicate and unknown keys are handled.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A reader implementing the BufRead trait
+    /// * `strict` - Reject sections with duplicate or unknown keys instead of
+    ///   collecting warnings
+    pub fn with_strict_keys(reader: R, strict: bool) -> Self {
+        TxtRecordReader {
+            reader,
+            strict,
+            warnings: Vec::new(),
+            layout: Vec::new(),
+            leading: Vec::new(),
+            done: false,
+            failed: false,
+            line_number: 0,
+            record_index: 0,
+            location: SourceLocation::default(),
+            raw: String::new(),
+        }
+    }
+
+    /// Reads the next section, splitting on lines starting with '#'.
+    ///
+    /// Updates the location to the first line of the section. Comment and blank
+    /// lines preceding the section are kept as its leading lines; blank lines
+    /// inside a section are dropped.
     ///
     /// # Returns
     ///
-    /// Returns a Result containing a Vec of sections (each section is a Vec<String>).
-    fn read_sections<R: Read + BufRead>(reader: R) -> Result<Vec<Vec<String>>, ParserError> {
-        let mut sections = Vec::new();
-        let mut current_section = Vec::new();
+    /// Returns the numbered lines of the next non-empty section, or None at end of input.
+    fn read_section(&mut self) -> Result<Option<Vec<(u64, String)>>, ParserError> {
+        let mut section = Vec::new();
 
-        for line_result in reader.lines() {
-            let line = line_result.map_err(|e| ParserError::ParseError(e.to_string()))?;
+        loop {
+            let mut line = String::new();
+            let read = self.reader.read_line(&mut line);
+            if let Ok(0) = read {
+                break;
+            }
+            self.line_number += 1;
+            read?;
+            if line.ends_with('\n') {
+                line.pop();
+                if line.ends_with('\r') {
+                    line.pop();
+                }
+            }
 
-            // Check if this line starts with "#"
+            // A delimiter line closes the current section, if it has content
             if line.starts_with('#') {
-                // If we have accumulated lines in the current section, save it
-                if !current_section.is_empty() {
-                    sections.push(current_section);
-                    current_section = Vec::new();
+                self.layout.push(line);
+                if !section.is_empty() {
+                    return Ok(Some(section));
                 }
-                // Skip the delimiter line itself - don't add it to any section
                 continue;
             }
 
-            // Skip empty lines if desired, or keep them
-            if !line.trim().is_empty() {
-                current_section.push(line);
+            if line.trim().is_empty() {
+                self.layout.push(line);
+            } else {
+                if section.is_empty() {
+                    self.leading = std::mem::take(&mut self.layout);
+                    self.location = SourceLocation {
+                        line: Some(self.line_number),
+                        record_index: Some(self.record_index),
+                        section: Some(self.record_index),
+                        ..SourceLocation::default()
+                    };
+                } else {
+                    // Blank lines followed by more keys are inside the section
+                    self.layout.clear();
+                }
+                section.push((self.line_number, line));
             }
         }
 
-        // Don't forget to add the last section if it has content
-        if !current_section.is_empty() {
-            sections.push(current_section);
+        self.done = true;
+        if section.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(section))
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for TxtRecordReader<R> {
+    type Item = Result<YPBankRecord, ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.read_section() {
+            Ok(Some(section)) => {
+                self.record_index += 1;
+                self.raw = section
+                    .iter()
+                    .map(|(_, line)| line.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let location = self.location;
+                let mut warnings = Vec::new();
+                let record = YPBankTxtParser::parse_section(section, self.strict, &mut warnings)
+                    .and_then(|dict| YPBankTxtParser::record_from_dict(&dict))
+                    .map_err(|e| e.at(location));
+                self.warnings
+                    .extend(warnings.into_iter().map(|w| w.at(location)));
+                Some(record)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                self.done = true;
+                self.failed = true;
+                self.location = SourceLocation {
+                    line: Some(self.line_number),
+                    ..SourceLocation::default()
+                };
+                Some(Err(e.at(self.location)))
+            }
         }
+    }
+}
+
+impl<R: BufRead> RecordSource for TxtRecordReader<R> {
+    fn location(&self) -> SourceLocation {
+        self.location
+    }
+
+    fn raw(&self) -> &[u8] {
+        self.raw.as_bytes()
+    }
+
+    fn can_resume(&self) -> bool {
+        !self.failed
+    }
+
+    fn warnings(&self) -> &[ParserError] {
+        &self.warnings
+    }
+}
+
+impl<R> TxtRecordReader<R> {
+    /// Returns the underlying reader.
+    #[cfg(feature = "async")]
+    pub(crate) fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
 
-        Ok(sections)
+    /// Takes the comment and blank lines preceding the section most recently returned.
+    pub(crate) fn take_leading_lines(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.leading)
     }
 
-    /// Parses sections into HashMaps of key-value pairs.
+    /// Takes the comment and blank lines read after the last section.
+    pub(crate) fn take_trailing_lines(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.layout)
+    }
+}
+
+/// Value of a `key: value` line together with its position.
+struct TxtValue {
+    value: String,
+    location: SourceLocation,
+}
+
+impl YPBankTxtParser {
+    /// Parses a section into a HashMap of key-value pairs.
     ///
     /// # Arguments
     ///
-    /// * `sections` - Vector of sections, each containing lines of text
+    /// * `section` - Numbered lines of text belonging to one record
+    /// * `strict` - Whether duplicate and unknown keys are errors rather than warnings
+    /// * `warnings` - Receives the located warnings in non-strict mode
     ///
     /// # Returns
     ///
-    /// Returns a Result containing a Vec of HashMaps with parsed key-value pairs.
-    fn parse_sections(
-        sections: Vec<Vec<String>>,
-    ) -> Result<Vec<HashMap<String, String>>, ParserError> {
-        sections
-            .into_iter()
-            .map(|section| {
-                let mut dict = HashMap::new();
-                for line in section {
-                    let parts: Vec<&str> = line.splitn(2, ':').collect();
-                    if parts.len() == 2 {
-                        dict.insert(
-                            parts[0].trim().to_lowercase().to_string(),
-                            parts[1].trim().to_string(),
-                        );
-                    } else {
-                        return Err(ParserError::ParseError(format!(
-                            "Invalid line format: {}",
-                            line
-                        )));
+    /// Returns a Result containing a HashMap with parsed key-value pairs,
+    /// each value carrying the line and column where it starts.
+    ///
+    /// # Errors
+    ///
+    /// Returns ParserError::InvalidLine for a line without ':', and in strict mode
+    /// ParserError::DuplicateKey or ParserError::UnknownKey located at the key.
+    fn parse_section(
+        section: Vec<(u64, String)>,
+        strict: bool,
+        warnings: &mut Vec<ParserError>,
+    ) -> Result<HashMap<String, TxtValue>, ParserError> {
+        let mut dict = HashMap::new();
+        for (line_number, line) in section {
+            let parts: Vec<&str> = line.splitn(2, ':').collect();
+            if parts.len() == 2 {
+                let key = parts[0].trim().to_lowercase();
+                let key_location = SourceLocation {
+                    line: Some(line_number),
+                    column: Some((parts[0].len() - parts[0].trim_start().len()) as u64 + 1),
+                    ..SourceLocation::default()
+                };
+                let problem = if !TXT_KEYS.contains(&key.as_str()) {
+                    Some(ParserError::UnknownKey(key.clone()))
+                } else if dict.contains_key(&key) {
+                    Some(ParserError::DuplicateKey(key.clone()))
+                } else {
+                    None
+                };
+                if let Some(problem) = problem {
+                    if strict {
+                        return Err(problem.at(key_location));
                     }
+                    warnings.push(problem.at(key_location));
                 }
-                Ok(dict)
+
+                let leading = parts[1].len() - parts[1].trim_start().len();
+                let column = parts[0].chars().count() + 1 + parts[1][..leading].chars().count() + 1;
+                dict.insert(
+                    key,
+                    TxtValue {
+                        value: parts[1].trim().to_string(),
+                        location: SourceLocation {
+                            line: Some(line_number),
+                            column: Some(column as u64),
+                            ..SourceLocation::default()
+                        },
+                    },
+                );
+            } else {
+                return Err(ParserError::InvalidLine(line).at(SourceLocation {
+                    line: Some(line_number),
+                    ..SourceLocation::default()
+                }));
+            }
+        }
+        Ok(dict)
+    }
+
+    /// Builds a YPBankRecord from parsed key-value pairs.
+    ///
+    /// # Arguments
+    ///
+    /// * `d` - HashMap of key-value pairs of a single section
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result containing the YPBankRecord, or ParserError for missing or invalid fields.
+    fn record_from_dict(d: &HashMap<String, TxtValue>) -> Result<YPBankRecord, ParserError> {
+        fn parse_helper<T>(d: &HashMap<String, TxtValue>, key: &str, error: ParserError) -> Result<T, ParserError>
+        where
+            T: std::str::FromStr,
+            T::Err: std::fmt::Display,
+        {
+            let tx_value = d.get(key).ok_or(error)?;
+            let tx_str = &tx_value.value;
+            tx_str.parse::<T>().map_err(|e| {
+                ParserError::InvalidField {
+                    field: key.to_string(),
+                    value: tx_str.to_string(),
+                    reason: e.to_string(),
+                }
+                .at(tx_value.location)
             })
-            .collect()
+        }
+
+        Ok(YPBankRecord {
+            tx_id: parse_helper::<u64>(d, "tx_id", ParserError::MissingTxId)?,
+            tx_type: parse_helper::<TransactionType>(d, "tx_type", ParserError::MissingTransactionType)?,
+            from_user_id: parse_helper::<u64>(d, "from_user_id", ParserError::MissingFromUserId)?,
+            to_user_id: parse_helper::<u64>(d, "to_user_id", ParserError::MissingToUserId)?,
+            amount: parse_helper::<i64>(d, "amount", ParserError::MissingAmount)?,
+            timestamp: parse_helper::<u64>(d, "timestamp", ParserError::MissingTimestamp)?,
+            status: parse_helper::<Status>(d, "status", ParserError::MissingStatus)?,
+            description: decode_description(&parse_helper::<String>(
+                d,
+                "description",
+                ParserError::MissingDescription,
+            )?),
+        })
     }
 
     /// Reads YPBank records from a text format reader.
     ///
     /// Parses text format with key-value pairs separated by colons.
+    /// Collects the output of a `TxtRecordReader`; use the reader directly
+    /// to process large inputs in constant memory.
     ///
     /// # Arguments
     ///
@@ -101,55 +439,13 @@ impl YPBankTxtParser {
     ///
     /// Returns ParserError variants for parsing failures or missing required fields.
     pub fn from_read<R: Read + BufRead>(reader: R) -> Result<Vec<YPBankRecord>, ParserError> {
-        let sections = Self::read_sections(reader)?;
-
-        let dict = Self::parse_sections(sections)?;
-
-        fn parse_helper<T>(d: &HashMap<String, String>, key: &str, error: ParserError) -> Result<T, ParserError>
-        where
-            T: std::str::FromStr,
-            T::Err: std::fmt::Display,
-        {
-            let tx_str = d.get(key).ok_or(error)?;
-            tx_str.parse::<T>().map_err(|e| {
-                ParserError::ParseError(format!(
-                    "Failed to parse {}: {} error: {}",
-                    key,
-                    tx_str,
-                    e
-                ))
-            })
-        }
-
-        let records = dict
-            .into_iter()
-            .map(|d| {
-                Ok(YPBankRecord {
-                    tx_id: parse_helper::<u64>(&d, "tx_id", ParserError::MissingTxId)?,                     
-                                           
-                    tx_type: parse_helper::<TransactionType>(&d, "tx_type", ParserError::MissingTransactionType)?,                         
-                                           
-                    from_user_id: parse_helper::<u64>(&d, "from_user_id", ParserError::MissingFromUserId)?,
-                        
-                    to_user_id: parse_helper::<u64>(&d, "to_user_id", ParserError::MissingToUserId)?,
-                       
-                    amount: parse_helper::<i64>(&d, "amount", ParserError::MissingAmount)?,
-                                                              
-                    timestamp: parse_helper::<u64>(&d, "timestamp", ParserError::MissingTimestamp)?,
-                                            
-                    status: parse_helper::<Status>(&d, "status", ParserError::MissingStatus)?,
-                                            
-                    description: parse_helper::<String>(&d, "description", ParserError::MissingDescription)?,                        
-                })
-            })
-            .collect::<Result<Vec<_>, ParserError>>()?;
-
-        Ok(records)
+        TxtRecordReader::new(reader).collect()
     }
 
     /// Writes YPBank records to a writer in text format.
     ///
-    /// Each record is written with key-value pairs and separated by comment headers.
+    /// Each record is written with key-value pairs and separated by comment headers,
+    /// using a `TxtRecordWriter`.
     ///
     /// # Arguments
     ///
@@ -159,21 +455,135 @@ impl YPBankTxtParser {
     /// # Returns
     ///
     /// Returns a Result with () on success, or ParserError on failure.
-    pub fn write_to<W: Write>(mut writer: W, records: &[YPBankRecord]) -> Result<(), ParserError> {
-        for record in records.iter().enumerate() {
-            writeln!(writer, "# Record {} ({:?})", record.0, record.1.tx_type)?;
-            writeln!(writer, "tx_id: {}", record.1.tx_id)?;
-            writeln!(writer, "tx_type: {:?}", record.1.tx_type)?;
-            writeln!(writer, "from_user_id: {}", record.1.from_user_id)?;
-            writeln!(writer, "to_user_id: {}", record.1.to_user_id)?;
-            writeln!(writer, "amount: {}", record.1.amount)?;
-            writeln!(writer, "timestamp: {}", record.1.timestamp)?;
-            writeln!(writer, "status: {:?}", record.1.status)?;
-            writeln!(writer, "description: {}", record.1.description)?;
-            if record.0 < records.len() - 1 {
-                writeln!(writer)?;
+    pub fn write_to<W: Write>(writer: W, records: &[YPBankRecord]) -> Result<(), ParserError> {
+        let mut record_writer = TxtRecordWriter::new(writer);
+        for record in records {
+            record_writer.write_record(record)?;
+        }
+        record_writer.finish()
+    }
+}
+
+impl Format for YPBankTxtParser {
+    fn name(&self) -> &str {
+        "txt"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["txt"]
+    }
+
+    fn reader<'a>(&self, input: Box<dyn BufRead + 'a>) -> Result<RecordReader<'a>, ParserError> {
+        Ok(RecordReader::from_source(TxtRecordReader::new(input)))
+    }
+
+    fn writer<'a>(
+        &self,
+        output: Box<dyn Write + 'a>,
+    ) -> Result<Box<dyn RecordWriter + 'a>, ParserError> {
+        Ok(Box::new(TxtRecordWriter::new(output)))
+    }
+
+    fn capabilities(&self) -> FormatCapabilities {
+        FormatCapabilities {
+            read: true,
+            write: true,
+            binary: false,
+        }
+    }
+
+    /// Recognises the input by `key: value` lines with known keys and `#` separators.
+    ///
+    /// The confidence is proportional to the share of non-empty lines that
+    /// match, and is zero if no known key is present at all.
+    fn sniff(&self, head: &[u8]) -> f32 {
+        let mut total = 0;
+        let mut separators = 0;
+        let mut known_keys = 0;
+
+        for line in complete_lines(head) {
+            if line.trim().is_empty() {
+                continue;
             }
+            total += 1;
+
+            if line.starts_with('#') {
+                separators += 1;
+            } else if let Some((key, _)) = line.split_once(':')
+                && TXT_KEYS.contains(&key.trim().to_lowercase().as_str())
+            {
+                known_keys += 1;
+            }
+        }
+
+        if known_keys == 0 {
+            return 0.0;
+        }
+        0.9 * (known_keys + separators) as f32 / total as f32
+    }
+}
+
+/// Writes the `key: value` lines of a record, without a section header.
+///
+/// # Arguments
+///
+/// * `writer` - A writer implementing the Write trait
+/// * `record` - The record to write
+pub(crate) fn write_fields<W: Write>(writer: &mut W, record: &YPBankRecord) -> std::io::Result<()> {
+    writeln!(writer, "tx_id: {}", record.tx_id)?;
+    writeln!(writer, "tx_type: {:?}", record.tx_type)?;
+    writeln!(writer, "from_user_id: {}", record.from_user_id)?;
+    writeln!(writer, "to_user_id: {}", record.to_user_id)?;
+    writeln!(writer, "amount: {}", record.amount)?;
+    writeln!(writer, "timestamp: {}", record.timestamp)?;
+    writeln!(writer, "status: {:?}", record.status)?;
+    writeln!(writer, "description: {}", encode_description(&record.description))
+}
+
+/// Incremental writer emitting YPBank records in text format.
+///
+/// Each record is preceded by a `# Record N (Type)` comment header, numbered
+/// from 0, and records are separated by an empty line. Descriptions that
+/// need it are quoted and escaped so that any text, including line breaks, round-trips.
+pub struct TxtRecordWriter<W> {
+    writer: W,
+    records_written: usize,
+}
+
+impl<W: Write> TxtRecordWriter<W> {
+    /// Creates a new text record writer over the given writer.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - A writer implementing the Write trait
+    pub fn new(writer: W) -> Self {
+        TxtRecordWriter {
+            writer,
+            records_written: 0,
         }
+    }
+
+    /// Returns the underlying writer.
+    #[cfg(feature = "async")]
+    pub(crate) fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+}
+
+impl<W: Write> RecordWriter for TxtRecordWriter<W> {
+    fn write_record(&mut self, record: &YPBankRecord) -> Result<(), ParserError> {
+        let writer = &mut self.writer;
+        if self.records_written > 0 {
+            writeln!(writer)?;
+        }
+        writeln!(writer, "# Record {} ({:?})", self.records_written, record.tx_type)?;
+        write_fields(writer, record)?;
+        self.records_written += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), ParserError> {
+        self.writer.flush()?;
         Ok(())
     }
 }
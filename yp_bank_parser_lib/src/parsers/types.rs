@@ -3,7 +3,11 @@ use strum::{EnumString, Display};
 use crate::parsers::error::ParserError;
 
 /// Represents a YPBank transaction record.
+///
+/// With the `serde` feature enabled, fields are (de)serialized under the
+/// same names as the CSV header columns.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct YPBankRecord {
     pub tx_id: u64,
     pub tx_type: TransactionType,
@@ -15,8 +19,56 @@ pub struct YPBankRecord {
     pub description: String,
 }
 
+/// Borrowed view of a YPBank transaction record.
+///
+/// Produced by `BinSliceReader` without copying: the description points into
+/// the decoded buffer and was validated as UTF-8 in place.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct YPBankRecordRef<'a> {
+    pub tx_id: u64,
+    pub tx_type: TransactionType,
+    pub from_user_id: u64,
+    pub to_user_id: u64,
+    pub amount: i64,
+    pub timestamp: u64,
+    pub status: Status,
+    pub description: &'a str,
+}
+
+impl YPBankRecordRef<'_> {
+    /// Copies the view into an owned record.
+    ///
+    /// # Returns
+    ///
+    /// Returns a YPBankRecord with the same fields; only the description is allocated.
+    // Takes `&self` so that calls on `&YPBankRecordRef` resolve here rather than to `ToOwned`
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_owned(&self) -> YPBankRecord {
+        YPBankRecord {
+            tx_id: self.tx_id,
+            tx_type: self.tx_type,
+            from_user_id: self.from_user_id,
+            to_user_id: self.to_user_id,
+            amount: self.amount,
+            timestamp: self.timestamp,
+            status: self.status,
+            description: self.description.to_string(),
+        }
+    }
+}
+
+impl From<YPBankRecordRef<'_>> for YPBankRecord {
+    fn from(record: YPBankRecordRef<'_>) -> Self {
+        record.to_owned()
+    }
+}
+
 /// Transaction type enumeration.
+///
+/// With the `serde` feature enabled, variants are (de)serialized as spelled
+/// by the CSV and TXT writers (e.g. "Deposit").
 #[derive(Debug, Clone, Copy, PartialEq, EnumString, Display)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[strum(serialize_all = "UPPERCASE", ascii_case_insensitive)]
 pub enum TransactionType {
     Deposit = 0,
@@ -34,10 +86,7 @@ impl TryFrom<u8> for TransactionType {
             0 => Ok(TransactionType::Deposit),
             1 => Ok(TransactionType::Transfer),
             2 => Ok(TransactionType::Withdrawal),
-            _ => Err(ParserError::ParseError(format!(
-                "Wrong Transaction Type: {}",
-                value
-            ))),
+            _ => Err(ParserError::WrongTransactionType(value)),
         }
     }
 }
@@ -72,7 +121,11 @@ impl TransactionType {
 }
 
 /// Transaction status enumeration.
+///
+/// With the `serde` feature enabled, variants are (de)serialized as spelled
+/// by the CSV and TXT writers (e.g. "Success").
 #[derive(Debug, Clone, Copy, PartialEq, EnumString, Display)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[strum(serialize_all = "UPPERCASE", ascii_case_insensitive)]
 pub enum Status {
     Success = 0,
@@ -90,7 +143,7 @@ impl TryFrom<u8> for Status {
             0 => Ok(Status::Success),
             1 => Ok(Status::Failure),
             2 => Ok(Status::Pending),
-            _ => Err(ParserError::ParseError(format!("Wrong Status: {}", value))),
+            _ => Err(ParserError::WrongStatusType(value)),
         }
     }
 }
@@ -123,3 +176,40 @@ impl Status {
         self as u8
     }
 }
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{create_test_record, create_test_records};
+
+    #[test]
+    fn test_serde_field_and_variant_names() {
+        let record = YPBankRecord {
+            tx_type: TransactionType::Transfer,
+            status: Status::Pending,
+            ..create_test_record(1)
+        };
+        let value = serde_json::to_value(&record).unwrap();
+
+        assert_eq!(value["tx_id"], record.tx_id);
+        assert_eq!(value["tx_type"], "Transfer");
+        assert_eq!(value["status"], "Pending");
+        assert!(value.get("from_user_id").is_some());
+        assert!(value.get("description").is_some());
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let records = create_test_records(6, 42);
+        let json = serde_json::to_string(&records).unwrap();
+        let parsed: Vec<YPBankRecord> = serde_json::from_str(&json).unwrap();
+        assert_eq!(records, parsed);
+    }
+
+    #[test]
+    fn test_serde_rejects_unknown_variant() {
+        let json = r#"{"tx_id":1,"tx_type":"Refund","from_user_id":0,"to_user_id":1,
+            "amount":10,"timestamp":0,"status":"Success","description":""}"#;
+        assert!(serde_json::from_str::<YPBankRecord>(json).is_err());
+    }
+}
@@ -0,0 +1,27 @@
+use crate::parsers::error::ParserError;
+use crate::parsers::types::YPBankRecord;
+
+/// Incremental writer of YPBank records.
+///
+/// Records are written one at a time with `write_record`; `finish` must be
+/// called once after the last record to emit any trailing data and flush the
+/// underlying writer.
+pub trait RecordWriter {
+    /// Writes a single record.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - The YPBankRecord to write
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result with () on success, or ParserError on failure.
+    fn write_record(&mut self, record: &YPBankRecord) -> Result<(), ParserError>;
+
+    /// Completes the output and flushes the underlying writer.
+    ///
+    /// # Returns
+    ///
+    /// Returns a Result with () on success, or ParserError on failure.
+    fn finish(&mut self) -> Result<(), ParserError>;
+}